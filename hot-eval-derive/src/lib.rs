@@ -0,0 +1,220 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, FnArg, Ident, ItemFn, Lifetime, LitStr, ParenthesizedGenericArguments, ReturnType, Type, TypeBareFn, TypeReference, ext::IdentExt, parse_macro_input, spanned::Spanned, visit_mut::{self, VisitMut}};
+
+/// Implements `hot_eval::HotEvalInputs` for a struct with named fields. Every
+/// field becomes a variable with the same name, unless it's marked with
+/// `#[hot_eval(skip)]`, or renamed with `#[hot_eval(rename = "name")]`
+#[proc_macro_derive(HotEvalInputs, attributes(hot_eval))]
+pub fn derive_hot_eval_inputs(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_hot_eval_inputs(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand_hot_eval_inputs(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    // inputs are identified by their TypeId, which requires a 'static type.
+    // generic types are rejected too, since each instantiation would need its
+    // own table anyway
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new(input.generics.span(), "HotEvalInputs can't be derived for generic structs"));
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(syn::Error::new(input.ident.span(), "HotEvalInputs can only be derived for structs with named fields")),
+        },
+        _ => return Err(syn::Error::new(input.ident.span(), "HotEvalInputs can only be derived for structs")),
+    };
+
+    let mut registrations = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_ref().unwrap();
+        let mut name = ident.to_string();
+        let mut skip = false;
+
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("hot_eval")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("skip") {
+                    skip = true;
+                    Ok(())
+                } else if meta.path.is_ident("rename") {
+                    name = meta.value()?.parse::<LitStr>()?.value();
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown hot_eval attribute; expected `skip` or `rename`"))
+                }
+            })?;
+        }
+
+        if skip {
+            continue;
+        }
+
+        let ty = &field.ty;
+        registrations.push(quote! {
+            // SAFETY: the offset is taken from the field itself
+            unsafe { table.add_input_field::<#ty>(#name.into(), ::core::mem::offset_of!(Self, #ident))?; }
+        });
+    }
+
+    let ident = &input.ident;
+    Ok(quote! {
+        unsafe impl ::hot_eval::common::inputs::HotEvalInputs for #ident {
+            fn add_input_fields(table: &mut ::hot_eval::common::table::Table) -> ::core::result::Result<(), ::hot_eval::common::error::CommonError> {
+                #(#registrations)*
+                ::core::result::Result::Ok(())
+            }
+        }
+    })
+}
+
+/// Adds a `const` fn pointer to a function, named after the function in upper
+/// case (e.g. `GET_X` for `get_x`), for places that need a `fn` pointer type
+/// instead of the function's own type. The function itself is left as is, and
+/// can also be passed to `Table::add_function` directly. Functions that are
+/// already named in upper case don't get a const. Elided lifetimes in the fn
+/// pointer's type become `'static`, since fn pointers with higher-ranked
+/// lifetimes can't be bound
+#[proc_macro_attribute]
+pub fn function(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        return syn::Error::new(proc_macro2::TokenStream::from(attr).span(), "hot_eval::function doesn't take any arguments").to_compile_error().into();
+    }
+
+    let item = parse_macro_input!(item as ItemFn);
+    match expand_function(item) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+struct StaticLifetimes;
+
+impl VisitMut for StaticLifetimes {
+    fn visit_type_reference_mut(&mut self, reference: &mut TypeReference) {
+        if reference.lifetime.is_none() {
+            reference.lifetime = Some(Lifetime::new("'static", reference.and_token.span));
+        }
+
+        visit_mut::visit_type_reference_mut(self, reference);
+    }
+
+    fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+        if lifetime.ident == "_" {
+            *lifetime = Lifetime::new("'static", lifetime.apostrophe);
+        }
+    }
+
+    // fn pointer types and Fn traits have their own elided lifetimes, which
+    // are higher-ranked and don't need to be replaced
+    fn visit_type_bare_fn_mut(&mut self, _: &mut TypeBareFn) { }
+
+    fn visit_parenthesized_generic_arguments_mut(&mut self, _: &mut ParenthesizedGenericArguments) { }
+}
+
+fn expand_function(item: ItemFn) -> syn::Result<proc_macro2::TokenStream> {
+    let sig = &item.sig;
+    if !sig.generics.params.is_empty() {
+        return Err(syn::Error::new(sig.generics.span(), "hot_eval::function can't be used on generic functions"));
+    }
+
+    if let Some(asyncness) = &sig.asyncness {
+        return Err(syn::Error::new(asyncness.span, "hot_eval::function can't be used on async functions"));
+    }
+
+    if sig.unsafety.is_some() || sig.abi.is_some() {
+        return Err(syn::Error::new(sig.fn_token.span, "hot_eval::function can't be used on unsafe or extern functions"));
+    }
+
+    if let Some(variadic) = &sig.variadic {
+        return Err(syn::Error::new(variadic.span(), "hot_eval::function can't be used on variadic functions"));
+    }
+
+    let mut param_types = Vec::<Type>::new();
+    for input in &sig.inputs {
+        match input {
+            FnArg::Typed(pat_type) => param_types.push((*pat_type.ty).clone()),
+            FnArg::Receiver(receiver) => return Err(syn::Error::new(receiver.span(), "hot_eval::function can't be used on methods")),
+        }
+    }
+
+    let mut ret_type = match &sig.output {
+        ReturnType::Default => return Err(syn::Error::new(sig.span(), "hot_eval::function must return a value")),
+        ReturnType::Type(_, ty) => (**ty).clone(),
+    };
+
+    for ty in param_types.iter_mut().chain(std::iter::once(&mut ret_type)) {
+        StaticLifetimes.visit_type_mut(ty);
+    }
+
+    let vis = &item.vis;
+    let ident = &sig.ident;
+    let const_ident = Ident::new(&ident.unraw().to_string().to_uppercase(), ident.span());
+    if const_ident == *ident {
+        return Ok(quote! { #item });
+    }
+
+    let doc = format!("`{ident}` as a fn pointer, which can be passed to `Table::add_function`");
+
+    Ok(quote! {
+        #item
+
+        #[doc = #doc]
+        #vis const #const_ident: fn(#(#param_types),*) -> #ret_type = #ident;
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::{Item, ItemConst, parse_quote};
+
+    use super::*;
+
+    fn expand_const(item: ItemFn) -> (ItemFn, ItemConst) {
+        let file = syn::parse2::<syn::File>(expand_function(item).unwrap()).unwrap();
+        match <[Item; 2]>::try_from(file.items) {
+            Ok([Item::Fn(item_fn), Item::Const(item_const)]) => (item_fn, item_const),
+            _ => panic!("expected a fn and a const"),
+        }
+    }
+
+    fn type_string(item_const: &ItemConst) -> String {
+        let ty = &item_const.ty;
+        quote!(#ty).to_string()
+    }
+
+    #[test]
+    fn function_is_kept_alongside_the_const() {
+        let item: ItemFn = parse_quote! {
+            /// Docs
+            pub fn get_x(a: u32, b: &u32) -> u32 { a + *b }
+        };
+        let (item_fn, item_const) = expand_const(item.clone());
+
+        assert_eq!(quote!(#item_fn).to_string(), quote!(#item).to_string());
+        assert_eq!(item_const.ident, "GET_X");
+        assert!(matches!(item_const.vis, syn::Visibility::Public(_)));
+        assert_eq!(type_string(&item_const), "fn (u32 , & 'static u32) -> u32");
+    }
+
+    #[test]
+    fn function_replaces_every_elided_lifetime() {
+        let (_, item_const) = expand_const(parse_quote! {
+            fn f(a: &'_ u8, b: Wrapper<'_, &u16>, c: &mut [&u32], d: &'static u64, e: fn(&u8) -> &u8) -> Wrapper<'_, u8> { todo!() }
+        });
+
+        let expected: Type = parse_quote!(fn(&'static u8, Wrapper<'static, &'static u16>, &'static mut [&'static u32], &'static u64, fn(&u8) -> &u8) -> Wrapper<'static, u8>);
+        assert_eq!(type_string(&item_const), quote!(#expected).to_string());
+    }
+
+    #[test]
+    fn upper_case_functions_are_kept_without_a_const() {
+        let item: ItemFn = parse_quote! { fn GET_X() -> u32 { 0 } };
+        let tokens = expand_function(item.clone()).unwrap();
+        assert_eq!(tokens.to_string(), quote!(#item).to_string());
+    }
+}
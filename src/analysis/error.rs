@@ -1,16 +1,36 @@
 use std::{error::Error, fmt};
 
-use crate::common::value_type::ValueType;
+use crate::common::{binding::TypeConstraint, value_type::ValueType};
 
 #[derive(Debug)]
 pub enum AnalysisError {
     BadAnalysis,
     EmptyAST,
     InvalidTypeForOp { value_type: ValueType },
+    InvalidExponentType { base_type: ValueType, exponent_type: ValueType },
     UnknownBinding { name: String },
     BadBindingKind { name: String, is_var: bool },
+    NotASet { name: String },
+    BadSetUsage { name: String },
+    SetBadValueType { name: String, expected: ValueType, got: ValueType },
     BadArguments { name: String, expected_argc: usize, actual_argc: usize },
     UnknownHiddenState { idx: usize },
+    MissingReturnIndex { name: String, count: usize },
+    BadReturnIndex { name: String, idx: usize, count: usize },
+    BadVariadicArguments { name: String, min_argc: usize, actual_argc: usize },
+    NoOverloadForArgc { name: String, actual_argc: usize },
+    PositionalAfterNamedArgument { name: String },
+    UnknownNamedArgument { name: String, arg: String },
+    DuplicateArgument { name: String, param: String },
+    MissingArgument { name: String, param: String },
+    NoOverloadForNamedArguments { name: String },
+    AmbiguousNamedArguments { name: String },
+    /// None is an untyped argument
+    NoMatchingOverload { name: String, arg_types: Box<[Option<ValueType>]> },
+    AmbiguousOverload { name: String, candidates: Box<[Box<[ValueType]>]> },
+    GenericConstraintNotMet { name: String, value_type: ValueType, constraint: TypeConstraint },
+    GenericSpecFailed { name: String, msg: String },
+    GenericInstanceMismatch { name: String, expected: Box<[ValueType]>, got: Box<[ValueType]> },
 }
 
 impl fmt::Display for AnalysisError {
@@ -19,6 +39,7 @@ impl fmt::Display for AnalysisError {
             Self::BadAnalysis => write!(f, "Invalid AAST; maybe it was manually changed?"),
             Self::EmptyAST => write!(f, "AST is empty"),
             Self::InvalidTypeForOp { value_type } => write!(f, "Type {:?} is invalid for operation", value_type),
+            Self::InvalidExponentType { base_type, exponent_type } => write!(f, "Type {:?} is invalid as an exponent for a {:?} base", exponent_type, base_type),
             Self::UnknownBinding { name } => write!(f, "Unknown binding \"{name}\""),
             Self::BadBindingKind { name, is_var } => {
                 if *is_var {
@@ -27,8 +48,32 @@ impl fmt::Display for AnalysisError {
                     write!(f, "Binding \"{name}\" is of an unexpected kind; expected variable, got function")
                 }
             },
+            Self::NotASet { name } => write!(f, "Binding \"{name}\" is not a set, so it can't be used on the right side of \"in\""),
+            Self::BadSetUsage { name } => write!(f, "Set \"{name}\" can only be used on the right side of \"in\""),
+            Self::SetBadValueType { name, expected, got } => write!(f, "Set \"{name}\" has type {expected:?}, which a value with type {got:?} can't be implicitly cast to"),
             Self::BadArguments { name, expected_argc, actual_argc } => write!(f, "Function \"{name}\" expects {expected_argc} arguments, got {actual_argc} instead"),
             Self::UnknownHiddenState { idx } => write!(f, "Unknown hidden state {idx}"),
+            Self::MissingReturnIndex { name, count } => write!(f, "Function \"{name}\" returns {count} values; select one with \"{name}(...).N\""),
+            Self::BadReturnIndex { name, idx, count } => write!(f, "Function \"{name}\" returns {count} values, but value {idx} was selected"),
+            Self::BadVariadicArguments { name, min_argc, actual_argc } => write!(f, "Function \"{name}\" expects at least {min_argc} arguments, got {actual_argc} instead"),
+            Self::NoOverloadForArgc { name, actual_argc } => write!(f, "No overload of function \"{name}\" takes {actual_argc} arguments"),
+            Self::PositionalAfterNamedArgument { name } => write!(f, "Call to function \"{name}\" has a positional argument after a named one"),
+            Self::UnknownNamedArgument { name, arg } => write!(f, "Function \"{name}\" has no parameter named \"{arg}\""),
+            Self::DuplicateArgument { name, param } => write!(f, "Parameter \"{param}\" of function \"{name}\" was given more than once"),
+            Self::MissingArgument { name, param } => write!(f, "Missing argument for parameter \"{param}\" of function \"{name}\", which has no default value"),
+            Self::NoOverloadForNamedArguments { name } => write!(f, "No overload of function \"{name}\" accepts the given named arguments"),
+            Self::AmbiguousNamedArguments { name } => write!(f, "Call to function \"{name}\" is ambiguous; more than one overload accepts the arguments, but in a different order or with different defaults"),
+            Self::NoMatchingOverload { name, arg_types } => {
+                let arg_types = arg_types.iter().map(|t| t.map_or("untyped".to_string(), |t| format!("{t:?}"))).collect::<Vec<_>>().join(", ");
+                write!(f, "No overload of function \"{name}\" accepts arguments ({arg_types})")
+            },
+            Self::AmbiguousOverload { name, candidates } => {
+                let candidates = candidates.iter().map(|params| format!("{params:?}")).collect::<Vec<_>>().join(", ");
+                write!(f, "Call to function \"{name}\" is ambiguous; it matches overloads {candidates}. Add a type suffix to the arguments to pick one")
+            },
+            Self::GenericConstraintNotMet { name, value_type, constraint } => write!(f, "Generic function \"{name}\" can't be used with type {value_type:?}; expected type matching {constraint:?}"),
+            Self::GenericSpecFailed { name, msg } => write!(f, "Generic function \"{name}\" specialisation failed: {msg}"),
+            Self::GenericInstanceMismatch { name, expected, got } => write!(f, "Generic function \"{name}\" was specialised for parameters {expected:?}, but the instance has parameters {got:?}"),
         }
     }
 }
@@ -1,6 +1,6 @@
 use std::fmt::{Debug, Formatter};
 
-use crate::{ast::ast_node::{BinaryOperator, UnaryOperator}, common::{binding::FnSpec, untyped_value::UntypedValue, value::Value, value_type::ValueType}};
+use crate::{ast::ast_node::{BinaryOperator, MembershipSet, UnaryOperator}, common::{binding::{Binding, FnSpec, FnSpecChoice, GenericFnSpec, GenericParam, TypeConstraint}, host_set::HostSetData, math_intrinsic::MathIntrinsic, untyped_value::UntypedValue, value::Value, value_type::ValueType}};
 
 #[derive(Debug)]
 pub struct PackedAnalysisFunctionArg {
@@ -8,14 +8,36 @@ pub struct PackedAnalysisFunctionArg {
     pub expected_type: ValueType,
 }
 
+pub enum AnalysisFnSpec<'table> {
+    Binding(&'table FnSpec<'table>),
+    /// The choice of a generic function instance, made during analysis
+    Generic(FnSpecChoice),
+}
+
 pub enum PackedAnalysisNodeData<'table> {
     TypedValue { value: Value },
     UntypedValue { value: UntypedValue },
-    FunctionCall { args: Box<[PackedAnalysisFunctionArg]>, fn_spec: &'table FnSpec<'table> },
+    FunctionCall { args: Box<[PackedAnalysisFunctionArg]>, fn_spec: AnalysisFnSpec<'table>, ret_types: Box<[ValueType]>, ret_idx: usize },
+    /// A call to an overloaded function, which is replaced with a
+    /// [Self::FunctionCall] once the argument types are known
+    OverloadedFunctionCall { name: String, arg_idxs: Box<[usize]>, overloads: Box<[&'table Binding<'table>]>, ret_idx: Option<usize> },
+    /// A call to a generic function, which is replaced with a
+    /// [Self::FunctionCall] once the type parameter is known
+    GenericFunctionCall { name: String, arg_idxs: Box<[usize]>, params: &'table [GenericParam], constraint: TypeConstraint, fn_spec: &'table GenericFnSpec<'table>, ret_idx: Option<usize> },
+    /// A built-in math function; all arguments have the same type as the node
+    MathIntrinsicCall { intrinsic: MathIntrinsic, arg_idxs: Box<[usize]> },
     UnaryOperation { operator: UnaryOperator, right_idx: usize },
     BinaryOperation { operator: BinaryOperator, left_idx: usize, right_idx: usize },
     Variable { name: String },
     Ternary { cond_idx: usize, left_idx: usize, right_idx: usize },
+    /// `operators[i]` compares `operand_idxs[i]` with `operand_idxs[i + 1]`
+    ComparisonChain { operators: Box<[BinaryOperator]>, operand_idxs: Box<[usize]> },
+    /// `value in [items...]`, or `value in items[0]..items[1]`. The value and
+    /// the items are compared in their widened type
+    Membership { set: MembershipSet, value_idx: usize, item_idxs: Box<[usize]> },
+    /// `value in name`. Updatable sets are read through the Slab instead of
+    /// being embedded
+    SetMembership { name: String, value_idx: usize, set: &'table HostSetData, is_updatable: bool },
 }
 
 #[derive(Debug)]
@@ -45,11 +67,36 @@ impl Debug for PackedAnalysisNodeData<'_> {
                  .field("value", value)
                  .finish()
             },
-            PackedAnalysisNodeData::FunctionCall { args, fn_spec: _ } => {
+            PackedAnalysisNodeData::FunctionCall { args, fn_spec: _, ret_types, ret_idx } => {
                 f.debug_struct("PackedAnalysisNodeData::FunctionCall")
                  .field("args", args)
+                 .field("ret_types", ret_types)
+                 .field("ret_idx", ret_idx)
                  .finish_non_exhaustive()
             },
+            PackedAnalysisNodeData::OverloadedFunctionCall { name, arg_idxs, overloads, ret_idx } => {
+                f.debug_struct("PackedAnalysisNodeData::OverloadedFunctionCall")
+                 .field("name", name)
+                 .field("arg_idxs", arg_idxs)
+                 .field("overload_count", &overloads.len())
+                 .field("ret_idx", ret_idx)
+                 .finish()
+            },
+            PackedAnalysisNodeData::GenericFunctionCall { name, arg_idxs, params, constraint, fn_spec: _, ret_idx } => {
+                f.debug_struct("PackedAnalysisNodeData::GenericFunctionCall")
+                 .field("name", name)
+                 .field("arg_idxs", arg_idxs)
+                 .field("params", params)
+                 .field("constraint", constraint)
+                 .field("ret_idx", ret_idx)
+                 .finish_non_exhaustive()
+            },
+            PackedAnalysisNodeData::MathIntrinsicCall { intrinsic, arg_idxs } => {
+                f.debug_struct("PackedAnalysisNodeData::MathIntrinsicCall")
+                 .field("intrinsic", intrinsic)
+                 .field("arg_idxs", arg_idxs)
+                 .finish()
+            },
             PackedAnalysisNodeData::UnaryOperation { operator, right_idx } => {
                 f.debug_struct("PackedAnalysisNodeData::UnaryOperation")
                  .field("operator", operator)
@@ -75,6 +122,28 @@ impl Debug for PackedAnalysisNodeData<'_> {
                  .field("right_idx", right_idx)
                  .finish()
             },
+            PackedAnalysisNodeData::ComparisonChain { operators, operand_idxs } => {
+                f.debug_struct("PackedAnalysisNodeData::ComparisonChain")
+                 .field("operators", operators)
+                 .field("operand_idxs", operand_idxs)
+                 .finish()
+            },
+            PackedAnalysisNodeData::Membership { set, value_idx, item_idxs } => {
+                f.debug_struct("PackedAnalysisNodeData::Membership")
+                 .field("set", set)
+                 .field("value_idx", value_idx)
+                 .field("item_idxs", item_idxs)
+                 .finish()
+            },
+            PackedAnalysisNodeData::SetMembership { name, value_idx, set, is_updatable } => {
+                f.debug_struct("PackedAnalysisNodeData::SetMembership")
+                 .field("name", name)
+                 .field("value_idx", value_idx)
+                 .field("value_type", &set.get_value_type())
+                 .field("len", &set.len())
+                 .field("is_updatable", is_updatable)
+                 .finish()
+            },
         }
     }
 }
\ No newline at end of file
@@ -1,9 +1,62 @@
 use std::error::Error;
 
-use crate::{analysis::packed_analysis_node::{PackedAnalysisFunctionArg, PackedAnalysisNodeData}, ast::ast_node::{BinaryOperator, Expression, UnaryOperator}, common::{binding::Binding, table::Table, untyped_value::UntypedValue, value_type::ValueType}};
+use crate::{analysis::packed_analysis_node::{AnalysisFnSpec, PackedAnalysisFunctionArg, PackedAnalysisNodeData}, ast::ast_node::{BinaryOperator, CallArgument, Expression, MembershipSet, UnaryOperator}, common::{binding::{Binding, GenericParam, TypeConstraint}, table::Table, untyped_value::UntypedValue, value::Value, value_type::ValueType}};
 
 use super::{error::AnalysisError, packed_analysis_node::PackedAnalysisNode};
 
+/// Where the value of a function argument comes from, after matching named
+/// arguments and filling in defaults
+#[derive(PartialEq)]
+enum CallArgSource {
+    Argument(usize),
+    Default(Value),
+}
+
+#[derive(Clone, Copy)]
+enum OverloadArg {
+    Typed(ValueType),
+    /// An untyped expression with only integer literals
+    UntypedInteger,
+    /// An untyped expression with at least one float literal
+    UntypedFloat,
+}
+
+/// Added to the cost of passing an integer to a float parameter, so that any
+/// integer parameter is preferred over a float one
+const INT_TO_FLOAT_COST: u32 = 16;
+
+impl OverloadArg {
+    /// Lower is better, None if the argument can't be passed to the parameter.
+    /// The cost is the distance between the types' implicit cast priorities,
+    /// so that the closest type wins. Untyped arguments are measured from their
+    /// fallback types
+    fn get_cost(&self, param: ValueType) -> Option<u32> {
+        match *self {
+            Self::Typed(value_type) if value_type == param || value_type.can_implicit_cast_to(&param) => Some(Self::get_conversion_cost(value_type, param)),
+            Self::UntypedInteger if param.is_integer() || param.is_float() => Some(Self::get_conversion_cost(ValueType::I32, param)),
+            Self::UntypedFloat if param.is_float() => Some(Self::get_conversion_cost(ValueType::F64, param)),
+            _ => None,
+        }
+    }
+
+    fn get_conversion_cost(from: ValueType, to: ValueType) -> u32 {
+        let distance = from.get_implicit_cast_priority().abs_diff(to.get_implicit_cast_priority());
+        if from.is_integer() && to.is_float() {
+            INT_TO_FLOAT_COST + distance
+        } else {
+            distance
+        }
+    }
+
+    fn get_value_type(&self) -> Option<ValueType> {
+        match *self {
+            Self::Typed(value_type) => Some(value_type),
+            Self::UntypedInteger |
+            Self::UntypedFloat => None,
+        }
+    }
+}
+
 pub struct PackedAnalysisTree<'table> {
     pub nodes: Vec<PackedAnalysisNode<'table>>,
 }
@@ -14,7 +67,7 @@ impl<'table> PackedAnalysisTree<'table> {
             Expression::TypedValue { value } => {
                 (PackedAnalysisNode {
                     resolved_type: Some(value.get_value_type()),
-                    data: PackedAnalysisNodeData::TypedValue { value: value.clone() },
+                    data: PackedAnalysisNodeData::TypedValue { value: *value },
                     parent_idx: None,
                 }, self.nodes.len())
             },
@@ -25,41 +78,116 @@ impl<'table> PackedAnalysisTree<'table> {
                     parent_idx: None,
                 }, self.nodes.len())
             },
-            Expression::FunctionCall { name, arguments } => {
-                let binding = table.get_binding(name).ok_or_else(|| { AnalysisError::UnknownBinding { name: name.clone() } })?;
+            Expression::FunctionCall { name, arguments, ret_idx } if let Some(intrinsic) = table.get_math_intrinsic(name) => {
+                if let Some(arg) = arguments.iter().find_map(|argument| argument.name.as_ref()) {
+                    return Err(AnalysisError::UnknownNamedArgument { name: name.clone(), arg: arg.clone() })
+                }
+
+                if let Some(idx) = *ret_idx {
+                    return Err(AnalysisError::BadReturnIndex { name: name.clone(), idx, count: 1 })
+                }
+
+                let expected_argc = intrinsic.get_argc();
                 let actual_argc = arguments.len();
-                let (ret_type, params, fn_spec) = match binding {
-                    Binding::Const { .. } |
-                    Binding::Variable { .. } => {
-                        return Err(AnalysisError::BadBindingKind { name: name.clone(), is_var: true })
-                    },
-                    Binding::Function { ret_type, params, fn_spec } => {
-                        let expected_argc = params.len();
-                        if expected_argc != actual_argc {
-                            return Err(AnalysisError::BadArguments { name: name.clone(), expected_argc, actual_argc })
-                        }
+                if expected_argc != actual_argc {
+                    return Err(AnalysisError::BadArguments { name: name.clone(), expected_argc, actual_argc })
+                }
 
-                        (ret_type, params, fn_spec)
-                    },
+                let mut arg_idxs = Vec::<usize>::new();
+                for argument in arguments {
+                    arg_idxs.push(self.ast_to_analysis_node(&argument.value, table)?);
+                }
+
+                let this_idx = self.nodes.len();
+
+                for arg_idx in &arg_idxs {
+                    self.nodes[*arg_idx].parent_idx = Some(this_idx);
+                }
+
+                (PackedAnalysisNode {
+                    resolved_type: None,
+                    data: PackedAnalysisNodeData::MathIntrinsicCall { intrinsic, arg_idxs: arg_idxs.into() },
+                    parent_idx: None,
+                }, this_idx)
+            },
+            Expression::FunctionCall { name, arguments, ret_idx } => {
+                let overloads = table.get_overloads(name).ok_or_else(|| { AnalysisError::UnknownBinding { name: name.clone() } })?;
+                let actual_argc = arguments.len();
+                let has_named = arguments.iter().any(|argument| argument.name.is_some());
+                if arguments.iter().skip_while(|argument| argument.name.is_none()).any(|argument| argument.name.is_none()) {
+                    return Err(AnalysisError::PositionalAfterNamedArgument { name: name.clone() })
+                }
+
+                let mut candidates = Vec::<(&'table Binding, Vec<CallArgSource>)>::new();
+                let mut first_error = None;
+                for binding in overloads {
+                    match binding {
+                        Binding::Const { .. } |
+                        Binding::Variable { .. } |
+                        Binding::Input { .. } => {
+                            return Err(AnalysisError::BadBindingKind { name: name.clone(), is_var: true })
+                        },
+                        Binding::Set { .. } => return Err(AnalysisError::BadSetUsage { name: name.clone() }),
+                        Binding::Function { .. } |
+                        Binding::GenericFunction { .. } => match Self::bind_call_arguments(name, binding, arguments) {
+                            Ok(sources) => candidates.push((binding, sources)),
+                            Err(err) => { first_error.get_or_insert(err); },
+                        },
+                    }
+                }
+
+                let Some((_, sources)) = candidates.first() else {
+                    return Err(match first_error {
+                        Some(err) if overloads.len() == 1 => err,
+                        _ if has_named => AnalysisError::NoOverloadForNamedArguments { name: name.clone() },
+                        _ => AnalysisError::NoOverloadForArgc { name: name.clone(), actual_argc },
+                    })
                 };
 
-                let mut args = Vec::<PackedAnalysisFunctionArg>::new();
-                for a in 0..params.len() {
-                    args.push(PackedAnalysisFunctionArg {
-                        idx: self.ast_to_analysis_node(&arguments[a], table)?,
-                        expected_type: params[a],
+                // overloads can only be picked by type if they take the
+                // arguments in the same order, with the same defaults
+                if candidates.iter().any(|(_, other_sources)| other_sources != sources) {
+                    return Err(AnalysisError::AmbiguousNamedArguments { name: name.clone() })
+                }
+
+                let mut arg_idxs = Vec::<usize>::new();
+                for source in sources {
+                    arg_idxs.push(match source {
+                        CallArgSource::Argument(i) => self.ast_to_analysis_node(&arguments[*i].value, table)?,
+                        CallArgSource::Default(value) => {
+                            self.nodes.push(PackedAnalysisNode {
+                                resolved_type: Some(value.get_value_type()),
+                                data: PackedAnalysisNodeData::TypedValue { value: *value },
+                                parent_idx: None,
+                            });
+
+                            self.nodes.len() - 1
+                        },
                     });
                 }
 
+                let candidates = candidates.into_iter().map(|(binding, _)| binding).collect::<Vec<_>>();
                 let this_idx = self.nodes.len();
 
-                for arg in &args {
-                    self.nodes[arg.idx].parent_idx = Some(this_idx);
+                for arg_idx in &arg_idxs {
+                    self.nodes[*arg_idx].parent_idx = Some(this_idx);
                 }
 
+                // overloads are picked, and generic functions specialised, after
+                // the argument types are resolved, in semantic_analysis
+                let (resolved_type, data) = if let [binding @ Binding::Function { ret_types, fn_spec, .. }] = candidates[..] {
+                    let params = binding.get_call_params(arg_idxs.len()).ok_or(AnalysisError::BadAnalysis)?;
+                    let (resolved_type, data) = Self::make_function_call(name, &params, ret_types.clone(), AnalysisFnSpec::Binding(fn_spec), &arg_idxs, *ret_idx)?;
+                    (Some(resolved_type), data)
+                } else if let [Binding::GenericFunction { params, constraint, fn_spec }] = candidates[..] {
+                    (None, PackedAnalysisNodeData::GenericFunctionCall { name: name.clone(), arg_idxs: arg_idxs.into(), params, constraint: *constraint, fn_spec, ret_idx: *ret_idx })
+                } else {
+                    (None, PackedAnalysisNodeData::OverloadedFunctionCall { name: name.clone(), arg_idxs: arg_idxs.into(), overloads: candidates.into(), ret_idx: *ret_idx })
+                };
+
                 (PackedAnalysisNode {
-                    resolved_type: Some(*ret_type),
-                    data: PackedAnalysisNodeData::FunctionCall { args: args.into(), fn_spec },
+                    resolved_type,
+                    data,
                     parent_idx: None,
                 }, this_idx)
             },
@@ -86,11 +214,18 @@ impl<'table> PackedAnalysisTree<'table> {
 
                 (PackedAnalysisNode {
                     resolved_type: match operator {
+                        BinaryOperator::Pow |
                         BinaryOperator::Mul |
                         BinaryOperator::Div |
                         BinaryOperator::Mod |
                         BinaryOperator::Add |
-                        BinaryOperator::Sub => None,
+                        BinaryOperator::Sub |
+                        BinaryOperator::SaturatingAdd |
+                        BinaryOperator::SaturatingSub |
+                        BinaryOperator::SaturatingMul |
+                        BinaryOperator::WrappingAdd |
+                        BinaryOperator::WrappingSub |
+                        BinaryOperator::WrappingMul => None,
                         BinaryOperator::Equals |
                         BinaryOperator::NotEquals |
                         BinaryOperator::LesserThanEquals |
@@ -109,17 +244,20 @@ impl<'table> PackedAnalysisTree<'table> {
                 let new_node = match binding {
                     Binding::Const { value } => PackedAnalysisNode {
                         resolved_type: Some(value.get_value_type()),
-                        data: PackedAnalysisNodeData::TypedValue { value: value.clone() },
+                        data: PackedAnalysisNodeData::TypedValue { value: *value },
                         parent_idx: None,
                     },
-                    Binding::Variable { value_type } => PackedAnalysisNode {
-                        resolved_type: Some(value_type.clone()),
+                    Binding::Variable { value_type } |
+                    Binding::Input { value_type, .. } => PackedAnalysisNode {
+                        resolved_type: Some(*value_type),
                         data: PackedAnalysisNodeData::Variable { name: name.clone() },
                         parent_idx: None,
                     },
-                    Binding::Function { .. } => {
+                    Binding::Function { .. } |
+                    Binding::GenericFunction { .. } => {
                         return Err(AnalysisError::BadBindingKind { name: name.clone(), is_var: false })
-                    }
+                    },
+                    Binding::Set { .. } => return Err(AnalysisError::BadSetUsage { name: name.clone() }),
                 };
 
                 (new_node, self.nodes.len())
@@ -139,6 +277,60 @@ impl<'table> PackedAnalysisTree<'table> {
                     parent_idx: None,
                 }, this_idx)
             },
+            Expression::ComparisonChain { operands, operators } => {
+                let mut operand_idxs = Vec::with_capacity(operands.len());
+                for operand in operands {
+                    operand_idxs.push(self.ast_to_analysis_node(operand, table)?);
+                }
+
+                let this_idx = self.nodes.len();
+                for operand_idx in &operand_idxs {
+                    self.nodes[*operand_idx].parent_idx = Some(this_idx);
+                }
+
+                (PackedAnalysisNode {
+                    resolved_type: Some(ValueType::Bool),
+                    data: PackedAnalysisNodeData::ComparisonChain { operators: operators.clone().into_boxed_slice(), operand_idxs: operand_idxs.into_boxed_slice() },
+                    parent_idx: None,
+                }, this_idx)
+            },
+            Expression::Membership { value, set, items } => {
+                let value_idx = self.ast_to_analysis_node(value, table)?;
+                let mut item_idxs = Vec::with_capacity(items.len());
+                for item in items {
+                    item_idxs.push(self.ast_to_analysis_node(item, table)?);
+                }
+
+                let this_idx = self.nodes.len();
+                self.nodes[value_idx].parent_idx = Some(this_idx);
+                for item_idx in &item_idxs {
+                    self.nodes[*item_idx].parent_idx = Some(this_idx);
+                }
+
+                (PackedAnalysisNode {
+                    resolved_type: Some(ValueType::Bool),
+                    data: PackedAnalysisNodeData::Membership { set: *set, value_idx, item_idxs: item_idxs.into_boxed_slice() },
+                    parent_idx: None,
+                }, this_idx)
+            },
+            Expression::SetMembership { value, name } => {
+                let Some(Binding::Set { data, handle_idx }) = table.get_binding(name) else {
+                    return Err(match table.get_binding(name) {
+                        Some(_) => AnalysisError::NotASet { name: name.clone() },
+                        None => AnalysisError::UnknownBinding { name: name.clone() },
+                    })
+                };
+
+                let value_idx = self.ast_to_analysis_node(value, table)?;
+                let this_idx = self.nodes.len();
+                self.nodes[value_idx].parent_idx = Some(this_idx);
+
+                (PackedAnalysisNode {
+                    resolved_type: Some(ValueType::Bool),
+                    data: PackedAnalysisNodeData::SetMembership { name: name.clone(), value_idx, set: data, is_updatable: handle_idx.is_some() },
+                    parent_idx: None,
+                }, this_idx)
+            },
         };
 
         self.nodes.push(node);
@@ -146,6 +338,79 @@ impl<'table> PackedAnalysisTree<'table> {
         Ok(this_idx)
     }
 
+    /// Matches the arguments of a call to the parameters of a function, in
+    /// parameter order, followed by any variadic arguments
+    fn bind_call_arguments(name: &str, binding: &Binding, arguments: &[CallArgument]) -> Result<Vec<CallArgSource>, AnalysisError> {
+        let actual_argc = arguments.len();
+        let (params, variadic, param_defs) = match binding {
+            Binding::Function { params, variadic, param_defs, .. } => (&params[..], *variadic, param_defs.as_deref()),
+            Binding::GenericFunction { params, .. } => {
+                if let Some(arg) = arguments.iter().find_map(|argument| argument.name.as_ref()) {
+                    return Err(AnalysisError::UnknownNamedArgument { name: name.to_string(), arg: arg.clone() })
+                }
+
+                if params.len() != actual_argc {
+                    return Err(AnalysisError::BadArguments { name: name.to_string(), expected_argc: params.len(), actual_argc })
+                }
+
+                return Ok((0..actual_argc).map(CallArgSource::Argument).collect())
+            },
+            _ => return Err(AnalysisError::BadAnalysis),
+        };
+
+        let mut slots = vec![None; params.len()];
+        let mut variadic_sources = Vec::new();
+        for (i, argument) in arguments.iter().enumerate() {
+            // positional arguments always come before named ones
+            let slot = match &argument.name {
+                None if i < params.len() => i,
+                None if variadic.is_some() => {
+                    variadic_sources.push(CallArgSource::Argument(i));
+                    continue
+                },
+                None => return Err(AnalysisError::BadArguments { name: name.to_string(), expected_argc: params.len(), actual_argc }),
+                Some(arg) => {
+                    param_defs.and_then(|defs| defs.iter().position(|def| def.name == *arg))
+                        .ok_or_else(|| AnalysisError::UnknownNamedArgument { name: name.to_string(), arg: arg.clone() })?
+                },
+            };
+
+            if slots[slot].replace(i).is_some() {
+                let param = param_defs.map_or_else(|| slot.to_string(), |defs| defs[slot].name.clone());
+                return Err(AnalysisError::DuplicateArgument { name: name.to_string(), param })
+            }
+        }
+
+        let mut sources = Vec::new();
+        for (slot_idx, slot) in slots.into_iter().enumerate() {
+            sources.push(match (slot, param_defs) {
+                (Some(i), _) => CallArgSource::Argument(i),
+                (None, Some(defs)) => match defs[slot_idx].default {
+                    Some(value) => CallArgSource::Default(value),
+                    None => return Err(AnalysisError::MissingArgument { name: name.to_string(), param: defs[slot_idx].name.clone() }),
+                },
+                (None, None) if variadic.is_some() => return Err(AnalysisError::BadVariadicArguments { name: name.to_string(), min_argc: params.len(), actual_argc }),
+                (None, None) => return Err(AnalysisError::BadArguments { name: name.to_string(), expected_argc: params.len(), actual_argc }),
+            });
+        }
+
+        sources.extend(variadic_sources);
+        Ok(sources)
+    }
+
+    fn make_function_call(name: &str, params: &[ValueType], ret_types: Box<[ValueType]>, fn_spec: AnalysisFnSpec<'table>, arg_idxs: &[usize], ret_idx: Option<usize>) -> Result<(ValueType, PackedAnalysisNodeData<'table>), AnalysisError> {
+        let count = ret_types.len();
+        let ret_idx = match ret_idx {
+            Some(idx) if idx >= count => return Err(AnalysisError::BadReturnIndex { name: name.to_string(), idx, count }),
+            Some(idx) => idx,
+            None if count != 1 => return Err(AnalysisError::MissingReturnIndex { name: name.to_string(), count }),
+            None => 0,
+        };
+
+        let args = arg_idxs.iter().zip(params).map(|(idx, expected_type)| PackedAnalysisFunctionArg { idx: *idx, expected_type: *expected_type }).collect();
+        Ok((ret_types[ret_idx], PackedAnalysisNodeData::FunctionCall { args, fn_spec, ret_types, ret_idx }))
+    }
+
     pub fn from_ast(ast_root_node: &Expression, table: &'table Table) -> Result<PackedAnalysisTree<'table>, Box<dyn Error>> {
         let mut tree = PackedAnalysisTree { nodes: Vec::new() };
         tree.ast_to_analysis_node(ast_root_node, table)?;
@@ -160,7 +425,20 @@ impl<'table> PackedAnalysisTree<'table> {
             PackedAnalysisNodeData::TypedValue { .. } |
             PackedAnalysisNodeData::UntypedValue { .. } |
             PackedAnalysisNodeData::FunctionCall { .. } |
-            PackedAnalysisNodeData::Variable { .. } => unreachable!(),
+            PackedAnalysisNodeData::Variable { .. } |
+            PackedAnalysisNodeData::ComparisonChain { .. } |
+            PackedAnalysisNodeData::Membership { .. } |
+            PackedAnalysisNodeData::SetMembership { .. } => unreachable!(),
+            PackedAnalysisNodeData::OverloadedFunctionCall { .. } |
+            PackedAnalysisNodeData::GenericFunctionCall { .. } => None,
+            PackedAnalysisNodeData::MathIntrinsicCall { intrinsic: _, arg_idxs } => {
+                let mut new_type = self.nodes[arg_idxs[0]].resolved_type;
+                for arg_idx in &arg_idxs[1..] {
+                    new_type = ValueType::widen_optional_non_greedy(new_type, self.nodes[*arg_idx].resolved_type)?;
+                }
+
+                new_type
+            },
             PackedAnalysisNodeData::UnaryOperation { operator, right_idx } => {
                 let right_idx = *right_idx;
 
@@ -174,11 +452,18 @@ impl<'table> PackedAnalysisTree<'table> {
                 let right_idx = *right_idx;
 
                 match operator {
+                    BinaryOperator::Pow => self.nodes[left_idx].resolved_type,
                     BinaryOperator::Mul |
                     BinaryOperator::Div |
                     BinaryOperator::Mod |
                     BinaryOperator::Add |
-                    BinaryOperator::Sub => {
+                    BinaryOperator::Sub |
+                    BinaryOperator::SaturatingAdd |
+                    BinaryOperator::SaturatingSub |
+                    BinaryOperator::SaturatingMul |
+                    BinaryOperator::WrappingAdd |
+                    BinaryOperator::WrappingSub |
+                    BinaryOperator::WrappingMul => {
                         ValueType::widen_optional_non_greedy(self.nodes[left_idx].resolved_type, self.nodes[right_idx].resolved_type)?
                     },
                     _ => unreachable!(),
@@ -218,7 +503,7 @@ impl<'table> PackedAnalysisTree<'table> {
             PackedAnalysisNodeData::TypedValue { .. } |
             PackedAnalysisNodeData::UntypedValue { .. } |
             PackedAnalysisNodeData::Variable { .. } => return Err(Box::new(AnalysisError::BadAnalysis)),
-            PackedAnalysisNodeData::FunctionCall { args, fn_spec: _  } => 'slfc_match: {
+            PackedAnalysisNodeData::FunctionCall { args, .. } => 'slfc_match: {
                 for PackedAnalysisFunctionArg { idx, expected_type } in args {
                     if child_idx == *idx {
                         break 'slfc_match Some(*expected_type);
@@ -227,6 +512,35 @@ impl<'table> PackedAnalysisTree<'table> {
 
                 return Err(Box::new(AnalysisError::BadAnalysis))
             },
+            // parameter types are unknown until an overload is picked
+            PackedAnalysisNodeData::OverloadedFunctionCall { .. } => None,
+            PackedAnalysisNodeData::GenericFunctionCall { arg_idxs, params, .. } => {
+                let Some(param) = arg_idxs.iter().position(|idx| *idx == child_idx).map(|param_idx| params[param_idx]) else {
+                    return Err(Box::new(AnalysisError::BadAnalysis))
+                };
+
+                match param {
+                    GenericParam::Concrete(value_type) => Some(value_type),
+                    GenericParam::Generic => self.get_generic_type(arg_idxs, params)?,
+                }
+            },
+            PackedAnalysisNodeData::MathIntrinsicCall { intrinsic, arg_idxs } => {
+                if !arg_idxs.contains(&child_idx) {
+                    return Err(Box::new(AnalysisError::BadAnalysis))
+                }
+
+                let mut sibling_type = None;
+                for arg_idx in arg_idxs {
+                    sibling_type = ValueType::widen_optional_greedy(sibling_type, self.nodes[*arg_idx].resolved_type)?;
+                }
+
+                // untyped arguments of float-only intrinsics, e.g. `sqrt(2)`,
+                // become floats instead of falling back to integers
+                node.resolved_type
+                    .or(parent_hint)
+                    .or(sibling_type)
+                    .or((intrinsic.get_constraint() == TypeConstraint::Float).then_some(ValueType::F64))
+            },
             PackedAnalysisNodeData::UnaryOperation { operator, right_idx } => {
                 if child_idx != *right_idx {
                     return Err(Box::new(AnalysisError::BadAnalysis))
@@ -252,11 +566,33 @@ impl<'table> PackedAnalysisTree<'table> {
                 }
 
                 match operator {
+                    BinaryOperator::Pow if child_idx == left_idx => {
+                        // an untyped base with a float exponent, e.g.
+                        // `2 ** 0.5`, becomes a float instead of falling back
+                        // to an integer
+                        let exponent_type = self.nodes[right_idx].resolved_type.filter(ValueType::is_float);
+                        node.resolved_type
+                            .or(parent_hint)
+                            .or(exponent_type)
+                            .or(self.contains_untyped_float(right_idx).then_some(ValueType::F64))
+                    },
+                    BinaryOperator::Pow => match self.nodes[left_idx].resolved_type {
+                        Some(base_type) if base_type.is_float() && self.contains_untyped_float(right_idx) => Some(base_type),
+                        Some(base_type) if base_type.is_float() => Some(ValueType::I32),
+                        Some(_) => Some(ValueType::U32),
+                        None => None,
+                    },
                     BinaryOperator::Mul |
                     BinaryOperator::Div |
                     BinaryOperator::Mod |
                     BinaryOperator::Add |
-                    BinaryOperator::Sub => {
+                    BinaryOperator::Sub |
+                    BinaryOperator::SaturatingAdd |
+                    BinaryOperator::SaturatingSub |
+                    BinaryOperator::SaturatingMul |
+                    BinaryOperator::WrappingAdd |
+                    BinaryOperator::WrappingSub |
+                    BinaryOperator::WrappingMul => {
                         if node.resolved_type.is_some() {
                             node.resolved_type
                         } else if parent_hint.is_some() {
@@ -270,7 +606,7 @@ impl<'table> PackedAnalysisTree<'table> {
                     BinaryOperator::LesserThanEquals |
                     BinaryOperator::GreaterThanEquals |
                     BinaryOperator::LesserThan |
-                    BinaryOperator::GreaterThan => ValueType::widen_optional_greedy(self.nodes[left_idx].resolved_type, self.nodes[right_idx].resolved_type)?,
+                    BinaryOperator::GreaterThan => ValueType::widen_optional_for_comparison(self.nodes[left_idx].resolved_type, self.nodes[right_idx].resolved_type)?,
                     BinaryOperator::LogicalAnd |
                     BinaryOperator::LogicalOr => Some(ValueType::Bool),
                 }
@@ -288,6 +624,42 @@ impl<'table> PackedAnalysisTree<'table> {
                     ValueType::widen_optional_greedy(self.nodes[left_idx].resolved_type, self.nodes[right_idx].resolved_type)?
                 }
             },
+            // each operand is unified with its neighbours, like in a pair of
+            // comparisons
+            PackedAnalysisNodeData::ComparisonChain { operators: _, operand_idxs } => {
+                let Some(pos) = operand_idxs.iter().position(|idx| *idx == child_idx) else {
+                    return Err(Box::new(AnalysisError::BadAnalysis))
+                };
+
+                let prev_type = pos.checked_sub(1).and_then(|prev| self.nodes[operand_idxs[prev]].resolved_type);
+                let next_type = operand_idxs.get(pos + 1).and_then(|next| self.nodes[*next].resolved_type);
+                ValueType::widen_optional_for_comparison(prev_type, next_type)?
+            },
+            // each item is compared with the value, so items are unified with
+            // the value, and the value with all of the items
+            PackedAnalysisNodeData::Membership { set: _, value_idx, item_idxs } => {
+                if child_idx != *value_idx {
+                    if !item_idxs.contains(&child_idx) {
+                        return Err(Box::new(AnalysisError::BadAnalysis))
+                    }
+
+                    return Ok(self.nodes[*value_idx].resolved_type);
+                }
+
+                let mut common_type = None;
+                for item_idx in item_idxs {
+                    common_type = ValueType::widen_optional_for_comparison(common_type, self.nodes[*item_idx].resolved_type)?;
+                }
+
+                common_type
+            },
+            PackedAnalysisNodeData::SetMembership { value_idx, set, .. } => {
+                if child_idx != *value_idx {
+                    return Err(Box::new(AnalysisError::BadAnalysis))
+                }
+
+                Some(set.get_value_type())
+            },
         })
     }
 
@@ -295,7 +667,7 @@ impl<'table> PackedAnalysisTree<'table> {
         if self.nodes[child_idx].resolved_type.is_none() {
             let child_hint = self.get_child_input_hint(parent_idx, child_idx, parent_hint)?;
             if let Some(child_hint) = child_hint {
-                return Ok(self.propagate_type_from_outer(child_idx, child_hint)?);
+                return self.propagate_type_from_outer(child_idx, child_hint);
             }
         }
 
@@ -310,7 +682,21 @@ impl<'table> PackedAnalysisTree<'table> {
         match &node.data {
             PackedAnalysisNodeData::TypedValue { .. } |
             PackedAnalysisNodeData::Variable { .. } |
-            PackedAnalysisNodeData::FunctionCall { .. } => unreachable!(),
+            PackedAnalysisNodeData::FunctionCall { .. } |
+            PackedAnalysisNodeData::ComparisonChain { .. } |
+            PackedAnalysisNodeData::Membership { .. } |
+            PackedAnalysisNodeData::SetMembership { .. } => unreachable!(),
+            PackedAnalysisNodeData::OverloadedFunctionCall { .. } |
+            PackedAnalysisNodeData::GenericFunctionCall { .. } => {
+                had_changes = false;
+            },
+            PackedAnalysisNodeData::MathIntrinsicCall { intrinsic: _, arg_idxs } => {
+                let arg_idxs = arg_idxs.clone();
+                had_changes = false;
+                for arg_idx in arg_idxs {
+                    had_changes = self.try_propagate_type_from_outer_to_child(idx, arg_idx, Some(hint))? || had_changes;
+                }
+            },
             PackedAnalysisNodeData::UntypedValue { value } => {
                 node.data = PackedAnalysisNodeData::TypedValue { value: value.get_resolved_value(hint)? };
                 node.resolved_type = Some(hint);
@@ -331,11 +717,22 @@ impl<'table> PackedAnalysisTree<'table> {
                 let right_idx = *right_idx;
 
                 match operator {
+                    // the exponent's type depends on the base, not on the
+                    // result, so it's hinted once the base is resolved
+                    BinaryOperator::Pow => {
+                        had_changes = self.try_propagate_type_from_outer_to_child(idx, left_idx, Some(hint))?;
+                    },
                     BinaryOperator::Mul |
                     BinaryOperator::Div |
                     BinaryOperator::Mod |
                     BinaryOperator::Add |
                     BinaryOperator::Sub |
+                    BinaryOperator::SaturatingAdd |
+                    BinaryOperator::SaturatingSub |
+                    BinaryOperator::SaturatingMul |
+                    BinaryOperator::WrappingAdd |
+                    BinaryOperator::WrappingSub |
+                    BinaryOperator::WrappingMul |
                     BinaryOperator::Equals |
                     BinaryOperator::NotEquals |
                     BinaryOperator::LesserThanEquals |
@@ -364,7 +761,7 @@ impl<'table> PackedAnalysisTree<'table> {
         let mut had_changes = false;
         for idx in (0..self.nodes.len()).rev() {
             let node = &self.nodes[idx];
-            match node.resolved_type { Some(_) => continue, None => {} };
+            if node.resolved_type.is_some() { continue }
             let parent_idx = match node.parent_idx { Some(x) => x, None => continue };
             let hint = self.get_child_input_hint(parent_idx, idx, None)?;
             if let Some(hint) = hint {
@@ -404,35 +801,41 @@ impl<'table> PackedAnalysisTree<'table> {
         // multiple iterations of type propagation
         self.resolve_types_from_both()?;
 
+        // overloads and generic functions depend on their argument types, and
+        // the rest of the tree may depend on the chosen overload or instance.
+        // they're picked one at a time, from the innermost call, so that each
+        // choice is propagated before the next
+        while self.resolve_next_call()? {
+            self.resolve_types_from_both()?;
+        }
+
         let mut had_fallback = false;
         for i in 0..self.nodes.len() {
             let node = &mut self.nodes[i];
-            if node.resolved_type.is_none() {
-                if let PackedAnalysisNodeData::UntypedValue { value } = &node.data {
-                    let resolved_type = match value {
-                        UntypedValue::Float { .. } => {
-                            Some(ValueType::F64)
-                        },
-                        UntypedValue::Integer { inner } => {
-                            if *inner <= i32::MAX as u64 {
-                                Some(ValueType::I32)
-                            } else if *inner <= i64::MAX as u64 {
-                                Some(ValueType::I64)
-                            } else {
-                                // XXX falling back to a u64 is not allowed even
-                                //     if it doesn't fit in a i64. the type will
-                                //     be unresolved unless something else
-                                //     causes it to be resolved
-                                None
-                            }
-                        },
-                    };
+            if node.resolved_type.is_none() && let PackedAnalysisNodeData::UntypedValue { value } = &node.data {
+                let resolved_type = match value {
+                    UntypedValue::Float { .. } => {
+                        Some(ValueType::F64)
+                    },
+                    UntypedValue::Integer { inner } => {
+                        if *inner <= i32::MAX as u64 {
+                            Some(ValueType::I32)
+                        } else if *inner <= i64::MAX as u64 {
+                            Some(ValueType::I64)
+                        } else {
+                            // XXX falling back to a u64 is not allowed even
+                            //     if it doesn't fit in a i64. the type will
+                            //     be unresolved unless something else
+                            //     causes it to be resolved
+                            None
+                        }
+                    },
+                };
 
-                    if let Some(resolved_type) = resolved_type {
-                        node.data = PackedAnalysisNodeData::TypedValue { value: value.get_resolved_value(resolved_type)? };
-                        node.resolved_type = Some(resolved_type);
-                        had_fallback = true;
-                    }
+                if let Some(resolved_type) = resolved_type {
+                    node.data = PackedAnalysisNodeData::TypedValue { value: value.get_resolved_value(resolved_type)? };
+                    node.resolved_type = Some(resolved_type);
+                    had_fallback = true;
                 }
             }
         }
@@ -441,6 +844,236 @@ impl<'table> PackedAnalysisTree<'table> {
             self.resolve_types_from_both()?;
         }
 
+        self.check_integer_only_ops()?;
+        self.check_pow_types()?;
+        self.check_membership_types()?;
+        self.check_math_intrinsic_types()
+    }
+
+    fn contains_untyped_float(&self, idx: usize) -> bool {
+        let node = &self.nodes[idx];
+        if node.resolved_type.is_some() { return false }
+
+        match &node.data {
+            PackedAnalysisNodeData::UntypedValue { value } => matches!(value, UntypedValue::Float { .. }),
+            PackedAnalysisNodeData::UnaryOperation { operator: _, right_idx } => self.contains_untyped_float(*right_idx),
+            PackedAnalysisNodeData::BinaryOperation { operator: BinaryOperator::Pow, left_idx, right_idx: _ } => self.contains_untyped_float(*left_idx),
+            PackedAnalysisNodeData::BinaryOperation { operator: _, left_idx, right_idx } |
+            PackedAnalysisNodeData::Ternary { cond_idx: _, left_idx, right_idx } => {
+                self.contains_untyped_float(*left_idx) || self.contains_untyped_float(*right_idx)
+            },
+            PackedAnalysisNodeData::MathIntrinsicCall { intrinsic: _, arg_idxs } => arg_idxs.iter().any(|arg_idx| self.contains_untyped_float(*arg_idx)),
+            _ => false,
+        }
+    }
+
+    fn get_overload_arg(&self, idx: usize) -> OverloadArg {
+        match self.nodes[idx].resolved_type {
+            Some(value_type) => OverloadArg::Typed(value_type),
+            None if self.contains_untyped_float(idx) => OverloadArg::UntypedFloat,
+            None => OverloadArg::UntypedInteger,
+        }
+    }
+
+    /// The type of the generic arguments of a generic function call, if any of
+    /// them is typed
+    fn get_generic_type(&self, arg_idxs: &[usize], params: &[GenericParam]) -> Result<Option<ValueType>, Box<dyn Error>> {
+        let mut generic_type = None;
+        for (idx, param) in arg_idxs.iter().zip(params) {
+            if *param == GenericParam::Generic {
+                generic_type = ValueType::widen_optional_greedy(generic_type, self.nodes[*idx].resolved_type)?;
+            }
+        }
+
+        Ok(generic_type)
+    }
+
+    fn resolve_next_call(&mut self) -> Result<bool, Box<dyn Error>> {
+        let Some(idx) = self.nodes.iter().position(|node| matches!(node.data, PackedAnalysisNodeData::OverloadedFunctionCall { .. } | PackedAnalysisNodeData::GenericFunctionCall { .. })) else {
+            return Ok(false)
+        };
+
+        let (resolved_type, data) = match &self.nodes[idx].data {
+            PackedAnalysisNodeData::OverloadedFunctionCall { .. } => self.resolve_overload(idx)?,
+            PackedAnalysisNodeData::GenericFunctionCall { .. } => self.resolve_generic_function(idx)?,
+            _ => unreachable!(),
+        };
+
+        let node = &mut self.nodes[idx];
+        node.resolved_type = Some(resolved_type);
+        node.data = data;
+        Ok(true)
+    }
+
+    fn resolve_generic_function(&self, idx: usize) -> Result<(ValueType, PackedAnalysisNodeData<'table>), Box<dyn Error>> {
+        let PackedAnalysisNodeData::GenericFunctionCall { name, arg_idxs, params, constraint, fn_spec, ret_idx } = &self.nodes[idx].data else { unreachable!() };
+
+        // with only untyped generic arguments, the type parameter falls back
+        // like an untyped value would
+        let generic_type = match self.get_generic_type(arg_idxs, params)? {
+            Some(generic_type) => generic_type,
+            None => {
+                let has_float = arg_idxs.iter().zip(params.iter()).any(|(idx, param)| *param == GenericParam::Generic && self.contains_untyped_float(*idx));
+                if has_float { ValueType::F64 } else { ValueType::I32 }
+            },
+        };
+
+        if !constraint.allows(generic_type) {
+            return Err(Box::new(AnalysisError::GenericConstraintNotMet { name: name.clone(), value_type: generic_type, constraint: *constraint }))
+        }
+
+        let concrete_params = params.iter().map(|param| match param {
+            GenericParam::Concrete(value_type) => *value_type,
+            GenericParam::Generic => generic_type,
+        }).collect::<Box<[ValueType]>>();
+
+        let instance = fn_spec(&concrete_params).map_err(|msg| AnalysisError::GenericSpecFailed { name: name.clone(), msg })?;
+        if instance.get_params() != &*concrete_params {
+            return Err(Box::new(AnalysisError::GenericInstanceMismatch { name: name.clone(), expected: concrete_params, got: instance.get_params().into() }))
+        }
+
+        Ok(Self::make_function_call(name, &concrete_params, instance.get_ret_types().into(), AnalysisFnSpec::Generic(instance.get_choice().clone()), arg_idxs, *ret_idx)?)
+    }
+
+    fn resolve_overload(&self, idx: usize) -> Result<(ValueType, PackedAnalysisNodeData<'table>), Box<dyn Error>> {
+        let PackedAnalysisNodeData::OverloadedFunctionCall { name, arg_idxs, overloads, ret_idx } = &self.nodes[idx].data else { unreachable!() };
+        let arg_kinds = arg_idxs.iter().map(|arg_idx| self.get_overload_arg(*arg_idx)).collect::<Vec<_>>();
+
+        let mut viable = Vec::<(&'table Binding, Box<[ValueType]>, Vec<u32>)>::new();
+        for binding in overloads {
+            let params = binding.get_call_params(arg_idxs.len()).ok_or(AnalysisError::BadAnalysis)?;
+            if let Some(mut costs) = params.iter().zip(&arg_kinds).map(|(param, arg)| arg.get_cost(*param)).collect::<Option<Vec<_>>>() {
+                // variadic overloads are only picked if nothing else matches
+                // as well
+                costs.push(matches!(binding, Binding::Function { variadic: Some(_), .. }) as u32);
+                viable.push((*binding, params, costs));
+            }
+        }
+
+        // the chosen overload must be at least as good as every other one for
+        // each argument, and better for at least one
+        let best = viable.iter().find(|(binding, _, costs)| {
+            viable.iter().all(|(other_binding, _, other_costs)| {
+                std::ptr::eq(*binding, *other_binding) || (
+                    costs.iter().zip(other_costs).all(|(a, b)| a <= b) &&
+                    costs.iter().zip(other_costs).any(|(a, b)| a < b)
+                )
+            })
+        });
+
+        let Some((Binding::Function { ret_types, fn_spec, .. }, params, _)) = best else {
+            return Err(Box::new(if viable.is_empty() {
+                AnalysisError::NoMatchingOverload { name: name.clone(), arg_types: arg_kinds.iter().map(OverloadArg::get_value_type).collect() }
+            } else {
+                AnalysisError::AmbiguousOverload { name: name.clone(), candidates: viable.into_iter().map(|(_, params, _)| params).collect() }
+            }))
+        };
+
+        Ok(Self::make_function_call(name, params, ret_types.clone(), AnalysisFnSpec::Binding(fn_spec), arg_idxs, *ret_idx)?)
+    }
+
+    fn check_integer_only_ops(&self) -> Result<(), Box<dyn Error>> {
+        for node in &self.nodes {
+            if let PackedAnalysisNodeData::BinaryOperation { operator, .. } = &node.data &&
+                operator.is_integer_only() &&
+                let Some(value_type) = node.resolved_type &&
+                !value_type.is_integer()
+            {
+                return Err(Box::new(AnalysisError::InvalidTypeForOp { value_type }));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Integer bases take any integer exponent. Float bases take an exponent of
+    /// the same float type, or an integer type whose values all fit in an i32
+    /// (`llvm.powi`)
+    fn check_pow_types(&self) -> Result<(), Box<dyn Error>> {
+        for node in &self.nodes {
+            if let PackedAnalysisNodeData::BinaryOperation { operator: BinaryOperator::Pow, right_idx, .. } = &node.data &&
+                let Some(base_type) = node.resolved_type &&
+                let Some(exponent_type) = self.nodes[*right_idx].resolved_type
+            {
+                if base_type == ValueType::Bool {
+                    return Err(Box::new(AnalysisError::InvalidTypeForOp { value_type: base_type }));
+                }
+
+                let is_valid = if base_type.is_float() {
+                    exponent_type == base_type || matches!(exponent_type, ValueType::U8 | ValueType::I8 | ValueType::U16 | ValueType::I16 | ValueType::I32)
+                } else {
+                    exponent_type.is_integer()
+                };
+
+                if !is_valid {
+                    return Err(Box::new(AnalysisError::InvalidExponentType { base_type, exponent_type }));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The value must be comparable with each item, ranges can't be boolean,
+    /// and values must be implicitly castable to their set's type
+    fn check_membership_types(&self) -> Result<(), Box<dyn Error>> {
+        for node in &self.nodes {
+            if let PackedAnalysisNodeData::Membership { set, value_idx, item_idxs } = &node.data &&
+                let Some(value_type) = self.nodes[*value_idx].resolved_type
+            {
+                for item_idx in item_idxs {
+                    let Some(item_type) = self.nodes[*item_idx].resolved_type else { continue };
+                    if matches!(set, MembershipSet::Range { .. }) && (value_type == ValueType::Bool || item_type == ValueType::Bool) {
+                        return Err(Box::new(AnalysisError::InvalidTypeForOp { value_type: ValueType::Bool }));
+                    }
+
+                    // integers with different signedness are compared without
+                    // casting to a common type
+                    if !(value_type.is_integer() && item_type.is_integer() && value_type.is_signed() != item_type.is_signed()) {
+                        ValueType::widen(value_type, item_type)?;
+                    }
+                }
+            }
+
+            if let PackedAnalysisNodeData::SetMembership { name, value_idx, set, .. } = &node.data &&
+                let Some(value_type) = self.nodes[*value_idx].resolved_type &&
+                value_type != set.get_value_type() &&
+                !value_type.can_implicit_cast_to(&set.get_value_type())
+            {
+                return Err(Box::new(AnalysisError::SetBadValueType { name: name.clone(), expected: set.get_value_type(), got: value_type }));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Gets the value of a literal, including negated literals, which are
+    /// folded as long as the negation doesn't overflow
+    pub fn get_const_value(&self, idx: usize) -> Option<Value> {
+        let node = &self.nodes[idx];
+        match &node.data {
+            PackedAnalysisNodeData::TypedValue { value } => Some(*value),
+            PackedAnalysisNodeData::UnaryOperation { operator: UnaryOperator::Negate, right_idx } => {
+                match (self.get_const_value(*right_idx)?, node.resolved_type?) {
+                    (Value::F32 { inner }, ValueType::F32) => Some(Value::F32 { inner: -inner }),
+                    (Value::F64 { inner }, ValueType::F64) => Some(Value::F64 { inner: -inner }),
+                    (value, value_type) => Value::from_int_key(value.get_int_key()?.checked_neg()?, value_type),
+                }
+            },
+            _ => None,
+        }
+    }
+
+    fn check_math_intrinsic_types(&self) -> Result<(), Box<dyn Error>> {
+        for node in &self.nodes {
+            if let PackedAnalysisNodeData::MathIntrinsicCall { intrinsic, .. } = &node.data &&
+                let Some(value_type) = node.resolved_type &&
+                !intrinsic.get_constraint().allows(value_type)
+            {
+                return Err(Box::new(AnalysisError::GenericConstraintNotMet { name: intrinsic.get_name().into(), value_type, constraint: intrinsic.get_constraint() }));
+            }
+        }
+
         Ok(())
     }
 
@@ -452,18 +1085,33 @@ impl<'table> PackedAnalysisTree<'table> {
             PackedAnalysisNodeData::TypedValue { .. } |
             PackedAnalysisNodeData::UntypedValue { .. } |
             PackedAnalysisNodeData::Variable { .. } => { },
-            PackedAnalysisNodeData::FunctionCall { args, fn_spec: _ } => {
+            PackedAnalysisNodeData::FunctionCall { args, .. } => {
                 for PackedAnalysisFunctionArg { idx, expected_type: _ } in args {
                     self.print_node_to_stderr(*idx, depth + 1);
                 }
             },
-            PackedAnalysisNodeData::UnaryOperation { operator: _, right_idx } => {
+            PackedAnalysisNodeData::OverloadedFunctionCall { arg_idxs, .. } |
+            PackedAnalysisNodeData::GenericFunctionCall { arg_idxs, .. } |
+            PackedAnalysisNodeData::MathIntrinsicCall { arg_idxs, .. } |
+            PackedAnalysisNodeData::ComparisonChain { operand_idxs: arg_idxs, .. } => {
+                for idx in arg_idxs {
+                    self.print_node_to_stderr(*idx, depth + 1);
+                }
+            },
+            PackedAnalysisNodeData::UnaryOperation { operator: _, right_idx } |
+            PackedAnalysisNodeData::SetMembership { value_idx: right_idx, .. } => {
                 self.print_node_to_stderr(*right_idx, depth + 1);
             },
             PackedAnalysisNodeData::BinaryOperation { operator: _, left_idx, right_idx } => {
                 self.print_node_to_stderr(*left_idx, depth + 1);
                 self.print_node_to_stderr(*right_idx, depth + 1);
             },
+            PackedAnalysisNodeData::Membership { set: _, value_idx, item_idxs } => {
+                self.print_node_to_stderr(*value_idx, depth + 1);
+                for idx in item_idxs {
+                    self.print_node_to_stderr(*idx, depth + 1);
+                }
+            },
             PackedAnalysisNodeData::Ternary { cond_idx, left_idx, right_idx } => {
                 self.print_node_to_stderr(*cond_idx, depth + 1);
                 self.print_node_to_stderr(*left_idx, depth + 1);
@@ -496,4 +1144,215 @@ impl<'table> PackedAnalysisTree<'table> {
             None => Err(AnalysisError::BadAnalysis),
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use crate::common::{binding::{FnSpecCallArg, GenericFnInstance, ParamDef}, error::CommonError};
+
+    use super::*;
+
+    fn analyse<'table>(source: &str, table: &'table Table) -> Result<PackedAnalysisTree<'table>, Box<dyn Error>> {
+        let ast = Expression::from_src(source).map_err(|err| err.to_string())?;
+        PackedAnalysisTree::from_ast(&ast, table)
+    }
+
+    fn expr_type(source: &str, table: &Table) -> ValueType {
+        analyse(source, table).unwrap_or_else(|err| panic!("failed to analyse {source:?}: {err}")).get_expr_type().unwrap()
+    }
+
+    fn analysis_error(source: &str, table: &Table) -> AnalysisError {
+        match analyse(source, table) {
+            Ok(_) => panic!("expected {source:?} to fail analysis"),
+            Err(err) => *err.downcast::<AnalysisError>().unwrap_or_else(|err| panic!("expected an AnalysisError, got {err}")),
+        }
+    }
+
+    #[test]
+    fn saturating_and_wrapping_ops_are_integer_only() {
+        let table = Table::new();
+        assert_eq!(expr_type("1u8 +| 2u8", &table), ValueType::U8);
+        assert_eq!(expr_type("1 *% 2i64", &table), ValueType::I64);
+        assert!(matches!(analysis_error("1.0 +| 2.0", &table), AnalysisError::InvalidTypeForOp { value_type: ValueType::F64 }));
+        assert!(matches!(analysis_error("1f32 -% 2", &table), AnalysisError::InvalidTypeForOp { value_type: ValueType::F32 }));
+    }
+
+    #[test]
+    fn multiple_return_values_must_be_selected() {
+        let mut table = Table::new();
+        table.add_function("divmod".into(), (|a, b| (a / b, a % b)) as fn(u32, u32) -> (u32, u32)).unwrap();
+        table.add_function("split".into(), (|x| (x as u8, x < 0.0)) as fn(f64) -> (u8, bool)).unwrap();
+
+        assert_eq!(expr_type("divmod(7u32, 2u32).1", &table), ValueType::U32);
+        assert_eq!(expr_type("split(1.5).0", &table), ValueType::U8);
+        assert_eq!(expr_type("split(1.5).1", &table), ValueType::Bool);
+        assert!(matches!(analysis_error("divmod(7u32, 2u32)", &table), AnalysisError::MissingReturnIndex { count: 2, .. }));
+        assert!(matches!(analysis_error("divmod(7u32, 2u32).2", &table), AnalysisError::BadReturnIndex { idx: 2, count: 2, .. }));
+    }
+
+    #[test]
+    fn overloads_prefer_the_closest_type() {
+        let mut table = Table::new();
+        table.add_function("magnitude".into(), i32::abs).unwrap();
+        table.add_function("magnitude".into(), f64::abs).unwrap();
+        table.add_function("magnitude".into(), |x: u64| x).unwrap();
+        assert_eq!(expr_type("magnitude(-3i16)", &table), ValueType::I32);
+        assert_eq!(expr_type("magnitude(5u8)", &table), ValueType::I32);
+        assert_eq!(expr_type("magnitude(5u64)", &table), ValueType::U64);
+        assert_eq!(expr_type("magnitude(5)", &table), ValueType::I32);
+        assert_eq!(expr_type("magnitude(5.0)", &table), ValueType::F64);
+        assert_eq!(expr_type("magnitude(5f32)", &table), ValueType::F64);
+        assert_eq!(expr_type("magnitude(5i64)", &table), ValueType::F64);
+    }
+
+    #[test]
+    fn overloads_prefer_integer_over_float_parameters() {
+        let mut table = Table::new();
+        table.add_function("scale".into(), (|x| x) as fn(f32) -> f32).unwrap();
+        table.add_function("scale".into(), (|x| x) as fn(i128) -> i128).unwrap();
+        assert_eq!(expr_type("scale(1u8)", &table), ValueType::I128);
+        assert_eq!(expr_type("scale(1)", &table), ValueType::I128);
+        assert_eq!(expr_type("scale(1.0)", &table), ValueType::F32);
+    }
+
+    #[test]
+    fn overloads_that_are_better_for_different_arguments_are_ambiguous() {
+        let mut table = Table::new();
+        table.add_function("pick".into(), (|x, _| x) as fn(i32, f64) -> i32).unwrap();
+        table.add_function("pick".into(), (|_, y| y) as fn(f64, i32) -> i32).unwrap();
+        assert!(matches!(analysis_error("pick(1i16, 2i16)", &table), AnalysisError::AmbiguousOverload { .. }));
+        assert_eq!(expr_type("pick(1i16, 2.0)", &table), ValueType::I32);
+    }
+
+    #[test]
+    fn generic_functions_are_specialised_for_the_argument_type() {
+        let mut table = Table::new();
+        table.add_generic_function("double".into(), TypeConstraint::Numeric, &[GenericParam::Generic], |types| match types[0] {
+            ValueType::U8 => Ok(GenericFnInstance::from_function(|x: u8| x.wrapping_mul(2))),
+            ValueType::U16 => Ok(GenericFnInstance::from_function(|x: u16| x.wrapping_mul(2))),
+            ValueType::I32 => Ok(GenericFnInstance::from_function(|x: i32| x.wrapping_mul(2))),
+            ValueType::F64 => Ok(GenericFnInstance::from_function(|x: f64| x * 2.0)),
+            value_type => Err(format!("{value_type:?} isn't supported")),
+        }).unwrap();
+        table.add_generic_function("shift".into(), TypeConstraint::Integer, &[GenericParam::Generic, GenericParam::Concrete(ValueType::U32)], |_| {
+            Ok(GenericFnInstance::from_function(|x: i32, _: u32| x))
+        }).unwrap();
+
+        assert_eq!(expr_type("double(3u8)", &table), ValueType::U8);
+        assert_eq!(expr_type("double(3u8 + 2u16)", &table), ValueType::U16);
+        assert_eq!(expr_type("double(3)", &table), ValueType::I32);
+        assert_eq!(expr_type("double(1.5)", &table), ValueType::F64);
+        assert_eq!(expr_type("shift(1, 2)", &table), ValueType::I32);
+    }
+
+    #[test]
+    fn generic_function_errors() {
+        let mut table = Table::new();
+        table.add_generic_function("double".into(), TypeConstraint::Numeric, &[GenericParam::Generic], |types| match types[0] {
+            ValueType::I32 => Ok(GenericFnInstance::from_function(|x: i32| x.wrapping_mul(2))),
+            value_type => Err(format!("{value_type:?} isn't supported")),
+        }).unwrap();
+        table.add_generic_function("shift".into(), TypeConstraint::Integer, &[GenericParam::Generic, GenericParam::Concrete(ValueType::U32)], |_| {
+            Ok(GenericFnInstance::from_function(|x: i32, _: u32| x))
+        }).unwrap();
+
+        assert!(matches!(analysis_error("double(true)", &table), AnalysisError::GenericConstraintNotMet { value_type: ValueType::Bool, constraint: TypeConstraint::Numeric, .. }));
+        assert!(matches!(analysis_error("double(3i64)", &table), AnalysisError::GenericSpecFailed { .. }));
+        assert!(matches!(analysis_error("shift(1.5, 2)", &table), AnalysisError::GenericConstraintNotMet { value_type: ValueType::F64, .. }));
+        assert!(matches!(analysis_error("shift(1u8, 2)", &table), AnalysisError::GenericInstanceMismatch { .. }));
+    }
+
+    #[test]
+    fn variadic_functions_require_their_fixed_arguments() {
+        let mut table = Table::new();
+        let scaled_sum = (|_, _, _| 0.0) as fn(f64, *const f64, usize) -> f64;
+        table.add_function_map("scaled_sum".into(), scaled_sum, [FnSpecCallArg::MappedArgument { param_idx: 0 }, FnSpecCallArg::from_variadic::<f64>(), FnSpecCallArg::VariadicLength]).unwrap();
+
+        assert_eq!(expr_type("scaled_sum(1.0)", &table), ValueType::F64);
+        assert_eq!(expr_type("scaled_sum(1.0, 2, 3u8, 4f32)", &table), ValueType::F64);
+        assert!(matches!(analysis_error("scaled_sum()", &table), AnalysisError::BadVariadicArguments { min_argc: 1, actual_argc: 0, .. }));
+    }
+
+    #[test]
+    fn named_argument_errors() {
+        let mut table = Table::new();
+        table.add_function("window".into(), (|x, _, _| x) as fn(i32, i32, i32) -> i32).unwrap();
+        table.set_param_defs("window", &[
+            ParamDef { name: "x".into(), default: None },
+            ParamDef { name: "size".into(), default: Some(10i32.into()) },
+            ParamDef { name: "offset".into(), default: Some(0i32.into()) },
+        ]).unwrap();
+
+        assert_eq!(expr_type("window(offset: 1, x: 2)", &table), ValueType::I32);
+        assert!(matches!(analysis_error("window(x: 1, 2)", &table), AnalysisError::PositionalAfterNamedArgument { .. }));
+        assert!(matches!(analysis_error("window(1, width: 2)", &table), AnalysisError::UnknownNamedArgument { arg, .. } if arg == "width"));
+        assert!(matches!(analysis_error("window(1, x: 2)", &table), AnalysisError::DuplicateArgument { param, .. } if param == "x"));
+        assert!(matches!(analysis_error("window(size: 2)", &table), AnalysisError::MissingArgument { param, .. } if param == "x"));
+    }
+
+    #[test]
+    fn param_defs_must_match_the_function() {
+        let mut table = Table::new();
+        table.add_function("window".into(), (|x, _| x) as fn(i32, i32) -> i32).unwrap();
+        let def = |name: &str, default: Option<Value>| ParamDef { name: name.into(), default };
+
+        assert!(matches!(table.set_param_defs("window", &[def("x", None)]), Err(CommonError::ParamDefsBadCount { expected: 2, got: 1, .. })));
+        assert!(matches!(table.set_param_defs("window", &[def("x", None), def("x", None)]), Err(CommonError::ParamDefsDuplicateName { .. })));
+        assert!(matches!(table.set_param_defs("window", &[def("x", None), def("size", Some(1.0.into()))]), Err(CommonError::ParamDefaultBadType { .. })));
+        assert!(matches!(table.set_param_defs("missing", &[]), Err(CommonError::ParamDefsNotFunction { .. })));
+    }
+
+    #[test]
+    fn math_intrinsic_errors() {
+        let mut table = Table::new();
+        assert_eq!(expr_type("sqrt(2f32)", &table), ValueType::F32);
+        assert!(matches!(analysis_error("sqrt(2u32)", &table), AnalysisError::GenericConstraintNotMet { value_type: ValueType::U32, constraint: TypeConstraint::Float, .. }));
+        assert!(matches!(analysis_error("abs(true)", &table), AnalysisError::GenericConstraintNotMet { value_type: ValueType::Bool, .. }));
+        assert!(matches!(analysis_error("clamp(1, 2)", &table), AnalysisError::BadArguments { expected_argc: 3, actual_argc: 2, .. }));
+        assert!(matches!(analysis_error("min(a: 1, b: 2)", &table), AnalysisError::UnknownNamedArgument { .. }));
+
+        table.set_math_intrinsics_enabled(false);
+        assert!(matches!(analysis_error("sqrt(2.0)", &table), AnalysisError::UnknownBinding { .. }));
+    }
+
+    #[test]
+    fn float_exponents_must_fit_in_an_i32() {
+        let table = Table::new();
+        assert_eq!(expr_type("2.0 ** 3u16", &table), ValueType::F64);
+        assert_eq!(expr_type("2.0 ** 3i32", &table), ValueType::F64);
+        assert_eq!(expr_type("2.0 ** -3", &table), ValueType::F64);
+        assert_eq!(expr_type("2f32 ** 0.5f32", &table), ValueType::F32);
+        assert!(matches!(analysis_error("2.0 ** 3u32", &table), AnalysisError::InvalidExponentType { exponent_type: ValueType::U32, .. }));
+        assert!(matches!(analysis_error("2.0 ** 3i64", &table), AnalysisError::InvalidExponentType { exponent_type: ValueType::I64, .. }));
+        assert!(matches!(analysis_error("2f32 ** 0.5f64", &table), AnalysisError::InvalidExponentType { .. }));
+        assert!(matches!(analysis_error("true ** 2", &table), AnalysisError::InvalidTypeForOp { value_type: ValueType::Bool }));
+    }
+
+    #[test]
+    fn membership_items_are_unified_with_the_value() {
+        let table = Table::new();
+        let tree = analyse("200 in [1u8, 2u8]", &table).unwrap();
+        assert_eq!(tree.get_node_type(0).unwrap(), ValueType::U8);
+        let tree = analyse("5u8 in [-1, 7]", &table).unwrap();
+        assert_eq!(tree.get_node_type(2).unwrap(), ValueType::I8);
+        assert_eq!(tree.get_node_type(3).unwrap(), ValueType::U8);
+        assert_eq!(expr_type("-1i8 in [255u8, 1u16]", &table), ValueType::Bool);
+        assert_eq!(expr_type("200u8 in -1i8..=127i8", &table), ValueType::Bool);
+        assert!(matches!(analysis_error("true in false..=true", &table), AnalysisError::InvalidTypeForOp { value_type: ValueType::Bool }));
+        assert!(matches!(analysis_error("1u8 in 0..true", &table), AnalysisError::InvalidTypeForOp { value_type: ValueType::Bool }));
+    }
+
+    #[test]
+    fn negated_literals_are_constants() {
+        let table = Table::new();
+        let tree = analyse("-5i8", &table).unwrap();
+        assert_eq!(tree.get_const_value(1), Some(Value::I8 { inner: -5 }));
+        let tree = analyse("-128u8", &table).unwrap();
+        assert_eq!(tree.get_const_value(1), Some(Value::I8 { inner: -128 }));
+        let tree = analyse("-200u8", &table).unwrap();
+        assert_eq!(tree.get_const_value(1), None);
+        let tree = analyse("-1.5f32", &table).unwrap();
+        assert_eq!(tree.get_const_value(1), Some(Value::F32 { inner: -1.5 }));
+        let tree = analyse("-(1i8 + 2i8)", &table).unwrap();
+        assert_eq!(tree.get_const_value(3), None);
+    }
+}
@@ -12,11 +12,19 @@ pub enum UnaryOperator {
 
 #[derive(Debug, Clone)]
 pub enum BinaryOperator {
+    /// `base ** exponent`; the result has the same type as the base
+    Pow,
     Mul,
     Div,
     Mod,
     Add,
     Sub,
+    SaturatingAdd,
+    SaturatingSub,
+    SaturatingMul,
+    WrappingAdd,
+    WrappingSub,
+    WrappingMul,
     Equals,
     NotEquals,
     LesserThanEquals,
@@ -27,19 +35,122 @@ pub enum BinaryOperator {
     LogicalOr,
 }
 
+impl BinaryOperator {
+    pub const fn is_integer_only(&self) -> bool {
+        matches!(self,
+            Self::SaturatingAdd |
+            Self::SaturatingSub |
+            Self::SaturatingMul |
+            Self::WrappingAdd |
+            Self::WrappingSub |
+            Self::WrappingMul
+        )
+    }
+}
+
+/// The right side of an `in` operator
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MembershipSet {
+    /// `[a, b, c]`
+    List,
+    /// `start..end`, or `start..=end` if inclusive
+    Range { inclusive: bool },
+}
+
+/// A function call argument, optionally named, e.g. `x` or `size: 4`
+#[derive(Debug)]
+pub struct CallArgument {
+    pub name: Option<String>,
+    pub value: Expression,
+}
+
 #[derive(Debug)]
 pub enum Expression {
     TypedValue { value: Value },
     UntypedValue { value: UntypedValue },
-    FunctionCall { name: String, arguments: Vec<Expression> },
+    /// `ret_idx` selects one of the return values of a function with multiple
+    /// return values, e.g. `divmod(a, b).1`
+    FunctionCall { name: String, arguments: Vec<CallArgument>, ret_idx: Option<usize> },
     UnaryOperation { operator: UnaryOperator, right: Box<Expression> },
     BinaryOperation { operator: BinaryOperator, left: Box<Expression>, right: Box<Expression> },
     Binding { name: String },
     Ternary { cond: Box<Expression>, left: Box<Expression>, right: Box<Expression> },
+    /// `a < b <= c`, which is the same as `a < b && b <= c`, except that `b`
+    /// is only evaluated once. Has at least 2 operators; single comparisons
+    /// are [Self::BinaryOperation]s
+    ComparisonChain { operands: Vec<Expression>, operators: Vec<BinaryOperator> },
+    /// `value in [a, b, c]`, or `value in start..end`, in which case `items`
+    /// is `[start, end]`
+    Membership { value: Box<Expression>, set: MembershipSet, items: Vec<Expression> },
+    /// `value in name`, where `name` is a set added to the Table
+    SetMembership { value: Box<Expression>, name: String },
 }
 
 impl Expression {
     pub fn from_src<'src>(source: &'src str) -> Result<Expression, Box<dyn Error + 'src>> {
         Ok(parser::ExpressionParser::new().parse(source)?)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Expression {
+        Expression::from_src(source).unwrap_or_else(|err| panic!("failed to parse {source:?}: {err}"))
+    }
+
+    #[test]
+    fn method_calls_prepend_the_receiver() {
+        let Expression::FunctionCall { name, arguments, ret_idx: None } = parse("x.clamp(0, max: 1)") else { panic!("expected a function call") };
+        assert_eq!(name, "clamp");
+        assert!(matches!(&arguments[..], [
+            CallArgument { name: None, value: Expression::Binding { name: receiver } },
+            CallArgument { name: None, value: Expression::UntypedValue { .. } },
+            CallArgument { name: Some(max), value: Expression::UntypedValue { .. } },
+        ] if receiver == "x" && max == "max"));
+
+        let Expression::FunctionCall { name, arguments, .. } = parse("(x + 1).abs()") else { panic!("expected a function call") };
+        assert_eq!(name, "abs");
+        assert!(matches!(&arguments[..], [CallArgument { name: None, value: Expression::BinaryOperation { operator: BinaryOperator::Add, .. } }]));
+    }
+
+    #[test]
+    fn method_calls_can_be_chained() {
+        let Expression::FunctionCall { name, arguments, ret_idx: Some(1) } = parse("divmod(x, 3).0.abs().divmod(2).1") else { panic!("expected a function call") };
+        assert_eq!(name, "divmod");
+        let [CallArgument { value: Expression::FunctionCall { name: inner, arguments, ret_idx: None }, .. }, _] = &arguments[..] else { panic!("expected a nested call") };
+        assert_eq!(inner, "abs");
+        assert!(matches!(&arguments[..], [CallArgument { value: Expression::FunctionCall { ret_idx: Some(0), .. }, .. }]));
+    }
+
+    #[test]
+    fn literals_are_not_receivers() {
+        assert!(Expression::from_src("1.abs()").is_err());
+        assert!(Expression::from_src("x.1").is_err());
+    }
+
+    #[test]
+    fn negative_exponents_need_no_parentheses() {
+        let Expression::BinaryOperation { operator: BinaryOperator::Pow, right, .. } = parse("x ** -1") else { panic!("expected a power") };
+        assert!(matches!(*right, Expression::UnaryOperation { operator: UnaryOperator::Negate, .. }));
+
+        // `-` binds looser than `**` on both sides, like in Python
+        let Expression::UnaryOperation { operator: UnaryOperator::Negate, right } = parse("-x ** -y ** 2") else { panic!("expected a negation") };
+        let Expression::BinaryOperation { operator: BinaryOperator::Pow, right, .. } = *right else { panic!("expected a power") };
+        let Expression::UnaryOperation { operator: UnaryOperator::Negate, right } = *right else { panic!("expected a negation") };
+        assert!(matches!(*right, Expression::BinaryOperation { operator: BinaryOperator::Pow, .. }));
+    }
+
+    #[test]
+    fn comparisons_are_chained() {
+        assert!(matches!(parse("a < b"), Expression::BinaryOperation { operator: BinaryOperator::LesserThan, .. }));
+
+        let Expression::ComparisonChain { operands, operators } = parse("a < b + 1 <= c == d") else { panic!("expected a comparison chain") };
+        assert_eq!(operands.len(), 4);
+        assert!(matches!(operands[1], Expression::BinaryOperation { operator: BinaryOperator::Add, .. }));
+        assert!(matches!(operators[..], [BinaryOperator::LesserThan, BinaryOperator::LesserThanEquals, BinaryOperator::Equals]));
+
+        // chains bind tighter than logical operators
+        assert!(matches!(parse("a < b < c && d"), Expression::BinaryOperation { operator: BinaryOperator::LogicalAnd, .. }));
+    }
+}
@@ -1,14 +1,18 @@
 use std::fmt::{Display, Formatter};
 
+use super::ast_node::{BinaryOperator, CallArgument, Expression};
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum HotEvalParserError {
     BadLiteral { type_str: &'static str },
+    BadReturnValueSelection,
 }
 
 impl Display for HotEvalParserError {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
         write!(f, "{}", match self {
             Self::BadLiteral { type_str } => format!("Invalid literal for type {}", type_str),
+            Self::BadReturnValueSelection => "Return values can only be selected from function calls".into(),
         })
     }
 }
@@ -22,7 +26,7 @@ pub struct UnevaluatedNumberLiteral {
     &value[0..value.len()-amount]
 }*/
 
-pub fn slice_after_begin<'a>(value: &'a str, amount: usize) -> &'a str {
+pub fn slice_after_begin(value: &str, amount: usize) -> &str {
     &value[amount..value.len()]
 }
 
@@ -30,6 +34,35 @@ pub fn filter_str_chars(s: &str, c: char) -> String {
     s.chars().filter(|&o| o != c).collect()
 }
 
-pub fn make_bad_literal_error<'a>(type_str: &'static str) -> HotEvalParserError {
+pub fn make_bad_literal_error(type_str: &'static str) -> HotEvalParserError {
     HotEvalParserError::BadLiteral { type_str }
-}
\ No newline at end of file
+}
+
+pub fn select_return_value(call: Expression, idx: usize) -> Result<Expression, HotEvalParserError> {
+    match call {
+        Expression::FunctionCall { name, arguments, ret_idx: None } => Ok(Expression::FunctionCall { name, arguments, ret_idx: Some(idx) }),
+        _ => Err(HotEvalParserError::BadReturnValueSelection),
+    }
+}
+
+pub fn prepend_receiver(receiver: Expression, arguments: Option<Vec<CallArgument>>) -> Vec<CallArgument> {
+    let mut all_arguments = vec![CallArgument { name: None, value: receiver }];
+    all_arguments.extend(arguments.unwrap_or_default());
+    all_arguments
+}
+
+pub fn make_comparison(first: Expression, rest: Vec<(BinaryOperator, Expression)>) -> Expression {
+    if rest.len() == 1 {
+        let (operator, right) = rest.into_iter().next().unwrap();
+        return Expression::BinaryOperation { operator, left: first.into(), right: right.into() };
+    }
+
+    let mut operands = vec![first];
+    let mut operators = Vec::with_capacity(rest.len());
+    for (operator, operand) in rest {
+        operators.push(operator);
+        operands.push(operand);
+    }
+
+    Expression::ComparisonChain { operands, operators }
+}
@@ -0,0 +1,40 @@
+/// The result of an integer division by zero when using
+/// [ArithmeticMode::Defined]. Note that the remainder of a division by zero is
+/// always the dividend, since `x == 0 * q + r` only holds for `r == x`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DivByZeroResult {
+    /// `x / 0 == 0`
+    Zero,
+    /// `x / 0` is the maximum value of the type if `x` is positive, the
+    /// minimum value of the type if `x` is negative, or zero if `x` is zero
+    Saturate,
+}
+
+/// Controls how operations that can't be represented by the resulting type are
+/// lowered to LLVM IR
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArithmeticMode {
+    /// Every operation has a defined result for every input:
+    /// - integer addition, subtraction, multiplication and negation wrap
+    /// - integer division by zero is decided by `div_by_zero`
+    /// - signed integer division of the minimum value by -1 wraps
+    /// - float to integer casts saturate, and NaN becomes zero (same as `as`
+    ///   casts in Rust)
+    Defined { div_by_zero: DivByZeroResult },
+    /// Operations are lowered to the plain LLVM instructions, which is slightly
+    /// faster, but division by zero, signed division overflow and out of range
+    /// float to integer casts produce poison values (undefined behaviour).
+    /// Only use this if the inputs are guaranteed to be in range
+    Unchecked,
+    /// Operations that overflow, divide by zero, or cast a NaN or out of range
+    /// float to an integer abort the evaluation. The error, and the node that
+    /// caused it, is stored in the Slab and can be retrieved with
+    /// [crate::codegen::compiled_expression::call_checked]
+    Checked,
+}
+
+impl Default for ArithmeticMode {
+    fn default() -> Self {
+        Self::Defined { div_by_zero: DivByZeroResult::Zero }
+    }
+}
\ No newline at end of file
@@ -2,6 +2,8 @@ use inkwell::{builder::Builder, context::Context, execution_engine::ExecutionEng
 
 use crate::common::slab::Slab;
 
+use super::arithmetic_mode::ArithmeticMode;
+
 pub struct CodegenContext<'ctx, 'build> {
     pub llvm_context: &'ctx Context,
     pub module: &'build Module<'ctx>,
@@ -9,4 +11,5 @@ pub struct CodegenContext<'ctx, 'build> {
     pub execution_engine: &'build ExecutionEngine<'ctx>,
     pub func: &'build FunctionValue<'ctx>,
     pub slab: &'build Slab,
+    pub arithmetic_mode: ArithmeticMode,
 }
\ No newline at end of file
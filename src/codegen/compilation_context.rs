@@ -1,15 +1,16 @@
 use std::error::Error;
 
-use inkwell::{OptimizationLevel, context::Context, execution_engine::ExecutionEngine, module::Module, support::LLVMString};
+use inkwell::{AddressSpace, OptimizationLevel, context::Context, execution_engine::ExecutionEngine, module::Module, support::LLVMString};
 
 use crate::{analysis::packed_analysis_tree::PackedAnalysisTree, ast::ast_node::Expression, codegen::{codegen_context::CodegenContext, ir_value::IRValue, ir_value_type::IRValueType}, common::{slab::Slab, table::Table, value_type::ValueType}};
 
-use super::compiled_expression::CompiledExpression;
+use super::{arithmetic_mode::ArithmeticMode, compiled_expression::CompiledExpression};
 
 pub struct CompilationContext<'ctx> {
     execution_engine: ExecutionEngine<'ctx>,
     module: Module<'ctx>,
     llvm_context: &'ctx Context,
+    arithmetic_mode: ArithmeticMode,
     next: usize,
 }
 
@@ -17,7 +18,17 @@ impl<'ctx> CompilationContext<'ctx> {
     pub fn new(llvm_context: &'ctx Context, comp_ctx_id: usize) -> Result<Self, LLVMString> {
         let module = llvm_context.create_module(&format!("hot_eval_module_{comp_ctx_id}"));
         let execution_engine = module.create_jit_execution_engine(OptimizationLevel::Aggressive)?;
-        Ok(Self { llvm_context, module, execution_engine, next: 0 })
+        Ok(Self { llvm_context, module, execution_engine, arithmetic_mode: ArithmeticMode::default(), next: 0 })
+    }
+
+    pub const fn get_arithmetic_mode(&self) -> ArithmeticMode {
+        self.arithmetic_mode
+    }
+
+    /// Changes how arithmetic is lowered for expressions compiled after this
+    /// call. Already compiled expressions are not affected
+    pub fn set_arithmetic_mode(&mut self, arithmetic_mode: ArithmeticMode) {
+        self.arithmetic_mode = arithmetic_mode;
     }
 
     pub fn compile_analysed_ast(&mut self, aast: PackedAnalysisTree, slab: Slab) -> Result<CompiledExpression<'_>, Box<dyn Error>> {
@@ -28,9 +39,11 @@ impl<'ctx> CompilationContext<'ctx> {
 
         let fn_name = format!("hot_eval_fn_{id}");
         let fn_ast_type = aast.get_expr_type()?;
-        let fn_type = match IRValueType::from_value_type(&fn_ast_type, &self.llvm_context) {
-            IRValueType::Int { llvm, .. } => llvm.fn_type(&[], false),
-            IRValueType::Float { llvm } => llvm.fn_type(&[], false),
+        // inputs pointer and context pointer
+        let ptr_type = self.llvm_context.ptr_type(AddressSpace::default()).into();
+        let fn_type = match IRValueType::from_value_type(&fn_ast_type, self.llvm_context) {
+            IRValueType::Int { llvm, .. } => llvm.fn_type(&[ptr_type, ptr_type], false),
+            IRValueType::Float { llvm } => llvm.fn_type(&[ptr_type, ptr_type], false),
         };
         let function = self.module.add_function(&fn_name, fn_type, None);
         let basic_block = self.llvm_context.append_basic_block(function, "entry");
@@ -38,12 +51,13 @@ impl<'ctx> CompilationContext<'ctx> {
         builder.position_at_end(basic_block);
 
         let codegen_ctx = CodegenContext {
-            llvm_context: &self.llvm_context,
+            llvm_context: self.llvm_context,
             module: &self.module,
             builder: &builder,
             execution_engine: &self.execution_engine,
             func: &function,
             slab: &slab,
+            arithmetic_mode: self.arithmetic_mode,
         };
         let expr = IRValue::from_aast(&aast, &codegen_ctx)?;
 
@@ -57,11 +71,14 @@ impl<'ctx> CompilationContext<'ctx> {
             ValueType::U16 => CompiledExpression::U16 { slab, jit_fn: unsafe { self.execution_engine.get_function(&fn_name) }? },
             ValueType::U32 => CompiledExpression::U32 { slab, jit_fn: unsafe { self.execution_engine.get_function(&fn_name) }? },
             ValueType::U64 => CompiledExpression::U64 { slab, jit_fn: unsafe { self.execution_engine.get_function(&fn_name) }? },
+            ValueType::U128 => CompiledExpression::U128 { slab, jit_fn: unsafe { self.execution_engine.get_function(&fn_name) }? },
             ValueType::USize => CompiledExpression::USize { slab, jit_fn: unsafe { self.execution_engine.get_function(&fn_name) }? },
             ValueType::I8 => CompiledExpression::I8 { slab, jit_fn: unsafe { self.execution_engine.get_function(&fn_name) }? },
             ValueType::I16 => CompiledExpression::I16 { slab, jit_fn: unsafe { self.execution_engine.get_function(&fn_name) }? },
             ValueType::I32 => CompiledExpression::I32 { slab, jit_fn: unsafe { self.execution_engine.get_function(&fn_name) }? },
             ValueType::I64 => CompiledExpression::I64 { slab, jit_fn: unsafe { self.execution_engine.get_function(&fn_name) }? },
+            ValueType::I128 => CompiledExpression::I128 { slab, jit_fn: unsafe { self.execution_engine.get_function(&fn_name) }? },
+            ValueType::ISize => CompiledExpression::ISize { slab, jit_fn: unsafe { self.execution_engine.get_function(&fn_name) }? },
             ValueType::F32 => CompiledExpression::F32 { slab, jit_fn: unsafe { self.execution_engine.get_function(&fn_name) }? },
             ValueType::F64 => CompiledExpression::F64 { slab, jit_fn: unsafe { self.execution_engine.get_function(&fn_name) }? },
             ValueType::Bool => CompiledExpression::Bool { slab, jit_fn: unsafe { self.execution_engine.get_function(&fn_name) }? },
@@ -1,20 +1,98 @@
+use std::{ffi::c_void, ptr::null_mut};
+
 use inkwell::execution_engine::JitFunction;
 
-use crate::common::slab::Slab;
+use crate::common::{eval_error::EvalError, inputs::HotEvalInputs, slab::Slab};
 
-pub type HotEvalJitFunction<'ctx, T> = JitFunction<'ctx, unsafe extern "C" fn() -> T>;
+/// The parameters are the inputs pointer, from [Slab::inputs_ptr], and the
+/// context pointer, from [Slab::context_ptr]. Either can be null if the
+/// expression's Table has no inputs or context type
+///
+/// Every compiled expression takes both parameters, even if its Table has no
+/// inputs or context, so code that called `jit_fn.call()` without arguments
+/// must now pass null pointers. Prefer [eval] and [eval_with], which check
+/// the pointers' types
+pub type HotEvalJitFunction<'ctx, T> = JitFunction<'ctx, unsafe extern "C" fn(*const c_void, *mut c_void) -> T>;
 
 pub enum CompiledExpression<'ctx> {
     U8 { slab: Slab, jit_fn: HotEvalJitFunction<'ctx, u8> },
     U16 { slab: Slab, jit_fn: HotEvalJitFunction<'ctx, u16> },
     U32 { slab: Slab, jit_fn: HotEvalJitFunction<'ctx, u32> },
     U64 { slab: Slab, jit_fn: HotEvalJitFunction<'ctx, u64> },
+    U128 { slab: Slab, jit_fn: HotEvalJitFunction<'ctx, u128> },
     USize { slab: Slab, jit_fn: HotEvalJitFunction<'ctx, usize> },
     I8 { slab: Slab, jit_fn: HotEvalJitFunction<'ctx, i8> },
     I16 { slab: Slab, jit_fn: HotEvalJitFunction<'ctx, i16> },
     I32 { slab: Slab, jit_fn: HotEvalJitFunction<'ctx, i32> },
     I64 { slab: Slab, jit_fn: HotEvalJitFunction<'ctx, i64> },
+    I128 { slab: Slab, jit_fn: HotEvalJitFunction<'ctx, i128> },
+    ISize { slab: Slab, jit_fn: HotEvalJitFunction<'ctx, isize> },
     F32 { slab: Slab, jit_fn: HotEvalJitFunction<'ctx, f32> },
     F64 { slab: Slab, jit_fn: HotEvalJitFunction<'ctx, f64> },
     Bool { slab: Slab, jit_fn: HotEvalJitFunction<'ctx, bool> },
-}
\ No newline at end of file
+}
+
+/// Calls a compiled expression with the given inputs and checks whether the
+/// evaluation failed. Expressions that can fail (when compiled with
+/// [super::arithmetic_mode::ArithmeticMode::Checked], or when calling host
+/// functions) must be called this way. Calling them directly returns zero
+/// when they fail, and leaves the error in the Slab, where it's reported by
+/// the next evaluation
+///
+/// Pass `&()` if the expression's Table has no inputs. Panics if I isn't the
+/// inputs type of the expression's Table, or if the Table has a context type;
+/// use [eval_with] instead
+///
+/// # Safety
+///
+/// The Slab must be the one that was compiled with the JIT function
+pub unsafe fn eval<T, I: HotEvalInputs>(jit_fn: &HotEvalJitFunction<'_, T>, slab: &mut Slab, inputs: &I) -> Result<T, EvalError> {
+    let inputs = slab.inputs_ptr(inputs);
+    unsafe { call_checked(jit_fn, slab, inputs) }
+}
+
+/// Same as [eval], but with a context that is passed to host functions bound
+/// with [crate::common::binding::FnSpecCallArg::Context]
+///
+/// Panics if I or C aren't the inputs and context types of the expression's
+/// Table
+///
+/// # Safety
+///
+/// Same as [eval]
+pub unsafe fn eval_with<T, I: HotEvalInputs, C: 'static>(jit_fn: &HotEvalJitFunction<'_, T>, slab: &mut Slab, inputs: &I, context: &mut C) -> Result<T, EvalError> {
+    let inputs = slab.inputs_ptr(inputs);
+    let context = slab.context_ptr(context);
+    unsafe { call_checked_with(jit_fn, slab, inputs, context) }
+}
+
+/// Same as [eval], but with a raw inputs pointer that isn't checked against
+/// the Table's inputs type
+///
+/// Panics if the expression's Table has a context type; use
+/// [call_checked_with] instead
+///
+/// # Safety
+///
+/// Same requirements as calling the JIT function directly. The Slab must be
+/// the one that was compiled with the JIT function, and `inputs` must be null
+/// or come from [Slab::inputs_ptr]
+pub unsafe fn call_checked<T>(jit_fn: &HotEvalJitFunction<'_, T>, slab: &mut Slab, inputs: *const c_void) -> Result<T, EvalError> {
+    assert!(slab.get_context_type().is_none(), "expressions that use a context must be evaluated with eval_with");
+    unsafe { call_checked_with(jit_fn, slab, inputs, null_mut()) }
+}
+
+/// Same as [eval_with], but with raw inputs and context pointers that aren't
+/// checked against the Table's types
+///
+/// # Safety
+///
+/// Same as [call_checked], and `context` must be null or come from
+/// [Slab::context_ptr]
+pub unsafe fn call_checked_with<T>(jit_fn: &HotEvalJitFunction<'_, T>, slab: &mut Slab, inputs: *const c_void, context: *mut c_void) -> Result<T, EvalError> {
+    let value = unsafe { jit_fn.call(inputs, context) };
+    match slab.take_eval_error() {
+        Some(err) => Err(err),
+        None => Ok(value),
+    }
+}
@@ -13,6 +13,10 @@ pub enum CodegenError {
     UnknownHiddenState { idx: usize },
     SpecFailed { msg: String },
     BadSpecConst { actual_type: ValueType, expected_type: ValueType },
+    UnknownIntrinsic { name: &'static str },
+    MissingInputsParameter,
+    MissingContextParameter,
+    MultiReturnSpecConst,
 }
 
 impl fmt::Display for CodegenError {
@@ -33,6 +37,10 @@ impl fmt::Display for CodegenError {
             Self::UnknownHiddenState { idx } => write!(f, "Unknown hidden state {idx}"),
             Self::SpecFailed { msg } => write!(f, "Function specialization failed: {msg}"),
             Self::BadSpecConst { actual_type, expected_type } => write!(f, "Const specialization has an unexpected type; expected {expected_type:?}, got {actual_type:?}"),
+            Self::UnknownIntrinsic { name } => write!(f, "Unknown LLVM intrinsic \"{name}\". This is probably a bug"),
+            Self::MissingInputsParameter => write!(f, "Compiled function has no inputs parameter. This is probably a bug"),
+            Self::MissingContextParameter => write!(f, "Compiled function has no context parameter. This is probably a bug"),
+            Self::MultiReturnSpecConst => write!(f, "Const specialization is not supported for functions with multiple return values"),
         }
     }
 }
@@ -1,11 +1,18 @@
 use std::error::Error;
 
-use inkwell::{AddressSpace, FloatPredicate, IntPredicate, builder::BuilderError, values::{BasicMetadataValueEnum, BasicValue, BasicValueEnum, FloatValue, IntValue, ValueKind}};
+use inkwell::{AddressSpace, FloatPredicate, IntPredicate, module::Linkage, types::{BasicType, IntType}, values::{BasicMetadataValueEnum, BasicValue, BasicValueEnum, FloatValue, IntValue, PointerValue, ValueKind}};
 
-use crate::{analysis::{error::AnalysisError, packed_analysis_node::{PackedAnalysisFunctionArg, PackedAnalysisNodeData}, packed_analysis_tree::PackedAnalysisTree}, ast::ast_node::{BinaryOperator, UnaryOperator}, codegen::utils::get_fn_llvm_type, common::{binding::{FnSpecCallArg, FnSpecChoice, FnSpecHints}, ir_const::IRConst, slab::SlabBindingInfo, value::Value, value_type::ValueType}};
+use crate::{analysis::{error::AnalysisError, packed_analysis_node::{AnalysisFnSpec, PackedAnalysisFunctionArg, PackedAnalysisNodeData}, packed_analysis_tree::PackedAnalysisTree}, ast::ast_node::{BinaryOperator, MembershipSet, UnaryOperator}, codegen::utils::get_fn_llvm_type, common::{binding::{FnSpecCallArg, FnSpecChoice, FnSpecHints}, eval_error::EvalErrorKind, host_set::HostSetData, ir_const::IRConst, math_intrinsic::MathIntrinsic, slab::SlabBindingInfo, value::Value, value_type::ValueType}};
 
-use super::{codegen_context::CodegenContext, error::CodegenError, ir_value_type::IRValueType, utils::get_usize_llvm_type};
+use super::{arithmetic_mode::{ArithmeticMode, DivByZeroResult}, codegen_context::CodegenContext, error::CodegenError, ir_value_type::IRValueType, utils::{build_entry_alloca, build_eval_error_check, build_host_error_check, build_intrinsic_call, get_usize_llvm_type}};
 
+/// Constant integer exponents up to this are lowered to multiplications
+const MAX_UNROLLED_EXPONENT: u64 = 16;
+/// Constant integer lists with up to this many items are lowered to a switch,
+/// unless they fit in a bitset. Longer lists use a binary search
+const MAX_SWITCH_ITEMS: usize = 16;
+
+#[derive(Clone, Copy)]
 pub enum IRValue<'ctx> {
     Int { inner: IntValue<'ctx>, is_signed: bool },
     Float { inner: FloatValue<'ctx> },
@@ -18,18 +25,22 @@ impl<'ctx> IRValue<'ctx> {
             Value::U16 { inner } => Self::Int { inner: context.llvm_context.i16_type().const_int(*inner as u64, false), is_signed: false },
             Value::U32 { inner } => Self::Int { inner: context.llvm_context.i32_type().const_int(*inner as u64, false), is_signed: false },
             Value::U64 { inner } => Self::Int { inner: context.llvm_context.i64_type().const_int(*inner, false), is_signed: false },
+            Value::U128 { inner } => Self::Int { inner: context.llvm_context.i128_type().const_int_arbitrary_precision(&[*inner as u64, (*inner >> 64) as u64]), is_signed: false },
             Value::USize { inner } => Self::Int { inner: get_usize_llvm_type(context.llvm_context).const_int(*inner as u64, false), is_signed: false },
             Value::I8 { inner } => Self::Int { inner: context.llvm_context.i8_type().const_int(*inner as u64, true), is_signed: true },
             Value::I16 { inner } => Self::Int { inner: context.llvm_context.i16_type().const_int(*inner as u64, true), is_signed: true },
             Value::I32 { inner } => Self::Int { inner: context.llvm_context.i32_type().const_int(*inner as u64, true), is_signed: true },
             Value::I64 { inner } => Self::Int { inner: context.llvm_context.i64_type().const_int(*inner as u64, true), is_signed: true },
+            Value::I128 { inner } => Self::Int { inner: context.llvm_context.i128_type().const_int_arbitrary_precision(&[*inner as u64, (*inner >> 64) as u64]), is_signed: true },
+            Value::ISize { inner } => Self::Int { inner: get_usize_llvm_type(context.llvm_context).const_int(*inner as u64, true), is_signed: true },
             Value::F32 { inner } => Self::Float { inner: context.llvm_context.f32_type().const_float(*inner as f64) },
             Value::F64 { inner } => Self::Float { inner: context.llvm_context.f64_type().const_float(*inner) },
             Value::Bool { inner } => Self::Int { inner: context.llvm_context.bool_type().const_int(*inner as u64, false), is_signed: false },
         }
     }
 
-    pub fn cast_if_needed<'build>(self, from: ValueType, to: ValueType, context: &CodegenContext<'ctx, 'build>) -> Result<Self, Box<dyn Error>> {
+    /// node_idx is only used to report evaluation errors for checked casts
+    pub fn cast_if_needed<'build>(self, from: ValueType, to: ValueType, node_idx: usize, context: &CodegenContext<'ctx, 'build>) -> Result<Self, Box<dyn Error>> {
         if from == to { return Ok(self) }
 
         let builder = context.builder;
@@ -57,14 +68,34 @@ impl<'ctx> IRValue<'ctx> {
                 IRValueType::Int { llvm: wanted_llvm, is_signed: to_signed } => {
                     match self {
                         Self::Int { inner, is_signed: _ } => {
-                            let val = builder.build_int_cast_sign_flag(inner, wanted_llvm, to_signed, "")?;
+                            // extending depends on the signedness of the
+                            // source, e.g. u64 -> i128 zero-extends
+                            let val = builder.build_int_cast_sign_flag(inner, wanted_llvm, from.is_signed(), "")?;
                             IRValue::Int { inner: val, is_signed: to_signed }
                         },
                         Self::Float { inner } => {
-                            let val = if to_signed {
-                                builder.build_float_to_signed_int(inner, wanted_llvm, "")?
-                            } else {
-                                builder.build_float_to_unsigned_int(inner, wanted_llvm, "")?
+                            let val = match context.arithmetic_mode {
+                                ArithmeticMode::Defined { .. } => {
+                                    // same semantics as `as` casts in rust
+                                    let intrinsic_name = if to_signed { "llvm.fptosi.sat" } else { "llvm.fptoui.sat" };
+                                    build_intrinsic_call(intrinsic_name, &[wanted_llvm.into(), inner.get_type().into()], &[inner.into()], context)?.into_int_value()
+                                },
+                                ArithmeticMode::Checked => {
+                                    Self::build_float_cast_check(inner, wanted_llvm, to_signed, node_idx, context)?;
+
+                                    if to_signed {
+                                        builder.build_float_to_signed_int(inner, wanted_llvm, "")?
+                                    } else {
+                                        builder.build_float_to_unsigned_int(inner, wanted_llvm, "")?
+                                    }
+                                },
+                                ArithmeticMode::Unchecked => {
+                                    if to_signed {
+                                        builder.build_float_to_signed_int(inner, wanted_llvm, "")?
+                                    } else {
+                                        builder.build_float_to_unsigned_int(inner, wanted_llvm, "")?
+                                    }
+                                },
                             };
                             IRValue::Int { inner: val, is_signed: to_signed }
                         },
@@ -110,14 +141,14 @@ impl<'ctx> IRValue<'ctx> {
 
     fn from_binary_op<'build, BI, BF>(aast: &PackedAnalysisTree, resolved_type: ValueType, left_idx: usize, right_idx: usize, context: &CodegenContext<'ctx, 'build>, build_int: BI, build_float: BF) -> Result<IRValue<'ctx>, Box<dyn Error>>
     where
-        BI: FnOnce(IntValue<'ctx>, IntValue<'ctx>, bool, &CodegenContext<'ctx, 'build>) -> Result<IntValue<'ctx>, BuilderError>,
-        BF: FnOnce(FloatValue<'ctx>, FloatValue<'ctx>, &CodegenContext<'ctx, 'build>) -> Result<FloatValue<'ctx>, BuilderError>,
+        BI: FnOnce(IntValue<'ctx>, IntValue<'ctx>, bool, &CodegenContext<'ctx, 'build>) -> Result<IntValue<'ctx>, Box<dyn Error>>,
+        BF: FnOnce(FloatValue<'ctx>, FloatValue<'ctx>, &CodegenContext<'ctx, 'build>) -> Result<FloatValue<'ctx>, Box<dyn Error>>,
     {
         let left_val = Self::from_aast_node(aast, left_idx, context)?;
         let right_val = Self::from_aast_node(aast, right_idx, context)?;
 
-        let right_val = right_val.cast_if_needed(aast.get_node_type(right_idx)?, resolved_type, context)?;
-        Ok(match left_val.cast_if_needed(aast.get_node_type(left_idx)?, resolved_type, context)? {
+        let right_val = right_val.cast_if_needed(aast.get_node_type(right_idx)?, resolved_type, right_idx, context)?;
+        Ok(match left_val.cast_if_needed(aast.get_node_type(left_idx)?, resolved_type, left_idx, context)? {
             IRValue::Int { inner: left_inner, is_signed } => IRValue::Int {
                 inner: build_int(left_inner, right_val.try_into()?, is_signed, context)?,
                 is_signed,
@@ -128,15 +159,168 @@ impl<'ctx> IRValue<'ctx> {
         })
     }
 
-    fn from_compare_op<'build>(aast: &PackedAnalysisTree, left_idx: usize, right_idx: usize, context: &CodegenContext<'ctx, 'build>, uint_pred: IntPredicate, sint_pred: IntPredicate, float_pred: FloatPredicate) -> Result<IRValue<'ctx>, Box<dyn Error>> {
+    fn from_math_intrinsic<'build>(aast: &PackedAnalysisTree, idx: usize, intrinsic: MathIntrinsic, arg_idxs: &[usize], resolved_type: ValueType, context: &CodegenContext<'ctx, 'build>) -> Result<IRValue<'ctx>, Box<dyn Error>> {
+        let mut args = Vec::<Self>::new();
+        for arg_idx in arg_idxs {
+            args.push(Self::from_aast_node(aast, *arg_idx, context)?.cast_if_needed(aast.get_node_type(*arg_idx)?, resolved_type, *arg_idx, context)?);
+        }
+
+        let builder = context.builder;
+        Ok(match args[0] {
+            IRValue::Int { inner: x, is_signed } => {
+                let int_args = args.into_iter().map(IntValue::try_from).collect::<Result<Vec<_>, _>>()?;
+                let int_type = x.get_type();
+                let call = |name: &'static str, call_args: &[IntValue<'ctx>]| -> Result<IntValue<'ctx>, Box<dyn Error>> {
+                    let call_args = call_args.iter().map(|arg| (*arg).into()).collect::<Vec<_>>();
+                    Ok(build_intrinsic_call(name, &[int_type.into()], &call_args, context)?.into_int_value())
+                };
+                let (min_name, max_name) = if is_signed { ("llvm.smin", "llvm.smax") } else { ("llvm.umin", "llvm.umax") };
+
+                let inner = match intrinsic {
+                    MathIntrinsic::Abs if is_signed => {
+                        if context.arithmetic_mode == ArithmeticMode::Checked {
+                            let signed_min = builder.build_not(builder.build_right_shift(int_type.const_all_ones(), int_type.const_int(1, false), false, "")?, "")?;
+                            let is_min = builder.build_int_compare(IntPredicate::EQ, x, signed_min, "")?;
+                            build_eval_error_check(is_min, EvalErrorKind::Overflow, idx, context)?;
+                        }
+
+                        // abs(MIN) wraps to MIN instead of being poison
+                        let call_args = [x.into(), context.llvm_context.bool_type().const_zero().into()];
+                        build_intrinsic_call("llvm.abs", &[int_type.into()], &call_args, context)?.into_int_value()
+                    },
+                    MathIntrinsic::Abs => x,
+                    MathIntrinsic::Min => call(min_name, &int_args)?,
+                    MathIntrinsic::Max => call(max_name, &int_args)?,
+                    MathIntrinsic::Clamp => {
+                        let lower_bounded = call(max_name, &int_args[0..2])?;
+                        call(min_name, &[lower_bounded, int_args[2]])?
+                    },
+                    MathIntrinsic::RemEuclid => {
+                        let rem = Self::build_int_div_or_rem(x, int_args[1], is_signed, true, idx, context)?;
+                        if is_signed {
+                            // a negative remainder is moved into [0, |rhs|),
+                            // which can't overflow since |rem| < |rhs|
+                            let zero = int_type.const_zero();
+                            let is_negative = builder.build_int_compare(IntPredicate::SLT, rem, zero, "")?;
+                            let rhs_is_negative = builder.build_int_compare(IntPredicate::SLT, int_args[1], zero, "")?;
+                            let adjusted = builder.build_select(rhs_is_negative, builder.build_int_sub(rem, int_args[1], "")?, builder.build_int_add(rem, int_args[1], "")?, "")?.into_int_value();
+                            builder.build_select(is_negative, adjusted, rem, "")?.into_int_value()
+                        } else {
+                            rem
+                        }
+                    },
+                    // float-only intrinsics; rejected during analysis
+                    _ => return Err(Box::new(AnalysisError::BadAnalysis)),
+                };
+
+                IRValue::Int { inner, is_signed }
+            },
+            IRValue::Float { inner: x } => {
+                let float_args = args.into_iter().map(FloatValue::try_from).collect::<Result<Vec<_>, _>>()?;
+                let float_type = x.get_type();
+                let call = |name: &'static str, call_args: &[FloatValue<'ctx>]| -> Result<FloatValue<'ctx>, Box<dyn Error>> {
+                    let call_args = call_args.iter().map(|arg| (*arg).into()).collect::<Vec<_>>();
+                    Ok(build_intrinsic_call(name, &[float_type.into()], &call_args, context)?.into_float_value())
+                };
+
+                let inner = match intrinsic {
+                    MathIntrinsic::Sqrt => call("llvm.sqrt", &float_args)?,
+                    MathIntrinsic::Abs => call("llvm.fabs", &float_args)?,
+                    MathIntrinsic::Min => call("llvm.minnum", &float_args)?,
+                    MathIntrinsic::Max => call("llvm.maxnum", &float_args)?,
+                    MathIntrinsic::Clamp => {
+                        let lower_bounded = call("llvm.maxnum", &float_args[0..2])?;
+                        call("llvm.minnum", &[lower_bounded, float_args[2]])?
+                    },
+                    MathIntrinsic::Floor => call("llvm.floor", &float_args)?,
+                    MathIntrinsic::Ceil => call("llvm.ceil", &float_args)?,
+                    MathIntrinsic::Round => call("llvm.round", &float_args)?,
+                    MathIntrinsic::Trunc => call("llvm.trunc", &float_args)?,
+                    MathIntrinsic::Fma => call("llvm.fma", &float_args)?,
+                    MathIntrinsic::Pow => call("llvm.pow", &float_args)?,
+                    MathIntrinsic::Exp => call("llvm.exp", &float_args)?,
+                    MathIntrinsic::Log => call("llvm.log", &float_args)?,
+                    MathIntrinsic::Sin => call("llvm.sin", &float_args)?,
+                    MathIntrinsic::Cos => call("llvm.cos", &float_args)?,
+                    MathIntrinsic::CopySign => call("llvm.copysign", &float_args)?,
+                    MathIntrinsic::RemEuclid => {
+                        let rem = builder.build_float_rem(x, float_args[1], "")?;
+                        let is_negative = builder.build_float_compare(FloatPredicate::OLT, rem, float_type.const_zero(), "")?;
+                        let adjusted = builder.build_float_add(rem, call("llvm.fabs", &float_args[1..2])?, "")?;
+                        builder.build_select(is_negative, adjusted, rem, "")?.into_float_value()
+                    },
+                };
+
+                IRValue::Float { inner }
+            },
+        })
+    }
+
+    fn from_pow<'build>(aast: &PackedAnalysisTree, idx: usize, left_idx: usize, right_idx: usize, resolved_type: ValueType, context: &CodegenContext<'ctx, 'build>) -> Result<IRValue<'ctx>, Box<dyn Error>> {
+        let base = Self::from_aast_node(aast, left_idx, context)?.cast_if_needed(aast.get_node_type(left_idx)?, resolved_type, left_idx, context)?;
+        let exponent_type = aast.get_node_type(right_idx)?;
+        let exponent = Self::from_aast_node(aast, right_idx, context)?;
+
+        let const_exponent = match exponent.get_ir_const() {
+            Some(IRConst::Int { inner }) if (0..=MAX_UNROLLED_EXPONENT as i64).contains(&inner) => Some(inner as u64),
+            Some(IRConst::Uint { inner }) if inner <= MAX_UNROLLED_EXPONENT => Some(inner),
+            _ => None,
+        };
+
+        Ok(match base {
+            IRValue::Int { inner, is_signed } => IRValue::Int {
+                inner: match const_exponent {
+                    Some(const_exponent) => Self::build_unrolled_pow(inner, inner.get_type().const_int(1, false), const_exponent, |lhs, rhs| {
+                        Self::build_int_arith(&BinaryOperator::Mul, lhs, rhs, is_signed, idx, context)
+                    })?,
+                    None => Self::build_int_pow_loop(inner, exponent.try_into()?, exponent_type.is_signed(), is_signed, idx, context)?,
+                },
+                is_signed,
+            },
+            IRValue::Float { inner } => IRValue::Float {
+                inner: if let Some(const_exponent) = const_exponent {
+                    Self::build_unrolled_pow(inner, inner.get_type().const_float(1.0), const_exponent, |lhs, rhs| {
+                        Ok(context.builder.build_float_mul(lhs, rhs, "")?)
+                    })?
+                } else if exponent_type.is_float() {
+                    let exponent = FloatValue::try_from(exponent)?;
+                    build_intrinsic_call("llvm.pow", &[inner.get_type().into()], &[inner.into(), exponent.into()], context)?.into_float_value()
+                } else {
+                    let exponent = IntValue::try_from(exponent.cast_if_needed(exponent_type, ValueType::I32, right_idx, context)?)?;
+                    build_intrinsic_call("llvm.powi", &[inner.get_type().into(), exponent.get_type().into()], &[inner.into(), exponent.into()], context)?.into_float_value()
+                },
+            },
+        })
+    }
+
+    fn from_compare_op<'build>(aast: &PackedAnalysisTree, operator: &BinaryOperator, left_idx: usize, right_idx: usize, context: &CodegenContext<'ctx, 'build>) -> Result<IRValue<'ctx>, Box<dyn Error>> {
         let left_val = Self::from_aast_node(aast, left_idx, context)?;
-        let left_type = aast.get_node_type(left_idx)?;
         let right_val = Self::from_aast_node(aast, right_idx, context)?;
-        let right_type = aast.get_node_type(right_idx)?;
+        Self::build_compare(operator, (left_val, aast.get_node_type(left_idx)?, left_idx), (right_val, aast.get_node_type(right_idx)?, right_idx), context)
+    }
+
+    /// Operands are a value, its type, and the index of the node it came from
+    fn build_compare<'build>(operator: &BinaryOperator, left: (Self, ValueType, usize), right: (Self, ValueType, usize), context: &CodegenContext<'ctx, 'build>) -> Result<IRValue<'ctx>, Box<dyn Error>> {
+        let (left_val, left_type, left_idx) = left;
+        let (right_val, right_type, right_idx) = right;
+        let (uint_pred, sint_pred, float_pred) = match operator {
+            BinaryOperator::Equals => (IntPredicate::EQ, IntPredicate::EQ, FloatPredicate::OEQ),
+            BinaryOperator::NotEquals => (IntPredicate::NE, IntPredicate::NE, FloatPredicate::ONE),
+            BinaryOperator::LesserThanEquals => (IntPredicate::ULE, IntPredicate::SLE, FloatPredicate::OLE),
+            BinaryOperator::GreaterThanEquals => (IntPredicate::UGE, IntPredicate::SGE, FloatPredicate::OGE),
+            BinaryOperator::LesserThan => (IntPredicate::ULT, IntPredicate::SLT, FloatPredicate::OLT),
+            BinaryOperator::GreaterThan => (IntPredicate::UGT, IntPredicate::SGT, FloatPredicate::OGT),
+            _ => return Err(Box::new(AnalysisError::BadAnalysis)),
+        };
+
+        if left_type.is_integer() && right_type.is_integer() && left_type.is_signed() != right_type.is_signed() {
+            return Self::build_mixed_sign_compare(left_val.try_into()?, left_type.is_signed(), right_val.try_into()?, uint_pred, context);
+        }
+
         let resolved_type = ValueType::widen(left_type, right_type)?;
 
-        let right_val = right_val.cast_if_needed(right_type, resolved_type, context)?;
-        Ok(match left_val.cast_if_needed(left_type, resolved_type, context)? {
+        let right_val = right_val.cast_if_needed(right_type, resolved_type, right_idx, context)?;
+        Ok(match left_val.cast_if_needed(left_type, resolved_type, left_idx, context)? {
             IRValue::Int { inner: left_inner, is_signed } => IRValue::Int {
                 inner: context.builder.build_int_compare(if is_signed { sint_pred } else { uint_pred }, left_inner, right_val.try_into()?, "")?,
                 is_signed: false,
@@ -148,14 +332,594 @@ impl<'ctx> IRValue<'ctx> {
         })
     }
 
-    fn from_branching_expr<'build, CC, LC, RC>(aast: &PackedAnalysisTree, context: &CodegenContext<'ctx, 'build>, out_type: ValueType, cond_callback: CC, left_callback: LC, right_callback: RC) -> Result<Self, Box<dyn Error>>
+    /// Compares a signed and an unsigned integer without losing the sign, like
+    /// `std::cmp_less` in C++20. If the signed operand is negative then it must
+    /// be lesser than the unsigned operand, otherwise both operands can be
+    /// compared as unsigned integers with the width of the widest operand
+    fn build_mixed_sign_compare<'build>(left: IntValue<'ctx>, is_left_signed: bool, right: IntValue<'ctx>, uint_pred: IntPredicate, context: &CodegenContext<'ctx, 'build>) -> Result<IRValue<'ctx>, Box<dyn Error>> {
+        let builder = context.builder;
+        let left_width = left.get_type().get_bit_width();
+        let right_width = right.get_type().get_bit_width();
+        let wide_type = if left_width >= right_width { left.get_type() } else { right.get_type() };
+        let left = builder.build_int_cast_sign_flag(left, wide_type, is_left_signed, "")?;
+        let right = builder.build_int_cast_sign_flag(right, wide_type, !is_left_signed, "")?;
+
+        let signed_val = if is_left_signed { left } else { right };
+        let is_negative = builder.build_int_compare(IntPredicate::SLT, signed_val, wide_type.const_zero(), "")?;
+        let unsigned_cmp = builder.build_int_compare(uint_pred, left, right, "")?;
+
+        let negative_result = if is_left_signed {
+            matches!(uint_pred, IntPredicate::ULT | IntPredicate::ULE | IntPredicate::NE)
+        } else {
+            matches!(uint_pred, IntPredicate::UGT | IntPredicate::UGE | IntPredicate::NE)
+        };
+        let negative_result = context.llvm_context.bool_type().const_int(negative_result as u64, false);
+
+        Ok(IRValue::Int {
+            inner: builder.build_select(is_negative, negative_result, unsigned_cmp, "")?.into_int_value(),
+            is_signed: false,
+        })
+    }
+
+    fn build_int_arith<'build>(operator: &BinaryOperator, lhs: IntValue<'ctx>, rhs: IntValue<'ctx>, is_signed: bool, node_idx: usize, context: &CodegenContext<'ctx, 'build>) -> Result<IntValue<'ctx>, Box<dyn Error>> {
+        let builder = context.builder;
+
+        // these ignore the arithmetic mode, since the behaviour on overflow is
+        // part of the operator
+        match operator {
+            BinaryOperator::SaturatingAdd |
+            BinaryOperator::SaturatingSub => {
+                let intrinsic_name = match (operator, is_signed) {
+                    (BinaryOperator::SaturatingAdd, false) => "llvm.uadd.sat",
+                    (BinaryOperator::SaturatingAdd, true) => "llvm.sadd.sat",
+                    (_, false) => "llvm.usub.sat",
+                    (_, true) => "llvm.ssub.sat",
+                };
+
+                return Ok(build_intrinsic_call(intrinsic_name, &[lhs.get_type().into()], &[lhs.into(), rhs.into()], context)?.into_int_value());
+            },
+            BinaryOperator::SaturatingMul => {
+                // there is no mul.sat intrinsic, but a fixed point
+                // multiplication with a scale of 0 is the same thing
+                let intrinsic_name = if is_signed { "llvm.smul.fix.sat" } else { "llvm.umul.fix.sat" };
+                let scale = context.llvm_context.i32_type().const_zero();
+                return Ok(build_intrinsic_call(intrinsic_name, &[lhs.get_type().into()], &[lhs.into(), rhs.into(), scale.into()], context)?.into_int_value());
+            },
+            BinaryOperator::WrappingAdd => return Ok(builder.build_int_add(lhs, rhs, "")?),
+            BinaryOperator::WrappingSub => return Ok(builder.build_int_sub(lhs, rhs, "")?),
+            BinaryOperator::WrappingMul => return Ok(builder.build_int_mul(lhs, rhs, "")?),
+            _ => {},
+        }
+
+        if context.arithmetic_mode == ArithmeticMode::Checked {
+            let intrinsic_name = match (operator, is_signed) {
+                (BinaryOperator::Mul, false) => "llvm.umul.with.overflow",
+                (BinaryOperator::Mul, true) => "llvm.smul.with.overflow",
+                (BinaryOperator::Add, false) => "llvm.uadd.with.overflow",
+                (BinaryOperator::Add, true) => "llvm.sadd.with.overflow",
+                (BinaryOperator::Sub, false) => "llvm.usub.with.overflow",
+                (BinaryOperator::Sub, true) => "llvm.ssub.with.overflow",
+                _ => return Err(Box::new(AnalysisError::BadAnalysis)),
+            };
+
+            return Self::build_int_arith_checked(intrinsic_name, lhs, rhs, node_idx, context);
+        }
+
+        // plain LLVM add/sub/mul wrap on overflow, since we don't use the
+        // nsw/nuw flags
+        Ok(match operator {
+            BinaryOperator::Mul => builder.build_int_mul(lhs, rhs, "")?,
+            BinaryOperator::Add => builder.build_int_add(lhs, rhs, "")?,
+            BinaryOperator::Sub => builder.build_int_sub(lhs, rhs, "")?,
+            _ => return Err(Box::new(AnalysisError::BadAnalysis)),
+        })
+    }
+
+    fn build_int_arith_checked<'build>(intrinsic_name: &'static str, lhs: IntValue<'ctx>, rhs: IntValue<'ctx>, node_idx: usize, context: &CodegenContext<'ctx, 'build>) -> Result<IntValue<'ctx>, Box<dyn Error>> {
+        let result = build_intrinsic_call(intrinsic_name, &[lhs.get_type().into()], &[lhs.into(), rhs.into()], context)?.into_struct_value();
+        let value = context.builder.build_extract_value(result, 0, "")?.into_int_value();
+        let overflowed = context.builder.build_extract_value(result, 1, "")?.into_int_value();
+        build_eval_error_check(overflowed, EvalErrorKind::Overflow, node_idx, context)?;
+        Ok(value)
+    }
+
+    /// Square-and-multiply from the most significant bit. Every intermediate
+    /// result is a smaller power than the final one, so checked
+    /// multiplications only overflow if the final result does
+    fn build_unrolled_pow<T: Copy>(base: T, one: T, exponent: u64, mut build_mul: impl FnMut(T, T) -> Result<T, Box<dyn Error>>) -> Result<T, Box<dyn Error>> {
+        let mut result = None;
+        for bit in (0..(u64::BITS - exponent.leading_zeros())).rev() {
+            if let Some(value) = result {
+                result = Some(build_mul(value, value)?);
+            }
+
+            if (exponent >> bit) & 1 == 1 {
+                result = Some(match result {
+                    Some(value) => build_mul(value, base)?,
+                    None => base,
+                });
+            }
+        }
+
+        Ok(result.unwrap_or(one))
+    }
+
+    /// Square-and-multiply from the least significant bit, with the base only
+    /// squared while there are higher bits left, so that checked
+    /// multiplications only overflow if the result does. Negative exponents
+    /// abort the evaluation in checked mode, and are reinterpreted as unsigned
+    /// otherwise
+    fn build_int_pow_loop<'build>(base: IntValue<'ctx>, exponent: IntValue<'ctx>, is_exponent_signed: bool, is_signed: bool, node_idx: usize, context: &CodegenContext<'ctx, 'build>) -> Result<IntValue<'ctx>, Box<dyn Error>> {
+        let builder = context.builder;
+        let base_type = base.get_type();
+        let exponent_type = exponent.get_type();
+        let one = base_type.const_int(1, false);
+
+        if is_exponent_signed && context.arithmetic_mode == ArithmeticMode::Checked {
+            let is_negative = builder.build_int_compare(IntPredicate::SLT, exponent, exponent_type.const_zero(), "")?;
+            build_eval_error_check(is_negative, EvalErrorKind::Overflow, node_idx, context)?;
+        }
+
+        let acc_ptr = build_entry_alloca(base_type.into(), context)?;
+        let base_ptr = build_entry_alloca(base_type.into(), context)?;
+        let exponent_ptr = build_entry_alloca(exponent_type.into(), context)?;
+        builder.build_store(acc_ptr, one)?;
+        builder.build_store(base_ptr, base)?;
+        builder.build_store(exponent_ptr, exponent)?;
+
+        let cond_block = context.llvm_context.append_basic_block(*context.func, "");
+        let body_block = context.llvm_context.append_basic_block(*context.func, "");
+        let tail_block = context.llvm_context.append_basic_block(*context.func, "");
+        builder.build_unconditional_branch(cond_block)?;
+
+        // while exponent > 1
+        builder.position_at_end(cond_block);
+        let remaining = builder.build_load(exponent_type, exponent_ptr, "")?.into_int_value();
+        let has_high_bits = builder.build_int_compare(IntPredicate::UGT, remaining, exponent_type.const_int(1, false), "")?;
+        builder.build_conditional_branch(has_high_bits, body_block, tail_block)?;
+
+        // multiplying by one instead of branching on odd exponents, since it
+        // never overflows
+        builder.position_at_end(body_block);
+        let acc = builder.build_load(base_type, acc_ptr, "")?.into_int_value();
+        let base = builder.build_load(base_type, base_ptr, "")?.into_int_value();
+        let low_bit = builder.build_and(remaining, exponent_type.const_int(1, false), "")?;
+        let is_odd = builder.build_int_compare(IntPredicate::NE, low_bit, exponent_type.const_zero(), "")?;
+        let factor = builder.build_select(is_odd, base, one, "")?.into_int_value();
+        let acc = Self::build_int_arith(&BinaryOperator::Mul, acc, factor, is_signed, node_idx, context)?;
+        builder.build_store(acc_ptr, acc)?;
+        let remaining_next = builder.build_right_shift(remaining, exponent_type.const_int(1, false), false, "")?;
+        builder.build_store(exponent_ptr, remaining_next)?;
+        let base = Self::build_int_arith(&BinaryOperator::Mul, base, base, is_signed, node_idx, context)?;
+        builder.build_store(base_ptr, base)?;
+        builder.build_unconditional_branch(cond_block)?;
+
+        // the exponent is now 0 (only if it was 0 to begin with) or 1
+        builder.position_at_end(tail_block);
+        let acc = builder.build_load(base_type, acc_ptr, "")?.into_int_value();
+        let base = builder.build_load(base_type, base_ptr, "")?.into_int_value();
+        let is_zero = builder.build_int_compare(IntPredicate::EQ, remaining, exponent_type.const_zero(), "")?;
+        let factor = builder.build_select(is_zero, one, base, "")?.into_int_value();
+        Self::build_int_arith(&BinaryOperator::Mul, acc, factor, is_signed, node_idx, context)
+    }
+
+    fn build_float_cast_check<'build>(value: FloatValue<'ctx>, int_type: IntType<'ctx>, to_signed: bool, node_idx: usize, context: &CodegenContext<'ctx, 'build>) -> Result<(), Box<dyn Error>> {
+        // the bounds are powers of 2, so they are exactly representable (or
+        // infinity, which still works as an upper bound). ordered comparisons
+        // are false for NaN, so NaN is also rejected
+        let float_type = value.get_type();
+        let bits = int_type.get_bit_width() as i32;
+        let (min, max) = if to_signed {
+            (-(2f64.powi(bits - 1)), 2f64.powi(bits - 1))
+        } else {
+            (-1.0, 2f64.powi(bits))
+        };
+
+        let min_pred = if to_signed { FloatPredicate::OGE } else { FloatPredicate::OGT };
+        let above_min = context.builder.build_float_compare(min_pred, value, float_type.const_float(min), "")?;
+        let below_max = context.builder.build_float_compare(FloatPredicate::OLT, value, float_type.const_float(max), "")?;
+        let in_range = context.builder.build_and(above_min, below_max, "")?;
+        let failed = context.builder.build_not(in_range, "")?;
+        build_eval_error_check(failed, EvalErrorKind::InvalidFloatCast, node_idx, context)
+    }
+
+    fn build_int_div_or_rem<'build>(lhs: IntValue<'ctx>, rhs: IntValue<'ctx>, is_signed: bool, is_rem: bool, node_idx: usize, context: &CodegenContext<'ctx, 'build>) -> Result<IntValue<'ctx>, Box<dyn Error>> {
+        let builder = context.builder;
+
+        let div_by_zero = match context.arithmetic_mode {
+            ArithmeticMode::Defined { div_by_zero } => Some(div_by_zero),
+            ArithmeticMode::Checked => None,
+            ArithmeticMode::Unchecked => {
+                return Ok(match (is_signed, is_rem) {
+                    (false, false) => builder.build_int_unsigned_div(lhs, rhs, "")?,
+                    (false, true) => builder.build_int_unsigned_rem(lhs, rhs, "")?,
+                    (true, false) => builder.build_int_signed_div(lhs, rhs, "")?,
+                    (true, true) => builder.build_int_signed_rem(lhs, rhs, "")?,
+                });
+            },
+        };
+
+        // replace the divisor with 1 in the cases that would be UB, and then
+        // patch the result for division by zero. dividing by 1 already gives
+        // the wrapped result for MIN / -1 (MIN), and MIN % -1 (0)
+        let int_type = lhs.get_type();
+        let zero = int_type.const_zero();
+        let one = int_type.const_int(1, false);
+        let all_ones = int_type.const_all_ones();
+        let is_zero = builder.build_int_compare(IntPredicate::EQ, rhs, zero, "")?;
+        let mut is_unsafe = is_zero;
+        let mut signed_min = None;
+
+        if is_signed {
+            let signed_max = builder.build_right_shift(all_ones, one, false, "")?;
+            let min = builder.build_not(signed_max, "")?;
+            let is_min = builder.build_int_compare(IntPredicate::EQ, lhs, min, "")?;
+            let is_neg_one = builder.build_int_compare(IntPredicate::EQ, rhs, all_ones, "")?;
+            let is_overflow = builder.build_and(is_min, is_neg_one, "")?;
+            is_unsafe = builder.build_or(is_unsafe, is_overflow, "")?;
+            signed_min = Some((min, signed_max));
+
+            if div_by_zero.is_none() {
+                build_eval_error_check(is_overflow, EvalErrorKind::Overflow, node_idx, context)?;
+            }
+        }
+
+        let div_by_zero = match div_by_zero {
+            Some(div_by_zero) => div_by_zero,
+            None => {
+                // checked mode. at this point the overflow case has already
+                // been handled
+                build_eval_error_check(is_zero, EvalErrorKind::DivisionByZero, node_idx, context)?;
+
+                return Ok(match (is_signed, is_rem) {
+                    (false, false) => builder.build_int_unsigned_div(lhs, rhs, "")?,
+                    (false, true) => builder.build_int_unsigned_rem(lhs, rhs, "")?,
+                    (true, false) => builder.build_int_signed_div(lhs, rhs, "")?,
+                    (true, true) => builder.build_int_signed_rem(lhs, rhs, "")?,
+                });
+            },
+        };
+
+        let divisor = builder.build_select(is_unsafe, one, rhs, "")?.into_int_value();
+
+        let (result, zero_result) = if is_rem {
+            let result = if is_signed {
+                builder.build_int_signed_rem(lhs, divisor, "")?
+            } else {
+                builder.build_int_unsigned_rem(lhs, divisor, "")?
+            };
+
+            (result, lhs)
+        } else {
+            let result = if is_signed {
+                builder.build_int_signed_div(lhs, divisor, "")?
+            } else {
+                builder.build_int_unsigned_div(lhs, divisor, "")?
+            };
+
+            let zero_result = match div_by_zero {
+                DivByZeroResult::Zero => zero,
+                DivByZeroResult::Saturate => {
+                    let is_lhs_zero = builder.build_int_compare(IntPredicate::EQ, lhs, zero, "")?;
+                    let saturated = if let Some((min, max)) = signed_min {
+                        let is_lhs_neg = builder.build_int_compare(IntPredicate::SLT, lhs, zero, "")?;
+                        builder.build_select(is_lhs_neg, min, max, "")?.into_int_value()
+                    } else {
+                        all_ones
+                    };
+
+                    builder.build_select(is_lhs_zero, zero, saturated, "")?.into_int_value()
+                },
+            };
+
+            (result, zero_result)
+        };
+
+        Ok(builder.build_select(is_zero, zero_result, result, "")?.into_int_value())
+    }
+
+    fn from_membership<'build>(aast: &PackedAnalysisTree, idx: usize, set: MembershipSet, value_idx: usize, item_idxs: &[usize], context: &CodegenContext<'ctx, 'build>) -> Result<IRValue<'ctx>, Box<dyn Error>> {
+        let value = Self::from_membership_operand(aast, value_idx, context)?;
+
+        if let MembershipSet::Range { inclusive } = set {
+            let start = Self::from_membership_operand(aast, item_idxs[0], context)?;
+            let end = Self::from_membership_operand(aast, item_idxs[1], context)?;
+            return Self::build_range_check(value, start, end, inclusive, context);
+        }
+
+        // literals have no side effects, so they don't need to be evaluated
+        // lazily
+        if !item_idxs.iter().all(|item_idx| aast.get_const_value(*item_idx).is_some()) {
+            return Self::from_list_chain(aast, idx, value, item_idxs, context);
+        }
+
+        let mut items = Vec::with_capacity(item_idxs.len());
+        for item_idx in item_idxs {
+            items.push(Self::from_membership_operand(aast, *item_idx, context)?);
+        }
+
+        let (value_val, value_type, _) = value;
+        let keys = items.iter().map(|(item, _, _)| item.get_const_int_key()).collect::<Option<Vec<_>>>();
+        let (IRValue::Int { inner, is_signed }, Some(keys)) = (value_val, keys) else {
+            let mut result = context.llvm_context.bool_type().const_zero();
+            for item in items {
+                let is_equal = Self::build_compare(&BinaryOperator::Equals, value, item, context)?;
+                result = context.builder.build_or(result, IntValue::try_from(is_equal)?, "")?;
+            }
+
+            return Ok(IRValue::Int { inner: result, is_signed: false });
+        };
+
+        // each key is compared with its own signedness, so keys that don't fit
+        // in the value's type can never be equal to it
+        let mut keys = keys.into_iter().filter(|key| Value::from_int_key(*key, value_type).is_some()).collect::<Vec<_>>();
+        if keys.is_empty() {
+            return Ok(IRValue::Int { inner: context.llvm_context.bool_type().const_zero(), is_signed: false });
+        }
+
+        keys.sort_unstable();
+        keys.dedup();
+        Ok(IRValue::Int { inner: Self::build_const_keys_check(inner, is_signed, &keys, context)?, is_signed: false })
+    }
+
+    /// Membership operands keep their own type, and are compared pairwise
+    /// with [Self::build_compare]. Literals are folded, so that negative
+    /// literals are also constants
+    fn from_membership_operand<'build>(aast: &PackedAnalysisTree, idx: usize, context: &CodegenContext<'ctx, 'build>) -> Result<(IRValue<'ctx>, ValueType, usize), Box<dyn Error>> {
+        let value = match aast.get_const_value(idx) {
+            Some(value) => Self::from_ast_typed_value(&value, context),
+            None => Self::from_aast_node(aast, idx, context)?,
+        };
+
+        Ok((value, aast.get_node_type(idx)?, idx))
+    }
+
+    fn from_set_membership<'build>(aast: &PackedAnalysisTree, name: &str, value_idx: usize, set: &HostSetData, is_updatable: bool, context: &CodegenContext<'ctx, 'build>) -> Result<IRValue<'ctx>, Box<dyn Error>> {
+        let value = Self::from_aast_node(aast, value_idx, context)?.cast_if_needed(aast.get_node_type(value_idx)?, set.get_value_type(), value_idx, context)?;
+        let IRValue::Int { inner, is_signed } = value else {
+            return Err(Box::new(AnalysisError::BadAnalysis))
+        };
+
+        let inner = if is_updatable {
+            let Some(SlabBindingInfo::Set { ptr_idx, len_idx, .. }) = context.slab.get_binding_info(name) else {
+                return Err(Box::new(CodegenError::UnknownBinding { name: name.into() }))
+            };
+
+            // the set's address and length are loaded on every evaluation, so
+            // that the set can be replaced without recompiling
+            let array_address = IntValue::try_from(Self::from_slab_value(*ptr_idx, &ValueType::USize, context)?)?;
+            let array_ptr = context.builder.build_int_to_ptr(array_address, context.llvm_context.ptr_type(AddressSpace::default()), "")?;
+            let len = IntValue::try_from(Self::from_slab_value(*len_idx, &ValueType::USize, context)?)?;
+            Self::build_binary_search_check(inner, is_signed, array_ptr, len, context)?
+        } else {
+            let keys = set.iter_keys().collect::<Vec<_>>();
+            if keys.is_empty() {
+                context.llvm_context.bool_type().const_zero()
+            } else {
+                Self::build_const_keys_check(inner, is_signed, &keys, context)?
+            }
+        };
+
+        Ok(IRValue::Int { inner, is_signed: false })
+    }
+
+    /// Picks the cheapest check for a non-empty list of sorted, deduplicated
+    /// constant keys
+    fn build_const_keys_check<'build>(value: IntValue<'ctx>, is_signed: bool, keys: &[i128], context: &CodegenContext<'ctx, 'build>) -> Result<IntValue<'ctx>, Box<dyn Error>> {
+        let span = keys[keys.len() - 1] - keys[0];
+        if span < 64 {
+            Self::build_bitset_check(value, keys, context)
+        } else if keys.len() <= MAX_SWITCH_ITEMS {
+            Self::build_switch_check(value, keys, context)
+        } else {
+            let int_type = value.get_type();
+            let array = int_type.const_array(&keys.iter().map(|key| int_type.const_int(*key as u64, *key < 0)).collect::<Vec<_>>());
+            let global = context.module.add_global(array.get_type(), None, "");
+            global.set_initializer(&array);
+            global.set_constant(true);
+            global.set_linkage(Linkage::Private);
+
+            let len = get_usize_llvm_type(context.llvm_context).const_int(keys.len() as u64, false);
+            Self::build_binary_search_check(value, is_signed, global.as_pointer_value(), len, context)
+        }
+    }
+
+    /// Compares the value with each item in order, and stops at the first
+    /// equal one
+    fn from_list_chain<'build>(aast: &PackedAnalysisTree, idx: usize, value: (Self, ValueType, usize), item_idxs: &[usize], context: &CodegenContext<'ctx, 'build>) -> Result<IRValue<'ctx>, Box<dyn Error>> {
+        let item = Self::from_membership_operand(aast, item_idxs[0], context)?;
+        let is_equal = Self::build_compare(&BinaryOperator::Equals, value, item, context)?;
+
+        if item_idxs.len() == 1 {
+            return Ok(is_equal);
+        }
+
+        Self::from_branching_expr(aast, idx, context, ValueType::Bool, |_, _| {
+            Ok((is_equal, ValueType::Bool))
+        }, |_, context| {
+            Ok((Self::from_ast_typed_value(&Value::Bool { inner: true }, context), ValueType::Bool))
+        }, |aast, context| {
+            Ok((Self::from_list_chain(aast, idx, value, &item_idxs[1..], context)?, ValueType::Bool))
+        })
+    }
+
+    /// With constant integer bounds, `start <= x < end` is lowered to a single
+    /// unsigned check, `x - start < end - start`. The bounds are first clamped
+    /// to the value's type, so that they can be compared with its signedness
+    fn build_range_check<'build>(value: (Self, ValueType, usize), start: (Self, ValueType, usize), end: (Self, ValueType, usize), inclusive: bool, context: &CodegenContext<'ctx, 'build>) -> Result<IRValue<'ctx>, Box<dyn Error>> {
+        let builder = context.builder;
+
+        if let (IRValue::Int { inner, .. }, value_type, _) = value &&
+            let Some(start_key) = start.0.get_const_int_key() &&
+            let Some(end_key) = end.0.get_const_int_key() &&
+            let Some((min_key, max_key)) = Self::get_int_key_bounds(value_type)
+        {
+            let last_key = if inclusive { end_key } else { end_key - 1 };
+            let start_key = start_key.max(min_key);
+            let last_key = last_key.min(max_key);
+            if last_key < start_key {
+                return Ok(IRValue::Int { inner: context.llvm_context.bool_type().const_zero(), is_signed: false });
+            }
+
+            let int_type = inner.get_type();
+            let span = last_key - start_key;
+            let offset = builder.build_int_sub(inner, int_type.const_int(start_key as u64, start_key < 0), "")?;
+            return Ok(IRValue::Int {
+                inner: builder.build_int_compare(IntPredicate::ULE, offset, int_type.const_int(span as u64, false), "")?,
+                is_signed: false,
+            });
+        }
+
+        let end_operator = if inclusive { BinaryOperator::LesserThanEquals } else { BinaryOperator::LesserThan };
+        let above_start = Self::build_compare(&BinaryOperator::LesserThanEquals, start, value, context)?;
+        let below_end = Self::build_compare(&end_operator, value, end, context)?;
+        Ok(IRValue::Int {
+            inner: builder.build_and(IntValue::try_from(above_start)?, IntValue::try_from(below_end)?, "")?,
+            is_signed: false,
+        })
+    }
+
+    /// The smallest and largest keys of an integer type with at most 64 bits,
+    /// since constant keys are at most 64 bits
+    fn get_int_key_bounds(value_type: ValueType) -> Option<(i128, i128)> {
+        if !value_type.is_integer() || value_type.get_size() > 8 {
+            return None;
+        }
+
+        let bits = value_type.get_size() as u32 * 8;
+        Some(if value_type.is_signed() {
+            (-(1 << (bits - 1)), (1 << (bits - 1)) - 1)
+        } else {
+            (0, (1 << bits) - 1)
+        })
+    }
+
+    /// Tests the value's bit in a 64-bit mask, offset by the smallest key
+    fn build_bitset_check<'build>(value: IntValue<'ctx>, keys: &[i128], context: &CodegenContext<'ctx, 'build>) -> Result<IntValue<'ctx>, Box<dyn Error>> {
+        let builder = context.builder;
+        let int_type = value.get_type();
+        let i64_type = context.llvm_context.i64_type();
+        let min_key = keys[0];
+        let span = keys[keys.len() - 1] - min_key;
+        let mask = keys.iter().fold(0u64, |mask, key| mask | (1 << (key - min_key)));
+
+        let offset = builder.build_int_sub(value, int_type.const_int(min_key as u64, min_key < 0), "")?;
+        let in_range = builder.build_int_compare(IntPredicate::ULE, offset, int_type.const_int(span as u64, false), "")?;
+        // the shift amount is masked so that out of range offsets don't make
+        // a poison value
+        let offset = builder.build_int_cast_sign_flag(offset, i64_type, false, "")?;
+        let offset = builder.build_and(offset, i64_type.const_int(63, false), "")?;
+        let bits = builder.build_right_shift(i64_type.const_int(mask, false), offset, false, "")?;
+        let is_set = builder.build_int_truncate(bits, context.llvm_context.bool_type(), "")?;
+        Ok(builder.build_and(in_range, is_set, "")?)
+    }
+
+    fn build_switch_check<'build>(value: IntValue<'ctx>, keys: &[i128], context: &CodegenContext<'ctx, 'build>) -> Result<IntValue<'ctx>, Box<dyn Error>> {
+        let builder = context.builder;
+        let int_type = value.get_type();
+        let found_block = context.llvm_context.append_basic_block(*context.func, "");
+        let missing_block = context.llvm_context.append_basic_block(*context.func, "");
+        let after_block = context.llvm_context.append_basic_block(*context.func, "");
+
+        let cases = keys.iter().map(|key| (int_type.const_int(*key as u64, *key < 0), found_block)).collect::<Vec<_>>();
+        builder.build_switch(value, missing_block, &cases)?;
+
+        builder.position_at_end(found_block);
+        builder.build_unconditional_branch(after_block)?;
+        builder.position_at_end(missing_block);
+        builder.build_unconditional_branch(after_block)?;
+
+        builder.position_at_end(after_block);
+        let bool_type = context.llvm_context.bool_type();
+        let phi = builder.build_phi(bool_type, "")?;
+        phi.add_incoming(&[(&bool_type.const_all_ones(), found_block), (&bool_type.const_zero(), missing_block)]);
+        Ok(phi.as_basic_value().into_int_value())
+    }
+
+    /// Searches a sorted array of `len` elements with the value's type
+    fn build_binary_search_check<'build>(value: IntValue<'ctx>, is_signed: bool, array_ptr: PointerValue<'ctx>, len: IntValue<'ctx>, context: &CodegenContext<'ctx, 'build>) -> Result<IntValue<'ctx>, Box<dyn Error>> {
+        let builder = context.builder;
+        let int_type = value.get_type();
+        let usize_type = get_usize_llvm_type(context.llvm_context);
+        let bool_type = context.llvm_context.bool_type();
+
+        let lo_ptr = build_entry_alloca(usize_type.into(), context)?;
+        let hi_ptr = build_entry_alloca(usize_type.into(), context)?;
+        builder.build_store(lo_ptr, usize_type.const_zero())?;
+        builder.build_store(hi_ptr, len)?;
+
+        let cond_block = context.llvm_context.append_basic_block(*context.func, "");
+        let body_block = context.llvm_context.append_basic_block(*context.func, "");
+        let step_block = context.llvm_context.append_basic_block(*context.func, "");
+        let found_block = context.llvm_context.append_basic_block(*context.func, "");
+        let missing_block = context.llvm_context.append_basic_block(*context.func, "");
+        let after_block = context.llvm_context.append_basic_block(*context.func, "");
+        builder.build_unconditional_branch(cond_block)?;
+
+        // while lo < hi
+        builder.position_at_end(cond_block);
+        let lo = builder.build_load(usize_type, lo_ptr, "")?.into_int_value();
+        let hi = builder.build_load(usize_type, hi_ptr, "")?.into_int_value();
+        let is_non_empty = builder.build_int_compare(IntPredicate::ULT, lo, hi, "")?;
+        builder.build_conditional_branch(is_non_empty, body_block, missing_block)?;
+
+        builder.position_at_end(body_block);
+        let mid = builder.build_int_add(lo, hi, "")?;
+        let mid = builder.build_right_shift(mid, usize_type.const_int(1, false), false, "")?;
+        // SAFETY: lo <= mid < hi <= len, so the element is in bounds
+        let item_ptr = unsafe { builder.build_in_bounds_gep(int_type, array_ptr, &[mid], "")? };
+        let item = builder.build_load(int_type, item_ptr, "")?.into_int_value();
+        let is_equal = builder.build_int_compare(IntPredicate::EQ, item, value, "")?;
+        builder.build_conditional_branch(is_equal, found_block, step_block)?;
+
+        builder.position_at_end(step_block);
+        let is_less = builder.build_int_compare(if is_signed { IntPredicate::SLT } else { IntPredicate::ULT }, item, value, "")?;
+        let after_mid = builder.build_int_add(mid, usize_type.const_int(1, false), "")?;
+        let new_lo = builder.build_select(is_less, after_mid, lo, "")?;
+        let new_hi = builder.build_select(is_less, hi, mid, "")?;
+        builder.build_store(lo_ptr, new_lo)?;
+        builder.build_store(hi_ptr, new_hi)?;
+        builder.build_unconditional_branch(cond_block)?;
+
+        builder.position_at_end(found_block);
+        builder.build_unconditional_branch(after_block)?;
+        builder.position_at_end(missing_block);
+        builder.build_unconditional_branch(after_block)?;
+
+        builder.position_at_end(after_block);
+        let phi = builder.build_phi(bool_type, "")?;
+        phi.add_incoming(&[(&bool_type.const_all_ones(), found_block), (&bool_type.const_zero(), missing_block)]);
+        Ok(phi.as_basic_value().into_int_value())
+    }
+
+    /// Compares `left` with the first operand, and only evaluates the rest of
+    /// the chain if the comparison is true
+    fn from_comparison_chain<'build>(aast: &PackedAnalysisTree, idx: usize, operators: &[BinaryOperator], operand_idxs: &[usize], left: (Self, ValueType, usize), context: &CodegenContext<'ctx, 'build>) -> Result<Self, Box<dyn Error>> {
+        let right_idx = operand_idxs[0];
+        let right = (Self::from_aast_node(aast, right_idx, context)?, aast.get_node_type(right_idx)?, right_idx);
+        let compare_val = Self::build_compare(&operators[0], left, right, context)?;
+
+        if operators.len() == 1 {
+            return Ok(compare_val);
+        }
+
+        Self::from_branching_expr(aast, idx, context, ValueType::Bool, |_, _| {
+            Ok((compare_val, ValueType::Bool))
+        }, |aast, context| {
+            Ok((Self::from_comparison_chain(aast, idx, &operators[1..], &operand_idxs[1..], right, context)?, ValueType::Bool))
+        }, |_, context| {
+            Ok((Self::from_ast_typed_value(&Value::Bool { inner: false }, context), ValueType::Bool))
+        })
+    }
+
+    fn from_branching_expr<'build, CC, LC, RC>(aast: &PackedAnalysisTree, idx: usize, context: &CodegenContext<'ctx, 'build>, out_type: ValueType, cond_callback: CC, left_callback: LC, right_callback: RC) -> Result<Self, Box<dyn Error>>
     where
         CC: FnOnce(&PackedAnalysisTree, &CodegenContext<'ctx, 'build>) -> Result<(Self, ValueType), Box<dyn Error>>,
         LC: FnOnce(&PackedAnalysisTree, &CodegenContext<'ctx, 'build>) -> Result<(Self, ValueType), Box<dyn Error>>,
         RC: FnOnce(&PackedAnalysisTree, &CodegenContext<'ctx, 'build>) -> Result<(Self, ValueType), Box<dyn Error>>,
     {
         let (cond_val, cond_type) = cond_callback(aast, context)?;
-        let cond_bool: IntValue<'ctx> = cond_val.cast_if_needed(cond_type, ValueType::Bool, context)?.try_into()?;
+        let cond_bool: IntValue<'ctx> = cond_val.cast_if_needed(cond_type, ValueType::Bool, idx, context)?.try_into()?;
 
         let then_block = context.llvm_context.append_basic_block(*context.func, "");
         let else_block = context.llvm_context.append_basic_block(*context.func, "");
@@ -164,12 +928,16 @@ impl<'ctx> IRValue<'ctx> {
 
         context.builder.position_at_end(then_block);
         let (left_val, left_type) = left_callback(aast, context)?;
-        let left_val = left_val.cast_if_needed(left_type, out_type, context)?;
+        let left_val = left_val.cast_if_needed(left_type, out_type, idx, context)?;
+        // the callback might have added more blocks, so the incoming block for
+        // the phi isn't necessarily the one we started with
+        let then_end_block = context.builder.get_insert_block().unwrap();
         context.builder.build_unconditional_branch(after_block)?;
 
         context.builder.position_at_end(else_block);
         let (right_val, right_type) = right_callback(aast, context)?;
-        let right_val = right_val.cast_if_needed(right_type, out_type, context)?;
+        let right_val = right_val.cast_if_needed(right_type, out_type, idx, context)?;
+        let else_end_block = context.builder.get_insert_block().unwrap();
         context.builder.build_unconditional_branch(after_block)?;
 
         context.builder.position_at_end(after_block);
@@ -177,12 +945,12 @@ impl<'ctx> IRValue<'ctx> {
         Ok(match left_val {
             IRValue::Int { inner: left_inner, is_signed } => {
                 let phi = context.builder.build_phi(left_inner.get_type(), "")?;
-                phi.add_incoming(&[(&left_inner, then_block), (&TryInto::<IntValue<'ctx>>::try_into(right_val)?, else_block)]);
+                phi.add_incoming(&[(&left_inner, then_end_block), (&TryInto::<IntValue<'ctx>>::try_into(right_val)?, else_end_block)]);
                 IRValue::Int { inner: phi.as_basic_value().into_int_value(), is_signed }
             },
             IRValue::Float { inner: left_inner } => {
                 let phi = context.builder.build_phi(left_inner.get_type(), "")?;
-                phi.add_incoming(&[(&left_inner, then_block), (&TryInto::<FloatValue<'ctx>>::try_into(right_val)?, else_block)]);
+                phi.add_incoming(&[(&left_inner, then_end_block), (&TryInto::<FloatValue<'ctx>>::try_into(right_val)?, else_end_block)]);
                 IRValue::Float { inner: phi.as_basic_value().into_float_value() }
             },
         })
@@ -193,8 +961,11 @@ impl<'ctx> IRValue<'ctx> {
 
         Ok(match &aast.nodes[idx].data {
             PackedAnalysisNodeData::TypedValue { value } => Self::from_ast_typed_value(value, context),
-            PackedAnalysisNodeData::UntypedValue { .. } => return Err(Box::new(AnalysisError::BadAnalysis)),
-            PackedAnalysisNodeData::FunctionCall { args, fn_spec } => {
+            PackedAnalysisNodeData::UntypedValue { .. } |
+            PackedAnalysisNodeData::OverloadedFunctionCall { .. } |
+            PackedAnalysisNodeData::GenericFunctionCall { .. } => return Err(Box::new(AnalysisError::BadAnalysis)),
+            PackedAnalysisNodeData::FunctionCall { args, fn_spec, ret_types, ret_idx } => {
+                let ret_idx = *ret_idx;
                 let mut spec_hint_consts = Vec::<Option<IRConst>>::new();
                 let mut call_arg_types = Vec::<ValueType>::new();
                 let mut call_arg_values = Vec::<Self>::new();
@@ -202,21 +973,31 @@ impl<'ctx> IRValue<'ctx> {
                 for PackedAnalysisFunctionArg { idx: arg_idx, expected_type: arg_type } in args {
                     let arg_idx = *arg_idx;
                     let arg_type = *arg_type;
-                    let llvm_val = Self::from_aast_node(aast, arg_idx, context)?.cast_if_needed(aast.get_node_type(arg_idx)?, arg_type, context)?;
+                    let llvm_val = Self::from_aast_node(aast, arg_idx, context)?.cast_if_needed(aast.get_node_type(arg_idx)?, arg_type, arg_idx, context)?;
                     spec_hint_consts.push(llvm_val.get_ir_const());
                     call_arg_types.push(arg_type);
                     call_arg_values.push(llvm_val);
                 }
 
-                let choice = match fn_spec(FnSpecHints { consts: spec_hint_consts.into() }) {
-                    Ok(x) => x,
-                    Err(msg) => return Err(CodegenError::SpecFailed { msg }.into()),
+                let choice = match fn_spec {
+                    AnalysisFnSpec::Binding(fn_spec) => match fn_spec(FnSpecHints { consts: spec_hint_consts.into() }) {
+                        Ok(x) => x,
+                        Err(msg) => return Err(CodegenError::SpecFailed { msg }.into()),
+                    },
+                    AnalysisFnSpec::Generic(choice) => choice.clone(),
                 };
 
                 match choice {
                     FnSpecChoice::Call { fn_ptr, args } => {
                         let mut arg_types = Vec::<ValueType>::new();
                         let mut llvm_args = Vec::<BasicMetadataValueEnum<'ctx>>::new();
+                        let mut checks_eval_status = false;
+                        let mut ret_out_ptrs = Vec::<PointerValue<'ctx>>::new();
+                        let variadic_start = args.iter().filter_map(|arg| match arg {
+                            FnSpecCallArg::MappedArgument { param_idx } => Some(param_idx + 1),
+                            _ => None,
+                        }).max().unwrap_or(0);
+                        let variadic_count = call_arg_values.len().saturating_sub(variadic_start);
 
                         for arg in args {
                             match arg {
@@ -228,7 +1009,7 @@ impl<'ctx> IRValue<'ctx> {
                                     arg_types.push(value.get_value_type());
                                     llvm_args.push(Self::from_ast_typed_value(&value, context).to_meta_value());
                                 },
-                                FnSpecCallArg::HiddenStateArgument { hidden_state_idx, cast_to_type } => {
+                                FnSpecCallArg::HiddenStateArgument { hidden_state_idx, cast_to_type, .. } => {
                                     let slab_value_type = match context.slab.get_hidden_state_type(hidden_state_idx) {
                                         Some(x) => x,
                                         None => return Err(Box::new(CodegenError::UnknownHiddenState { idx: hidden_state_idx })),
@@ -236,7 +1017,7 @@ impl<'ctx> IRValue<'ctx> {
 
                                     let mut ir_slab_value = IRValue::from_slab_value(hidden_state_idx, &slab_value_type, context)?;
                                     if let Some(cast_to_type) = cast_to_type {
-                                        ir_slab_value = ir_slab_value.cast_if_needed(slab_value_type, cast_to_type, context)?;
+                                        ir_slab_value = ir_slab_value.cast_if_needed(slab_value_type, cast_to_type, idx, context)?;
                                         arg_types.push(cast_to_type);
                                     } else {
                                         arg_types.push(slab_value_type);
@@ -244,14 +1025,80 @@ impl<'ctx> IRValue<'ctx> {
 
                                     llvm_args.push(ir_slab_value.to_meta_value());
                                 },
+                                FnSpecCallArg::EvalStatus => {
+                                    let address = context.slab.get_eval_status_address();
+                                    arg_types.push(ValueType::USize);
+                                    llvm_args.push(get_usize_llvm_type(context.llvm_context).const_int(address as u64, false).into());
+                                    checks_eval_status = true;
+                                },
+                                FnSpecCallArg::ReturnOutPointers => {
+                                    let usize_type = get_usize_llvm_type(context.llvm_context);
+                                    let outs_val = if ret_types.len() > 1 {
+                                        let ptr_type = context.llvm_context.ptr_type(AddressSpace::default());
+                                        let outs_type = ptr_type.array_type((ret_types.len() - 1) as u32);
+                                        let outs_ptr = build_entry_alloca(outs_type.into(), context)?;
+
+                                        for (i, out_type) in ret_types[1..].iter().enumerate() {
+                                            let out_ptr = build_entry_alloca(IRValueType::from_value_type(out_type, context.llvm_context).to_basic_type(), context)?;
+                                            let out_ptr_slot = unsafe { context.builder.build_in_bounds_gep(outs_type, outs_ptr, &[usize_type.const_zero(), usize_type.const_int(i as u64, false)], "") }?;
+                                            context.builder.build_store(out_ptr_slot, out_ptr)?;
+                                            ret_out_ptrs.push(out_ptr);
+                                        }
+
+                                        context.builder.build_ptr_to_int(outs_ptr, usize_type, "")?
+                                    } else {
+                                        usize_type.const_zero()
+                                    };
+
+                                    arg_types.push(ValueType::USize);
+                                    llvm_args.push(outs_val.into());
+                                },
+                                FnSpecCallArg::Context { .. } => {
+                                    let context_ptr = context.func.get_nth_param(1).ok_or(CodegenError::MissingContextParameter)?.into_pointer_value();
+                                    arg_types.push(ValueType::USize);
+                                    llvm_args.push(context.builder.build_ptr_to_int(context_ptr, get_usize_llvm_type(context.llvm_context), "")?.into());
+                                },
+                                FnSpecCallArg::VariadicPointer { value_type, .. } => {
+                                    // the array always has at least one element,
+                                    // so that the pointer is valid for an empty
+                                    // slice
+                                    let usize_type = get_usize_llvm_type(context.llvm_context);
+                                    let element_type = IRValueType::from_value_type(&value_type, context.llvm_context).to_basic_type();
+                                    let array_type = element_type.array_type(variadic_count.max(1) as u32);
+                                    let array_ptr = build_entry_alloca(array_type.into(), context)?;
+
+                                    for (i, value) in call_arg_values[variadic_start..].iter().enumerate() {
+                                        let slot = unsafe { context.builder.build_in_bounds_gep(array_type, array_ptr, &[usize_type.const_zero(), usize_type.const_int(i as u64, false)], "") }?;
+                                        match value {
+                                            Self::Int { inner, .. } => context.builder.build_store(slot, *inner)?,
+                                            Self::Float { inner } => context.builder.build_store(slot, *inner)?,
+                                        };
+                                    }
+
+                                    arg_types.push(ValueType::USize);
+                                    llvm_args.push(context.builder.build_ptr_to_int(array_ptr, usize_type, "")?.into());
+                                },
+                                FnSpecCallArg::VariadicLength => {
+                                    arg_types.push(ValueType::USize);
+                                    llvm_args.push(get_usize_llvm_type(context.llvm_context).const_int(variadic_count as u64, false).into());
+                                },
                             }
                         }
 
-                        let fn_type = get_fn_llvm_type(context.llvm_context, resolved_type, arg_types);
+                        let fn_type = get_fn_llvm_type(context.llvm_context, ret_types[0], arg_types);
                         let ptr_type = context.llvm_context.ptr_type(AddressSpace::default());
                         let ptr_val = get_usize_llvm_type(context.llvm_context).const_int(fn_ptr.addr() as u64, false).const_to_pointer(ptr_type);
                         let ret_val = context.builder.build_indirect_call(fn_type, ptr_val, llvm_args.as_slice(), "")?;
 
+                        if checks_eval_status {
+                            build_host_error_check(idx, context)?;
+                        }
+
+                        if ret_idx > 0 {
+                            let out_ptr = *ret_out_ptrs.get(ret_idx - 1).ok_or(CodegenError::UnexpectedFunctionReturnValue)?;
+                            return Self::from_ptr_load(out_ptr, &resolved_type, context);
+                        }
+
                         match ret_val.try_as_basic_value() {
                             ValueKind::Basic(basic_value_enum) => {
                                 match basic_value_enum {
@@ -264,6 +1111,10 @@ impl<'ctx> IRValue<'ctx> {
                         }
                     },
                     FnSpecChoice::Const { value } => {
+                        if ret_types.len() > 1 {
+                            return Err(CodegenError::MultiReturnSpecConst.into());
+                        }
+
                         let actual_type = value.get_value_type();
                         if actual_type != resolved_type {
                             return Err(CodegenError::BadSpecConst { actual_type, expected_type: resolved_type }.into());
@@ -273,14 +1124,38 @@ impl<'ctx> IRValue<'ctx> {
                     },
                 }
             },
+            PackedAnalysisNodeData::MathIntrinsicCall { intrinsic, arg_idxs } => Self::from_math_intrinsic(aast, idx, *intrinsic, arg_idxs, resolved_type, context)?,
             PackedAnalysisNodeData::UnaryOperation { operator, right_idx } => {
                 let inner_val = Self::from_aast_node(aast, *right_idx, context)?;
 
                 match operator {
                     UnaryOperator::Negate => {
-                        match inner_val.cast_if_needed(aast.get_node_type(*right_idx)?, resolved_type, context)? {
+                        let right_type = aast.get_node_type(*right_idx)?;
+                        match inner_val.cast_if_needed(right_type, resolved_type, *right_idx, context)? {
                             IRValue::Int { inner, is_signed } => IRValue::Int {
-                                inner: context.builder.build_int_neg(inner, "")?,
+                                inner: if context.arithmetic_mode != ArithmeticMode::Checked {
+                                    context.builder.build_int_neg(inner, "")?
+                                } else if !is_signed {
+                                    // only zero can be negated without
+                                    // overflowing an unsigned type
+                                    let zero = inner.get_type().const_zero();
+                                    Self::build_int_arith_checked("llvm.usub.with.overflow", zero, inner, idx, context)?
+                                } else if right_type.is_integer() && !right_type.is_signed() {
+                                    // the unsigned operand was cast to the
+                                    // signed type without changing its bits,
+                                    // so it still has to be compared as
+                                    // unsigned. the negation only fits if the
+                                    // operand is at most the magnitude of the
+                                    // signed type's minimum value
+                                    let int_type = inner.get_type();
+                                    let min_magnitude = context.builder.build_left_shift(int_type.const_int(1, false), int_type.const_int((int_type.get_bit_width() - 1).into(), false), "")?;
+                                    let overflowed = context.builder.build_int_compare(IntPredicate::UGT, inner, min_magnitude, "")?;
+                                    build_eval_error_check(overflowed, EvalErrorKind::Overflow, idx, context)?;
+                                    context.builder.build_int_neg(inner, "")?
+                                } else {
+                                    let zero = inner.get_type().const_zero();
+                                    Self::build_int_arith_checked("llvm.ssub.with.overflow", zero, inner, idx, context)?
+                                },
                                 is_signed,
                             },
                             IRValue::Float { inner } => IRValue::Float {
@@ -308,51 +1183,56 @@ impl<'ctx> IRValue<'ctx> {
             },
             PackedAnalysisNodeData::BinaryOperation { operator, left_idx, right_idx } => {
                 match operator {
-                    BinaryOperator::Mul => Self::from_binary_op(aast, resolved_type, *left_idx, *right_idx, context, |lhs, rhs, _, context|{
-                        context.builder.build_int_mul(lhs, rhs, "")
+                    BinaryOperator::Pow => Self::from_pow(aast, idx, *left_idx, *right_idx, resolved_type, context)?,
+                    BinaryOperator::Mul => Self::from_binary_op(aast, resolved_type, *left_idx, *right_idx, context, |lhs, rhs, is_signed, context|{
+                        Self::build_int_arith(operator, lhs, rhs, is_signed, idx, context)
                     }, |lhs, rhs, context|{
-                        context.builder.build_float_mul(lhs, rhs, "")
+                        Ok(context.builder.build_float_mul(lhs, rhs, "")?)
                     })?,
                     BinaryOperator::Div => Self::from_binary_op(aast, resolved_type, *left_idx, *right_idx, context, |lhs, rhs, is_signed, context|{
-                        if is_signed {
-                            context.builder.build_int_signed_div(lhs, rhs, "")
-                        } else {
-                            context.builder.build_int_unsigned_div(lhs, rhs, "")
-                        }
+                        Self::build_int_div_or_rem(lhs, rhs, is_signed, false, idx, context)
                     }, |lhs, rhs, context|{
-                        context.builder.build_float_div(lhs, rhs, "")
+                        Ok(context.builder.build_float_div(lhs, rhs, "")?)
                     })?,
-                    // FIXME: verify this behaviour. it feels off, as this isn't modulo: https://llvm.org/docs/LangRef.html#urem-instruction
+                    // remainder, not modulo: the result has the sign of the
+                    // dividend, same as Rust's %
                     BinaryOperator::Mod => Self::from_binary_op(aast, resolved_type, *left_idx, *right_idx, context, |lhs, rhs, is_signed, context|{
-                        if is_signed {
-                            context.builder.build_int_signed_rem(lhs, rhs, "")
-                        } else {
-                            context.builder.build_int_unsigned_rem(lhs, rhs, "")
-                        }
+                        Self::build_int_div_or_rem(lhs, rhs, is_signed, true, idx, context)
                     }, |lhs, rhs, context|{
-                        context.builder.build_float_rem(lhs, rhs, "")
+                        Ok(context.builder.build_float_rem(lhs, rhs, "")?)
                     })?,
-                    BinaryOperator::Add => Self::from_binary_op(aast, resolved_type, *left_idx, *right_idx, context, |lhs, rhs, _, context|{
-                        context.builder.build_int_add(lhs, rhs, "")
+                    BinaryOperator::Add => Self::from_binary_op(aast, resolved_type, *left_idx, *right_idx, context, |lhs, rhs, is_signed, context|{
+                        Self::build_int_arith(operator, lhs, rhs, is_signed, idx, context)
                     }, |lhs, rhs, context|{
-                        context.builder.build_float_add(lhs, rhs, "")
+                        Ok(context.builder.build_float_add(lhs, rhs, "")?)
                     })?,
-                    BinaryOperator::Sub => Self::from_binary_op(aast, resolved_type, *left_idx, *right_idx, context, |lhs, rhs, _, context|{
-                        context.builder.build_int_sub(lhs, rhs, "")
+                    BinaryOperator::Sub => Self::from_binary_op(aast, resolved_type, *left_idx, *right_idx, context, |lhs, rhs, is_signed, context|{
+                        Self::build_int_arith(operator, lhs, rhs, is_signed, idx, context)
                     }, |lhs, rhs, context|{
-                        context.builder.build_float_sub(lhs, rhs, "")
+                        Ok(context.builder.build_float_sub(lhs, rhs, "")?)
+                    })?,
+                    BinaryOperator::SaturatingAdd |
+                    BinaryOperator::SaturatingSub |
+                    BinaryOperator::SaturatingMul |
+                    BinaryOperator::WrappingAdd |
+                    BinaryOperator::WrappingSub |
+                    BinaryOperator::WrappingMul => Self::from_binary_op(aast, resolved_type, *left_idx, *right_idx, context, |lhs, rhs, is_signed, context|{
+                        Self::build_int_arith(operator, lhs, rhs, is_signed, idx, context)
+                    }, |_, _, _|{
+                        // integer-only operation; rejected during analysis
+                        Err(Box::new(AnalysisError::BadAnalysis))
                     })?,
-                    BinaryOperator::Equals => Self::from_compare_op(aast, *left_idx, *right_idx, context, IntPredicate::EQ, IntPredicate::EQ, FloatPredicate::OEQ)?,
-                    BinaryOperator::NotEquals => Self::from_compare_op(aast, *left_idx, *right_idx, context, IntPredicate::NE, IntPredicate::NE, FloatPredicate::ONE)?,
-                    BinaryOperator::LesserThanEquals => Self::from_compare_op(aast, *left_idx, *right_idx, context, IntPredicate::ULE, IntPredicate::SLE, FloatPredicate::OLE)?,
-                    BinaryOperator::GreaterThanEquals => Self::from_compare_op(aast, *left_idx, *right_idx, context, IntPredicate::UGE, IntPredicate::SGE, FloatPredicate::OGE)?,
-                    BinaryOperator::LesserThan => Self::from_compare_op(aast, *left_idx, *right_idx, context, IntPredicate::ULT, IntPredicate::SLT, FloatPredicate::OLT)?,
-                    BinaryOperator::GreaterThan => Self::from_compare_op(aast, *left_idx, *right_idx, context, IntPredicate::UGT, IntPredicate::SGT, FloatPredicate::OGT)?,
+                    BinaryOperator::Equals |
+                    BinaryOperator::NotEquals |
+                    BinaryOperator::LesserThanEquals |
+                    BinaryOperator::GreaterThanEquals |
+                    BinaryOperator::LesserThan |
+                    BinaryOperator::GreaterThan => Self::from_compare_op(aast, operator, *left_idx, *right_idx, context)?,
                     BinaryOperator::LogicalAnd => {
                         let left_idx = *left_idx;
                         let right_idx = *right_idx;
 
-                        Self::from_branching_expr(aast, context, ValueType::Bool, |aast, context| {
+                        Self::from_branching_expr(aast, idx, context, ValueType::Bool, |aast, context| {
                             Ok((Self::from_aast_node(aast, left_idx, context)?, aast.get_node_type(left_idx)?))
                         }, |aast, context| {
                             Ok((Self::from_aast_node(aast, right_idx, context)?, aast.get_node_type(right_idx)?))
@@ -364,7 +1244,7 @@ impl<'ctx> IRValue<'ctx> {
                         let left_idx = *left_idx;
                         let right_idx = *right_idx;
 
-                        Self::from_branching_expr(aast, context, ValueType::Bool, |aast, context| {
+                        Self::from_branching_expr(aast, idx, context, ValueType::Bool, |aast, context| {
                             Ok((Self::from_aast_node(aast, left_idx, context)?, aast.get_node_type(left_idx)?))
                         }, |_, context| {
                             Ok((Self::from_ast_typed_value(&Value::Bool { inner: true }, context), ValueType::Bool))
@@ -380,27 +1260,27 @@ impl<'ctx> IRValue<'ctx> {
                     None => Err(CodegenError::UnknownBinding { name: name.clone() }),
                 }?;
 
-                let slab_idx = *match info {
-                    SlabBindingInfo::Variable { idx, value_type } => {
-                        if *value_type != resolved_type {
-                            Err(CodegenError::BadBindingType { name: name.clone(), actual_type: resolved_type, expected_type: *value_type })
-                        } else {
-                            Ok(idx)
-                        }
-                    },
-                    SlabBindingInfo::Function { .. } => {
-                        Err(CodegenError::BadBindingKind { name: name.clone(), is_var: false })
-                    },
-                }?;
+                let (SlabBindingInfo::Variable { value_type, .. } | SlabBindingInfo::Input { value_type, .. }) = info else {
+                    return Err(Box::new(CodegenError::BadBindingKind { name: name.clone(), is_var: false }))
+                };
+
+                if *value_type != resolved_type {
+                    return Err(Box::new(CodegenError::BadBindingType { name: name.clone(), actual_type: resolved_type, expected_type: *value_type }))
+                }
 
-                IRValue::from_slab_value(slab_idx, &resolved_type, context)?
+                match info {
+                    SlabBindingInfo::Variable { idx, .. } => IRValue::from_slab_value(*idx, &resolved_type, context)?,
+                    SlabBindingInfo::Input { offset, .. } => IRValue::from_input_value(*offset, &resolved_type, context)?,
+                    SlabBindingInfo::Function { .. } |
+                    SlabBindingInfo::Set { .. } => unreachable!(),
+                }
             },
             PackedAnalysisNodeData::Ternary { cond_idx, left_idx, right_idx } => {
                 let cond_idx = *cond_idx;
                 let left_idx = *left_idx;
                 let right_idx = *right_idx;
 
-                Self::from_branching_expr(aast, context, resolved_type, |aast, context| {
+                Self::from_branching_expr(aast, idx, context, resolved_type, |aast, context| {
                     Ok((Self::from_aast_node(aast, cond_idx, context)?, aast.get_node_type(cond_idx)?))
                 }, |aast, context| {
                     Ok((Self::from_aast_node(aast, left_idx, context)?, aast.get_node_type(left_idx)?))
@@ -408,6 +1288,13 @@ impl<'ctx> IRValue<'ctx> {
                     Ok((Self::from_aast_node(aast, right_idx, context)?, aast.get_node_type(right_idx)?))
                 })?
             },
+            PackedAnalysisNodeData::ComparisonChain { operators, operand_idxs } => {
+                let first_idx = operand_idxs[0];
+                let first = (Self::from_aast_node(aast, first_idx, context)?, aast.get_node_type(first_idx)?, first_idx);
+                Self::from_comparison_chain(aast, idx, operators, &operand_idxs[1..], first, context)?
+            },
+            PackedAnalysisNodeData::Membership { set, value_idx, item_idxs } => Self::from_membership(aast, idx, *set, *value_idx, item_idxs, context)?,
+            PackedAnalysisNodeData::SetMembership { name, value_idx, set, is_updatable } => Self::from_set_membership(aast, name, *value_idx, set, *is_updatable, context)?,
         })
     }
 
@@ -417,12 +1304,15 @@ impl<'ctx> IRValue<'ctx> {
             ValueType::U16 |
             ValueType::U32 |
             ValueType::U64 |
+            ValueType::U128 |
             ValueType::USize |
             ValueType::Bool => Ok(IRValue::Int { inner, is_signed: false }),
             ValueType::I8 |
             ValueType::I16 |
             ValueType::I32 |
-            ValueType::I64 => Ok(IRValue::Int { inner, is_signed: true }),
+            ValueType::I64 |
+            ValueType::I128 |
+            ValueType::ISize => Ok(IRValue::Int { inner, is_signed: true }),
             _ => Err(CodegenError::UnexpectedBaseType),
         }
     }
@@ -436,43 +1326,53 @@ impl<'ctx> IRValue<'ctx> {
     }
 
     fn from_slab_value<'build>(slab_idx: usize, slab_value_type: &ValueType, context: &CodegenContext<'ctx, 'build>) -> Result<Self, Box<dyn Error>> {
-        let pointee_type = IRValueType::from_value_type(slab_value_type, context.llvm_context);
         let ptr = context.slab.get_address(slab_idx);
         let ptr_type = context.llvm_context.ptr_type(AddressSpace::default());
         let ptr_val = get_usize_llvm_type(context.llvm_context).const_int(ptr as u64, false).const_to_pointer(ptr_type);
+        Self::from_ptr_load(ptr_val, slab_value_type, context)
+    }
 
-        let res = match pointee_type {
+    fn from_input_value<'build>(offset: usize, input_value_type: &ValueType, context: &CodegenContext<'ctx, 'build>) -> Result<Self, Box<dyn Error>> {
+        let inputs_ptr = context.func.get_first_param().ok_or(CodegenError::MissingInputsParameter)?.into_pointer_value();
+        let offset_val = get_usize_llvm_type(context.llvm_context).const_int(offset as u64, false);
+        let ptr_val = unsafe { context.builder.build_in_bounds_gep(context.llvm_context.i8_type(), inputs_ptr, &[offset_val], "") }?;
+        Self::from_ptr_load(ptr_val, input_value_type, context)
+    }
+
+    fn from_ptr_load<'build>(ptr_val: PointerValue<'ctx>, value_type: &ValueType, context: &CodegenContext<'ctx, 'build>) -> Result<Self, Box<dyn Error>> {
+        let res = match IRValueType::from_value_type(value_type, context.llvm_context) {
             IRValueType::Int { llvm, is_signed: _ } => context.builder.build_load(llvm, ptr_val, ""),
             IRValueType::Float { llvm } => context.builder.build_load(llvm, ptr_val, ""),
         }?;
 
         match res {
-            BasicValueEnum::IntValue(inner) => Ok(IRValue::from_int_value(inner, *slab_value_type)?),
-            BasicValueEnum::FloatValue(inner) => Ok(IRValue::from_float_value(inner, *slab_value_type)?),
+            BasicValueEnum::IntValue(inner) => Ok(IRValue::from_int_value(inner, *value_type)?),
+            BasicValueEnum::FloatValue(inner) => Ok(IRValue::from_float_value(inner, *value_type)?),
             _ => Err(Box::new(CodegenError::UnexpectedBasicValueEnum)),
         }
     }
 
+    /// Constant integers (including bools) as an i128, so that signed and
+    /// unsigned keys are both ordered correctly
+    fn get_const_int_key(&self) -> Option<i128> {
+        match self.get_ir_const()? {
+            IRConst::Int { inner } => Some(inner.into()),
+            IRConst::Uint { inner } => Some(inner.into()),
+            IRConst::Float { .. } => None,
+        }
+    }
+
     fn get_ir_const(&self) -> Option<IRConst> {
         match *self {
             IRValue::Int { inner, is_signed } => {
                 if is_signed {
-                    match inner.get_sign_extended_constant() {
-                        Some(inner) => Some(IRConst::Int { inner }),
-                        None => None,
-                    }
+                    inner.get_sign_extended_constant().map(|inner| IRConst::Int { inner })
                 } else {
-                    match inner.get_zero_extended_constant() {
-                        Some(inner) => Some(IRConst::Uint { inner }),
-                        None => None,
-                    }
+                    inner.get_zero_extended_constant().map(|inner| IRConst::Uint { inner })
                 }
             },
             IRValue::Float { inner } => {
-                match inner.get_constant() {
-                    Some((inner, _)) => Some(IRConst::Float { inner }),
-                    None => None,
-                }
+                inner.get_constant().map(|(inner, _)| IRConst::Float { inner })
             },
         }
     }
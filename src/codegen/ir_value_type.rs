@@ -1,4 +1,4 @@
-use inkwell::{context::Context, types::{FloatType, IntType}};
+use inkwell::{context::Context, types::{BasicTypeEnum, FloatType, IntType}};
 
 use crate::common::value_type::ValueType;
 
@@ -16,14 +16,24 @@ impl<'ctx> IRValueType<'ctx> {
             ValueType::U16 => Self::Int { llvm: llvm_ctx.i16_type(), is_signed: false },
             ValueType::U32 => Self::Int { llvm: llvm_ctx.i32_type(), is_signed: false },
             ValueType::U64 => Self::Int { llvm: llvm_ctx.i64_type(), is_signed: false },
+            ValueType::U128 => Self::Int { llvm: llvm_ctx.i128_type(), is_signed: false },
             ValueType::USize => Self::Int { llvm: get_usize_llvm_type(llvm_ctx), is_signed: false },
             ValueType::I8 => Self::Int { llvm: llvm_ctx.i8_type(), is_signed: true },
             ValueType::I16 => Self::Int { llvm: llvm_ctx.i16_type(), is_signed: true },
             ValueType::I32 => Self::Int { llvm: llvm_ctx.i32_type(), is_signed: true },
             ValueType::I64 => Self::Int { llvm: llvm_ctx.i64_type(), is_signed: true },
+            ValueType::I128 => Self::Int { llvm: llvm_ctx.i128_type(), is_signed: true },
+            ValueType::ISize => Self::Int { llvm: get_usize_llvm_type(llvm_ctx), is_signed: true },
             ValueType::F32 => Self::Float { llvm: llvm_ctx.f32_type() },
             ValueType::F64 => Self::Float { llvm: llvm_ctx.f64_type() },
             ValueType::Bool => Self::Int { llvm: llvm_ctx.bool_type(), is_signed: false },
         }
     }
+
+    pub fn to_basic_type(&self) -> BasicTypeEnum<'ctx> {
+        match self {
+            Self::Int { llvm, .. } => (*llvm).into(),
+            Self::Float { llvm } => (*llvm).into(),
+        }
+    }
 }
\ No newline at end of file
@@ -7,6 +7,12 @@ pub struct JITContext {
     next: usize,
 }
 
+impl Default for JITContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl JITContext {
     pub fn new() -> Self {
         // HACK this is needed due to a bug in inkwell where compiling a project
@@ -6,4 +6,5 @@ pub mod ir_value;
 pub mod ir_value_type;
 pub mod jit_context;
 pub mod error;
-pub mod compilation_context;
\ No newline at end of file
+pub mod compilation_context;
+pub mod arithmetic_mode;
\ No newline at end of file
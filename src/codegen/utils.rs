@@ -1,6 +1,10 @@
-use inkwell::{context::Context, types::{BasicMetadataTypeEnum, FunctionType, IntType}};
+use std::{error::Error, mem::offset_of};
 
-use crate::{codegen::ir_value_type::IRValueType, common::value_type::ValueType};
+use inkwell::{AddressSpace, IntPredicate, context::Context, intrinsics::Intrinsic, types::{BasicMetadataTypeEnum, BasicTypeEnum, FunctionType, IntType}, values::{BasicMetadataValueEnum, BasicValueEnum, IntValue, PointerValue, ValueKind}};
+
+use crate::{codegen::ir_value_type::IRValueType, common::{eval_error::{EvalErrorKind, EvalStatus}, value_type::ValueType}};
+
+use super::{codegen_context::CodegenContext, error::CodegenError};
 
 pub fn get_usize_llvm_type<'ctx>(llvm_ctx: &'ctx Context) -> IntType<'ctx> {
     match size_of::<usize>() {
@@ -26,7 +30,10 @@ pub fn get_fn_llvm_type<'ctx>(llvm_ctx: &'ctx Context, ret_type: ValueType, arg_
             ValueType::I32 => llvm_ctx.i32_type().into(),
             ValueType::U64 |
             ValueType::I64 => llvm_ctx.i64_type().into(),
-            ValueType::USize => get_usize_llvm_type(llvm_ctx).into(),
+            ValueType::U128 |
+            ValueType::I128 => llvm_ctx.i128_type().into(),
+            ValueType::USize |
+            ValueType::ISize => get_usize_llvm_type(llvm_ctx).into(),
             ValueType::F32 => llvm_ctx.f32_type().into(),
             ValueType::F64 => llvm_ctx.f64_type().into(),
             ValueType::Bool => llvm_ctx.bool_type().into(),
@@ -41,4 +48,72 @@ pub fn get_fn_llvm_type<'ctx>(llvm_ctx: &'ctx Context, ret_type: ValueType, arg_
             llvm.fn_type(param_types.iter().as_slice(), false)
         },
     }
+}
+
+/// Allocas in the entry block can be promoted to registers by LLVM, unlike
+/// allocas in other blocks
+pub fn build_entry_alloca<'ctx, 'build>(llvm_type: BasicTypeEnum<'ctx>, context: &CodegenContext<'ctx, 'build>) -> Result<PointerValue<'ctx>, Box<dyn Error>> {
+    let builder = context.llvm_context.create_builder();
+    let entry_block = context.func.get_first_basic_block().ok_or(CodegenError::UnexpectedBaseType)?;
+    match entry_block.get_first_instruction() {
+        Some(instruction) => builder.position_before(&instruction),
+        None => builder.position_at_end(entry_block),
+    }
+
+    Ok(builder.build_alloca(llvm_type, "")?)
+}
+
+pub fn build_intrinsic_call<'ctx, 'build>(name: &'static str, overload_types: &[BasicTypeEnum<'ctx>], args: &[BasicMetadataValueEnum<'ctx>], context: &CodegenContext<'ctx, 'build>) -> Result<BasicValueEnum<'ctx>, Box<dyn Error>> {
+    let intrinsic = Intrinsic::find(name).ok_or(CodegenError::UnknownIntrinsic { name })?;
+    let function = intrinsic.get_declaration(context.module, overload_types).ok_or(CodegenError::UnknownIntrinsic { name })?;
+
+    match context.builder.build_call(function, args, "")?.try_as_basic_value() {
+        ValueKind::Basic(value) => Ok(value),
+        ValueKind::Instruction(..) => Err(Box::new(CodegenError::UnexpectedFunctionReturnValue)),
+    }
+}
+
+pub fn get_const_address_ptr<'ctx>(llvm_ctx: &'ctx Context, address: usize) -> PointerValue<'ctx> {
+    let ptr_type = llvm_ctx.ptr_type(AddressSpace::default());
+    get_usize_llvm_type(llvm_ctx).const_int(address as u64, false).const_to_pointer(ptr_type)
+}
+
+/// Aborts the evaluation if `failed` is true, by storing the error in the
+/// Slab's EvalStatus and returning a zero value. The builder is positioned at
+/// the non-failing path afterwards
+pub fn build_eval_error_check<'ctx, 'build>(failed: IntValue<'ctx>, kind: EvalErrorKind, node_idx: usize, context: &CodegenContext<'ctx, 'build>) -> Result<(), Box<dyn Error>> {
+    build_eval_abort_if(failed, Some(kind), node_idx, context)
+}
+
+/// Aborts the evaluation if the host function that was just called reported
+/// an error in the Slab's EvalStatus. Only the node is stored, since the host
+/// function already stored the error kind
+pub fn build_host_error_check<'ctx, 'build>(node_idx: usize, context: &CodegenContext<'ctx, 'build>) -> Result<(), Box<dyn Error>> {
+    let usize_type = get_usize_llvm_type(context.llvm_context);
+    let kind_ptr = get_const_address_ptr(context.llvm_context, context.slab.get_eval_status_address() + offset_of!(EvalStatus, kind));
+    let kind = context.builder.build_load(usize_type, kind_ptr, "")?.into_int_value();
+    let failed = context.builder.build_int_compare(IntPredicate::NE, kind, usize_type.const_zero(), "")?;
+    build_eval_abort_if(failed, None, node_idx, context)
+}
+
+fn build_eval_abort_if<'ctx, 'build>(failed: IntValue<'ctx>, kind: Option<EvalErrorKind>, node_idx: usize, context: &CodegenContext<'ctx, 'build>) -> Result<(), Box<dyn Error>> {
+    let fail_block = context.llvm_context.append_basic_block(*context.func, "");
+    let ok_block = context.llvm_context.append_basic_block(*context.func, "");
+    context.builder.build_conditional_branch(failed, fail_block, ok_block)?;
+
+    context.builder.position_at_end(fail_block);
+    let usize_type = get_usize_llvm_type(context.llvm_context);
+    let status_address = context.slab.get_eval_status_address();
+    if let Some(kind) = kind {
+        let kind_ptr = get_const_address_ptr(context.llvm_context, status_address + offset_of!(EvalStatus, kind));
+        context.builder.build_store(kind_ptr, usize_type.const_int(kind as u64, false))?;
+    }
+
+    let node_ptr = get_const_address_ptr(context.llvm_context, status_address + offset_of!(EvalStatus, node));
+    context.builder.build_store(node_ptr, usize_type.const_int(node_idx as u64, false))?;
+    let ret_type = context.func.get_type().get_return_type().ok_or(CodegenError::UnexpectedBaseType)?;
+    context.builder.build_return(Some(&ret_type.const_zero()))?;
+
+    context.builder.position_at_end(ok_block);
+    Ok(())
 }
\ No newline at end of file
@@ -1,18 +1,58 @@
-use std::ffi::c_void;
+use std::{any::{TypeId, type_name}, ffi::c_void, sync::Arc};
 
-use super::{error::CommonError, ir_const::IRConst, value::Value, value_type::ValueType};
+use super::{error::CommonError, host_function::HostFunction, host_set::HostSetData, ir_const::IRConst, table::HiddenState, value::Value, value_type::ValueType};
 
 pub type FnPointer = *const c_void;
 
+/// Compared by TypeId; the name is only used in error messages
+#[derive(Clone, Copy, Debug)]
+pub struct PointeeType {
+    type_id: TypeId,
+    type_name: &'static str,
+}
+
+impl PointeeType {
+    pub fn of<T: 'static>() -> Self {
+        Self { type_id: TypeId::of::<T>(), type_name: type_name::<T>() }
+    }
+
+    pub const fn get_type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    pub const fn get_type_name(&self) -> &'static str {
+        self.type_name
+    }
+}
+
+impl PartialEq for PointeeType {
+    fn eq(&self, other: &Self) -> bool {
+        self.type_id == other.type_id
+    }
+}
+
+impl Eq for PointeeType { }
+
 #[derive(Clone, Copy)]
 pub enum FnSpecCallArg {
     MappedArgument { param_idx: usize },
     ConstArgument { value: Value },
-    HiddenStateArgument { hidden_state_idx: usize, cast_to_type: Option<ValueType> },
+    HiddenStateArgument { hidden_state_idx: usize, state_type: ValueType, pointee_type: Option<PointeeType>, cast_to_type: Option<ValueType> },
+    /// Address of the Slab's [super::eval_error::EvalStatus]
+    EvalStatus,
+    /// One out pointer per return value after the first
+    ReturnOutPointers,
+    /// The `*mut C` context pointer passed when evaluating the expression
+    Context { context_type: PointeeType },
+    /// Address of the arguments after the last mapped parameter
+    VariadicPointer { value_type: ValueType, pointee_type: PointeeType },
+    VariadicLength,
 }
 
+#[derive(Clone)]
 pub enum FnSpecChoice {
     Call { fn_ptr: FnPointer, args: Box<[FnSpecCallArg]> },
+    /// Only valid for functions with a single return value
     Const { value: Value },
 }
 
@@ -21,21 +61,150 @@ pub struct FnSpecHints {
 }
 
 pub type BindingFuncParams = Box<[ValueType]>;
+/// Values after the first are written to [FnSpecCallArg::ReturnOutPointers]
+pub type BindingFuncRets = Box<[ValueType]>;
 pub type FnSpec<'table> = Box<dyn Fn(FnSpecHints) -> Result<FnSpecChoice, String> + 'table>;
 
+/// Name and default value of a function parameter
+#[derive(Debug, Clone)]
+pub struct ParamDef {
+    pub name: String,
+    pub default: Option<Value>,
+}
+
+/// A parameter of a [Binding::GenericFunction]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GenericParam {
+    Concrete(ValueType),
+    /// All generic parameters of a function share the same type
+    Generic,
+}
+
+/// Types allowed for the type parameter of a [Binding::GenericFunction]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TypeConstraint {
+    Any,
+    Numeric,
+    Integer,
+    Float,
+}
+
+/// A generic function specialised for some concrete parameter types
+pub struct GenericFnInstance {
+    params: BindingFuncParams,
+    ret_types: BindingFuncRets,
+    choice: FnSpecChoice,
+}
+
+/// Called during analysis with the concrete parameter types of a call
+pub type GenericFnSpec<'table> = Box<dyn Fn(&[ValueType]) -> Result<GenericFnInstance, String> + 'table>;
+
 pub enum Binding<'table> {
     Const { value: Value },
     Variable { value_type: ValueType },
-    Function { ret_type: ValueType, params: BindingFuncParams, fn_spec: FnSpec<'table> },
+    Input { offset: usize, value_type: ValueType },
+    /// param_defs has one entry per param
+    Function { ret_types: BindingFuncRets, params: BindingFuncParams, variadic: Option<ValueType>, param_defs: Option<Box<[ParamDef]>>, fn_spec: FnSpec<'table> },
+    GenericFunction { params: Box<[GenericParam]>, constraint: TypeConstraint, fn_spec: GenericFnSpec<'table> },
+    /// handle_idx is only set for updatable sets
+    Set { data: Arc<HostSetData>, handle_idx: Option<usize> },
+}
+
+impl ParamDef {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into(), default: None }
+    }
+
+    pub fn with_default(name: impl Into<String>, default: impl Into<Value>) -> Self {
+        Self { name: name.into(), default: Some(default.into()) }
+    }
+}
+
+impl Binding<'_> {
+    /// None if the function doesn't accept argc arguments
+    pub fn get_call_params(&self, argc: usize) -> Option<Box<[ValueType]>> {
+        match self {
+            Self::Function { params, variadic: None, .. } if params.len() == argc => Some(params.clone()),
+            Self::Function { params, variadic: Some(variadic), .. } if params.len() <= argc => {
+                Some(params.iter().copied().chain(std::iter::repeat_n(*variadic, argc - params.len())).collect())
+            },
+            _ => None,
+        }
+    }
+}
+
+impl TypeConstraint {
+    pub const fn allows(&self, value_type: ValueType) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Numeric => !matches!(value_type, ValueType::Bool),
+            Self::Integer => value_type.is_integer(),
+            Self::Float => value_type.is_float(),
+        }
+    }
+}
+
+impl GenericFnInstance {
+    /// Panics if the function has captures
+    pub fn from_function<A, F: HostFunction<A>>(function: F) -> Self {
+        let params = F::get_param_types();
+        let args = (0..params.len()).map(|param_idx| FnSpecCallArg::MappedArgument { param_idx }).collect::<Vec<_>>();
+        let (shim, target) = function.get_shim().expect("generic function instances must be fn items, fn pointers or closures without captures");
+
+        Self {
+            params,
+            ret_types: F::get_ret_types(),
+            choice: FnSpecChoice::Call { fn_ptr: shim, args: FnSpecCallArg::shim_args(target, &args) },
+        }
+    }
+
+    pub fn get_params(&self) -> &[ValueType] {
+        &self.params
+    }
+
+    pub fn get_ret_types(&self) -> &[ValueType] {
+        &self.ret_types
+    }
+
+    pub fn get_choice(&self) -> &FnSpecChoice {
+        &self.choice
+    }
 }
 
 impl FnSpecCallArg {
-    pub fn from_hidden_state(hidden_state_idx: usize) -> Self {
-        Self::HiddenStateArgument { hidden_state_idx, cast_to_type: None }
+    pub fn from_hidden_state<T: ToBFPValueType>(state: HiddenState<T>) -> Self {
+        Self::HiddenStateArgument {
+            hidden_state_idx: state.get_index(),
+            state_type: T::to_bfp_value_type(),
+            pointee_type: T::to_bfp_pointee_type(),
+            cast_to_type: None,
+        }
+    }
+
+    pub fn from_hidden_state_cast<T: ToBFPValueType>(state: HiddenState<T>, value_type: ValueType) -> Self {
+        Self::HiddenStateArgument {
+            hidden_state_idx: state.get_index(),
+            state_type: T::to_bfp_value_type(),
+            pointee_type: None,
+            cast_to_type: Some(value_type),
+        }
     }
 
-    pub fn from_hidden_state_cast(hidden_state_idx: usize, value_type: ValueType) -> Self {
-        Self::HiddenStateArgument { hidden_state_idx, cast_to_type: Some(value_type) }
+    pub fn from_context<C: 'static>() -> Self {
+        Self::Context { context_type: PointeeType::of::<C>() }
+    }
+
+    /// Must be followed by [Self::VariadicLength]
+    pub fn from_variadic<T: ToBFPValueType + 'static>() -> Self {
+        Self::VariadicPointer { value_type: T::to_bfp_value_type(), pointee_type: PointeeType::of::<T>() }
+    }
+
+    /// Prepends the shim's target, EvalStatus and out pointer arguments
+    pub fn shim_args(target: usize, args: &[Self]) -> Box<[Self]> {
+        [Self::ConstArgument { value: target.into() }, Self::EvalStatus, Self::ReturnOutPointers].iter()
+            .chain(args)
+            .copied()
+            .collect()
     }
 
     pub fn guard<T: ToBFPValueType>(&self, params: &BindingFuncParams) -> Result<(), CommonError> {
@@ -51,13 +220,34 @@ impl FnSpecCallArg {
             Self::ConstArgument { ref value } => {
                 value.get_value_type()
             },
-            Self::HiddenStateArgument { hidden_state_idx: _, cast_to_type } => {
-                if let Some(value_type) = cast_to_type {
-                    value_type
-                } else {
-                    return Ok(())
+            Self::HiddenStateArgument { hidden_state_idx: _, state_type, pointee_type, cast_to_type } => {
+                // if the parameter is a pointer, then the hidden state must
+                // point to the same type
+                if let Some(expected) = T::to_bfp_pointee_type() && pointee_type != Some(expected) {
+                    return Err(CommonError::FuncSpecArgBadPointee { expected: expected.get_type_name(), got: pointee_type.map(|got| got.get_type_name()) })
+                }
+
+                cast_to_type.unwrap_or(state_type)
+            },
+            Self::EvalStatus |
+            Self::ReturnOutPointers => ValueType::USize,
+            Self::Context { context_type } => {
+                let got = T::to_bfp_pointee_type();
+                if got != Some(context_type) {
+                    return Err(CommonError::FuncSpecArgBadPointee { expected: context_type.get_type_name(), got: got.map(|got| got.get_type_name()) })
+                }
+
+                ValueType::USize
+            },
+            Self::VariadicPointer { value_type: _, pointee_type } => {
+                let got = T::to_bfp_pointee_type();
+                if got != Some(pointee_type) {
+                    return Err(CommonError::FuncSpecArgBadPointee { expected: pointee_type.get_type_name(), got: got.map(|got| got.get_type_name()) })
                 }
+
+                ValueType::USize
             },
+            Self::VariadicLength => ValueType::USize,
         };
 
         let expected = T::to_bfp_value_type();
@@ -74,32 +264,116 @@ impl From<u8> for FnSpecCallArg { fn from(x: u8) -> Self { Self::ConstArgument {
 impl From<u16> for FnSpecCallArg { fn from(x: u16) -> Self { Self::ConstArgument { value: x.into() } } }
 impl From<u32> for FnSpecCallArg { fn from(x: u32) -> Self { Self::ConstArgument { value: x.into() } } }
 impl From<u64> for FnSpecCallArg { fn from(x: u64) -> Self { Self::ConstArgument { value: x.into() } } }
+impl From<u128> for FnSpecCallArg { fn from(x: u128) -> Self { Self::ConstArgument { value: x.into() } } }
 impl From<usize> for FnSpecCallArg { fn from(x: usize) -> Self { Self::ConstArgument { value: x.into() } } }
 impl From<i8> for FnSpecCallArg { fn from(x: i8) -> Self { Self::ConstArgument { value: x.into() } } }
 impl From<i16> for FnSpecCallArg { fn from(x: i16) -> Self { Self::ConstArgument { value: x.into() } } }
 impl From<i32> for FnSpecCallArg { fn from(x: i32) -> Self { Self::ConstArgument { value: x.into() } } }
 impl From<i64> for FnSpecCallArg { fn from(x: i64) -> Self { Self::ConstArgument { value: x.into() } } }
+impl From<i128> for FnSpecCallArg { fn from(x: i128) -> Self { Self::ConstArgument { value: x.into() } } }
+impl From<isize> for FnSpecCallArg { fn from(x: isize) -> Self { Self::ConstArgument { value: x.into() } } }
 impl From<f32> for FnSpecCallArg { fn from(x: f32) -> Self { Self::ConstArgument { value: x.into() } } }
 impl From<f64> for FnSpecCallArg { fn from(x: f64) -> Self { Self::ConstArgument { value: x.into() } } }
 impl From<bool> for FnSpecCallArg { fn from(x: bool) -> Self { Self::ConstArgument { value: x.into() } } }
 
 pub trait ToBFPValueType {
     fn to_bfp_value_type() -> ValueType;
+
+    /// The pointee type, if this is a pointer or a reference
+    fn to_bfp_pointee_type() -> Option<PointeeType> { None }
 }
 
+/// Pointers are stored with [super::table::Table::add_ptr_hidden_state] instead
+pub trait HiddenStateValue: ToBFPValueType + Copy { }
+
 impl ToBFPValueType for u8 { fn to_bfp_value_type() -> ValueType { ValueType::U8 } }
 impl ToBFPValueType for u16 { fn to_bfp_value_type() -> ValueType { ValueType::U16 } }
 impl ToBFPValueType for u32 { fn to_bfp_value_type() -> ValueType { ValueType::U32 } }
 impl ToBFPValueType for u64 { fn to_bfp_value_type() -> ValueType { ValueType::U64 } }
+impl ToBFPValueType for u128 { fn to_bfp_value_type() -> ValueType { ValueType::U128 } }
 impl ToBFPValueType for usize { fn to_bfp_value_type() -> ValueType { ValueType::USize } }
 impl ToBFPValueType for i8 { fn to_bfp_value_type() -> ValueType { ValueType::I8 } }
 impl ToBFPValueType for i16 { fn to_bfp_value_type() -> ValueType { ValueType::I16 } }
 impl ToBFPValueType for i32 { fn to_bfp_value_type() -> ValueType { ValueType::I32 } }
 impl ToBFPValueType for i64 { fn to_bfp_value_type() -> ValueType { ValueType::I64 } }
+impl ToBFPValueType for i128 { fn to_bfp_value_type() -> ValueType { ValueType::I128 } }
+impl ToBFPValueType for isize { fn to_bfp_value_type() -> ValueType { ValueType::ISize } }
 impl ToBFPValueType for f32 { fn to_bfp_value_type() -> ValueType { ValueType::F32 } }
 impl ToBFPValueType for f64 { fn to_bfp_value_type() -> ValueType { ValueType::F64 } }
 impl ToBFPValueType for bool { fn to_bfp_value_type() -> ValueType { ValueType::Bool } }
-impl<T> ToBFPValueType for *const T { fn to_bfp_value_type() -> ValueType { ValueType::USize } }
-impl<T> ToBFPValueType for *mut T { fn to_bfp_value_type() -> ValueType { ValueType::USize } }
-impl<T> ToBFPValueType for &T { fn to_bfp_value_type() -> ValueType { ValueType::USize } }
-impl<T> ToBFPValueType for &mut T { fn to_bfp_value_type() -> ValueType { ValueType::USize } }
\ No newline at end of file
+impl<T: 'static> ToBFPValueType for *const T { fn to_bfp_value_type() -> ValueType { ValueType::USize } fn to_bfp_pointee_type() -> Option<PointeeType> { Some(PointeeType::of::<T>()) } }
+impl<T: 'static> ToBFPValueType for *mut T { fn to_bfp_value_type() -> ValueType { ValueType::USize } fn to_bfp_pointee_type() -> Option<PointeeType> { Some(PointeeType::of::<T>()) } }
+impl<T: 'static> ToBFPValueType for &T { fn to_bfp_value_type() -> ValueType { ValueType::USize } fn to_bfp_pointee_type() -> Option<PointeeType> { Some(PointeeType::of::<T>()) } }
+impl<T: 'static> ToBFPValueType for &mut T { fn to_bfp_value_type() -> ValueType { ValueType::USize } fn to_bfp_pointee_type() -> Option<PointeeType> { Some(PointeeType::of::<T>()) } }
+
+impl HiddenStateValue for u8 { }
+impl HiddenStateValue for u16 { }
+impl HiddenStateValue for u32 { }
+impl HiddenStateValue for u64 { }
+impl HiddenStateValue for u128 { }
+impl HiddenStateValue for usize { }
+impl HiddenStateValue for i8 { }
+impl HiddenStateValue for i16 { }
+impl HiddenStateValue for i32 { }
+impl HiddenStateValue for i64 { }
+impl HiddenStateValue for i128 { }
+impl HiddenStateValue for isize { }
+impl HiddenStateValue for f32 { }
+impl HiddenStateValue for f64 { }
+impl HiddenStateValue for bool { }
+#[cfg(test)]
+mod tests {
+    use crate::common::{error::CommonError, table::Table};
+
+    use super::*;
+
+    fn read_u32(value: &u32) -> u32 {
+        *value
+    }
+
+    #[test]
+    fn hidden_state_pointee_must_match_parameter() {
+        let mut table = Table::new();
+        let good = table.add_ptr_hidden_state::<u32>();
+        let bad = table.add_ptr_hidden_state::<i32>();
+
+        let read_u32 = read_u32 as fn(&'static u32) -> u32;
+        table.add_function_map("good".into(), read_u32, [FnSpecCallArg::from_hidden_state(good)]).unwrap();
+        let err = table.add_function_map("bad".into(), read_u32, [FnSpecCallArg::from_hidden_state(bad)]).unwrap_err();
+        assert!(matches!(err, CommonError::FuncSpecArgBadPointee { expected: "u32", got: Some("i32") }));
+    }
+
+    #[test]
+    fn pointee_types_are_compared_by_type_id() {
+        assert_eq!(PointeeType::of::<u32>(), PointeeType::of::<u32>());
+        assert_ne!(PointeeType::of::<u32>(), PointeeType::of::<i32>());
+        assert_eq!(<&u32>::to_bfp_pointee_type(), Some(PointeeType::of::<u32>()));
+        assert_eq!(<*mut u8>::to_bfp_pointee_type(), Some(PointeeType::of::<u8>()));
+        assert_eq!(u32::to_bfp_pointee_type(), None);
+    }
+
+    struct Counter(u32);
+
+    fn bump(counter: &'static mut Counter, by: u32) -> u32 {
+        counter.0 += by;
+        counter.0
+    }
+
+    #[test]
+    fn context_type_must_match_parameter() {
+        let bump = bump as fn(&'static mut Counter, u32) -> u32;
+        let mut table = Table::new();
+        table.add_function_map("bump".into(), bump, [FnSpecCallArg::from_context::<Counter>(), FnSpecCallArg::MappedArgument { param_idx: 0 }]).unwrap();
+        assert_eq!(table.get_context_type(), Some(TypeId::of::<Counter>()));
+
+        let mut table = Table::new();
+        let err = table.add_function_map("bump".into(), bump, [FnSpecCallArg::from_context::<u32>(), FnSpecCallArg::MappedArgument { param_idx: 0 }]).unwrap_err();
+        assert!(matches!(err, CommonError::FuncSpecArgBadPointee { got: Some(got), .. } if got.ends_with("Counter")));
+        assert_eq!(table.get_context_type(), None);
+
+        let mut table = Table::new();
+        table.set_context_type::<u32>().unwrap();
+        let err = table.add_function_map("bump".into(), bump, [FnSpecCallArg::from_context::<Counter>(), FnSpecCallArg::MappedArgument { param_idx: 0 }]).unwrap_err();
+        assert!(matches!(err, CommonError::ContextTypeConflict { existing: "u32", .. }));
+    }
+}
@@ -8,10 +8,25 @@ pub enum CommonError {
     CannotResolve { from: UntypedValue, to: ValueType },
     CannotMakeSigned { from: ValueType },
     BindingAlreadyExists { name: String },
+    OverloadAlreadyExists { name: String, params: Box<[ValueType]> },
+    ParamDefsNotFunction { name: String },
+    ParamDefsBadCount { name: String, expected: usize, got: usize },
+    ParamDefsDuplicateName { name: String, param: String },
+    ParamDefaultBadType { name: String, param: String, expected: ValueType, got: ValueType },
     FuncSpecArgBadType { expected: ValueType, got: ValueType },
     FuncSpecArgBadParamIndex { idx: usize, count: usize },
+    FuncSpecArgBadVariadic,
     FuncSpecArgParamIndexConflict { idx: usize, new_type: ValueType, existing_type: ValueType },
     FuncSpecArgDiscontinuousParamMap { max_idx: usize, missing_idx: usize },
+    FuncSpecArgBadPointee { expected: &'static str, got: Option<&'static str> },
+    UnknownVariable { name: String },
+    VariableBadType { name: String, expected: ValueType, got: ValueType },
+    InputsAlreadyAdded { existing: &'static str, new: &'static str },
+    ContextTypeConflict { existing: &'static str, new: &'static str },
+    SetBadType { name: String, value_type: ValueType },
+    SetValueBadType { name: String, expected: ValueType, got: ValueType },
+    SetHandleMismatch,
+    UnsupportedFunction { name: String },
 }
 
 impl fmt::Display for CommonError {
@@ -21,10 +36,28 @@ impl fmt::Display for CommonError {
             Self::CannotResolve { from, to } => write!(f, "Cannot resolve untyped value ({from:?}) to {to:?}"),
             Self::CannotMakeSigned { from } => write!(f, "Cannot convert {from:?} to another signed type"),
             Self::BindingAlreadyExists { name } => write!(f, "Binding \"{name}\" already exists"),
+            Self::OverloadAlreadyExists { name, params } => write!(f, "Function \"{name}\" already has an overload with parameters {params:?}"),
+            Self::ParamDefsNotFunction { name } => write!(f, "Can't set parameter names of \"{name}\"; only non-generic functions have named parameters"),
+            Self::ParamDefsBadCount { name, expected, got } => write!(f, "Function \"{name}\" has {expected} parameters, but {got} parameter names were given"),
+            Self::ParamDefsDuplicateName { name, param } => write!(f, "Function \"{name}\" has more than one parameter named \"{param}\""),
+            Self::ParamDefaultBadType { name, param, expected, got } => write!(f, "Default value of parameter \"{param}\" of function \"{name}\" has type {got:?}, expected {expected:?}"),
             Self::FuncSpecArgBadType { expected, got } => write!(f, "Expected function specialisation argument with type {expected:?}, got {got:?}"),
             Self::FuncSpecArgBadParamIndex { idx, count } => write!(f, "Function specialisation argument is mapped to parameter index {idx}, but there are only {count} parameters"),
+            Self::FuncSpecArgBadVariadic => write!(f, "Function specialisation arguments must have at most one variadic pointer, followed by a variadic length"),
             Self::FuncSpecArgParamIndexConflict { idx, new_type, existing_type } => write!(f, "Function specialisation argument is mapped to parameter index {idx} with type {new_type:?}, which is already mapped to a different type {existing_type:?}"),
             Self::FuncSpecArgDiscontinuousParamMap { max_idx, missing_idx } => write!(f, "Function specialisation arguments are mapped to a discontinuous parameter index range; expected range 0..={max_idx}, but missing index {missing_idx}"),
+            Self::FuncSpecArgBadPointee { expected, got } => match got {
+                Some(got) => write!(f, "Expected function specialisation argument pointing to {expected}, got pointer to {got}"),
+                None => write!(f, "Expected function specialisation argument pointing to {expected}, got non-pointer"),
+            },
+            Self::UnknownVariable { name } => write!(f, "Unknown variable \"{name}\""),
+            Self::VariableBadType { name, expected, got } => write!(f, "Variable \"{name}\" has type {got:?}, but {expected:?} was expected"),
+            Self::InputsAlreadyAdded { existing, new } => write!(f, "Cannot add inputs {new}; the table already has inputs {existing}"),
+            Self::ContextTypeConflict { existing, new } => write!(f, "Cannot use context type {new}; the table already uses context type {existing}"),
+            Self::SetBadType { name, value_type } => write!(f, "Set \"{name}\" can't have type {value_type:?}; only integers up to 64 bits are supported"),
+            Self::SetValueBadType { name, expected, got } => write!(f, "Set \"{name}\" has type {expected:?}, but a value with type {got:?} was given"),
+            Self::SetHandleMismatch => write!(f, "Set handle doesn't belong to the Table that this Slab was created from"),
+            Self::UnsupportedFunction { name } => write!(f, "Function \"{name}\" must be a fn item, a fn pointer without higher-ranked lifetimes, or a closure without captures; bind other closures with add_closure"),
         }
     }
 }
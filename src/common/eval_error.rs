@@ -0,0 +1,102 @@
+use std::{any::Any, error::Error, fmt};
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+#[repr(usize)]
+pub enum EvalErrorKind {
+    Overflow = 1,
+    DivisionByZero = 2,
+    InvalidFloatCast = 3,
+    HostPanic = 4,
+    /// A host function returned `None` or `Err`
+    HostError = 5,
+}
+
+impl EvalErrorKind {
+    pub const fn from_raw(raw: usize) -> Option<Self> {
+        match raw {
+            1 => Some(Self::Overflow),
+            2 => Some(Self::DivisionByZero),
+            3 => Some(Self::InvalidFloatCast),
+            4 => Some(Self::HostPanic),
+            5 => Some(Self::HostError),
+            _ => None,
+        }
+    }
+}
+
+/// A fault that aborted the evaluation of an expression. Either an arithmetic
+/// fault in an expression compiled with
+/// [crate::codegen::arithmetic_mode::ArithmeticMode::Checked], or a host
+/// function that panicked or failed
+#[derive(Debug)]
+pub struct EvalError {
+    pub kind: EvalErrorKind,
+    /// Index of the failing node in [crate::analysis::packed_analysis_tree::PackedAnalysisTree::nodes]
+    pub node: usize,
+    /// The error returned by the host function, if any. For panics, this is
+    /// the panic message
+    pub host_error: Option<Box<dyn Error + Send + Sync>>,
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            EvalErrorKind::Overflow => write!(f, "Arithmetic overflow in node {}", self.node),
+            EvalErrorKind::DivisionByZero => write!(f, "Division by zero in node {}", self.node),
+            EvalErrorKind::InvalidFloatCast => write!(f, "Float is NaN or out of range for integer cast in node {}", self.node),
+            EvalErrorKind::HostPanic => match &self.host_error {
+                Some(err) => write!(f, "Host function panicked in node {}: {err}", self.node),
+                None => write!(f, "Host function panicked in node {}", self.node),
+            },
+            EvalErrorKind::HostError => match &self.host_error {
+                Some(err) => write!(f, "Host function failed in node {}: {err}", self.node),
+                None => write!(f, "Host function returned no value in node {}", self.node),
+            },
+        }
+    }
+}
+
+impl Error for EvalError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.host_error.as_deref().map(|err| err as &(dyn Error + 'static))
+    }
+}
+
+/// Written by compiled code when an evaluation fails. The layout must not be
+/// changed without also changing the codegen that writes to it
+#[repr(C)]
+#[derive(Default)]
+pub struct EvalStatus {
+    /// 0 if there is no error, otherwise an [EvalErrorKind]
+    pub kind: usize,
+    pub node: usize,
+    /// Set by host functions along with kind. The node is set by the compiled
+    /// code after the host function returns
+    pub host_error: Option<Box<dyn Error + Send + Sync>>,
+}
+
+impl EvalStatus {
+    pub fn take_error(&mut self) -> Option<EvalError> {
+        let kind = EvalErrorKind::from_raw(self.kind)?;
+        self.kind = 0;
+        Some(EvalError { kind, node: self.node, host_error: self.host_error.take() })
+    }
+
+    pub fn set_host_panic(&mut self, payload: Box<dyn Any + Send>) {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => *message,
+            Err(payload) => match payload.downcast::<&'static str>() {
+                Ok(message) => (*message).into(),
+                Err(_) => "Box<dyn Any>".into(),
+            },
+        };
+
+        self.kind = EvalErrorKind::HostPanic as usize;
+        self.host_error = Some(message.into());
+    }
+
+    pub fn set_host_error(&mut self, err: Option<Box<dyn Error + Send + Sync>>) {
+        self.kind = EvalErrorKind::HostError as usize;
+        self.host_error = err;
+    }
+}
\ No newline at end of file
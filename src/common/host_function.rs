@@ -0,0 +1,210 @@
+use std::{any::Any, error::Error, ffi::c_void, mem::{MaybeUninit, transmute}, panic::{AssertUnwindSafe, catch_unwind}, ptr::NonNull};
+
+use super::{binding::{BindingFuncParams, BindingFuncRets, FnPointer, FnSpecCallArg, ToBFPValueType}, error::CommonError, eval_error::EvalStatus};
+
+/// A function without captures, with up to 12 parameters
+pub trait HostFunction<Args>: Copy + 'static {
+    /// One specialisation argument per parameter
+    type SpecArgs: AsRef<[FnSpecCallArg]> + Into<Box<[FnSpecCallArg]>>;
+
+    fn get_ret_types() -> BindingFuncRets;
+    fn get_param_types() -> BindingFuncParams;
+    fn guard_spec_args(args: &Self::SpecArgs, params: &BindingFuncParams) -> Result<(), CommonError>;
+    /// The shim and its target address, or None if the function has captures
+    fn get_shim(self) -> Option<(FnPointer, usize)>;
+}
+
+/// A closure with up to 12 parameters
+pub trait HostClosure<Args>: Send + Sync + 'static {
+    fn get_ret_types() -> BindingFuncRets;
+    fn get_param_types() -> BindingFuncParams;
+    /// Calls the closure at the address passed as the first argument
+    fn get_trampoline() -> FnPointer;
+}
+
+/// A single value, or a tuple of 2 to 4 values
+pub trait HostValues {
+    /// The value returned directly; the others are returned via out pointers
+    type First: ToBFPValueType;
+
+    fn get_value_types() -> BindingFuncRets;
+    /// Writes the values after the first to `outs`, and returns the first
+    ///
+    /// # Safety
+    ///
+    /// `outs` must point to one valid pointer per value after the first, each
+    /// one pointing to storage for that value
+    unsafe fn write_rest(self, outs: *const *mut c_void) -> Self::First;
+}
+
+impl<T: ToBFPValueType> HostValues for T {
+    type First = T;
+
+    fn get_value_types() -> BindingFuncRets {
+        [T::to_bfp_value_type()].into()
+    }
+
+    unsafe fn write_rest(self, _outs: *const *mut c_void) -> T {
+        self
+    }
+}
+
+macro_rules! impl_host_values {
+    ($first:ident; $($value:ident $idx:tt),*) => {
+        impl<$first: ToBFPValueType, $($value: ToBFPValueType),*> HostValues for ($first, $($value),*) {
+            type First = $first;
+
+            fn get_value_types() -> BindingFuncRets {
+                [$first::to_bfp_value_type(), $($value::to_bfp_value_type()),*].into()
+            }
+
+            unsafe fn write_rest(self, outs: *const *mut c_void) -> $first {
+                $(unsafe { (*outs.add($idx - 1)).cast::<$value>().write(self.$idx) };)*
+                self.0
+            }
+        }
+    };
+}
+
+impl_host_values!(V0; V1 1);
+impl_host_values!(V0; V1 1, V2 2);
+impl_host_values!(V0; V1 1, V2 2, V3 3);
+
+/// Plain values, or an `Option` or `Result` that aborts the evaluation
+pub trait HostReturn {
+    type Values: HostValues;
+
+    fn into_host_result(self) -> Result<Self::Values, Option<Box<dyn Error + Send + Sync>>>;
+}
+
+impl<V: HostValues> HostReturn for V {
+    type Values = V;
+
+    fn into_host_result(self) -> Result<V, Option<Box<dyn Error + Send + Sync>>> {
+        Ok(self)
+    }
+}
+
+impl<V: HostValues> HostReturn for Option<V> {
+    type Values = V;
+
+    fn into_host_result(self) -> Result<V, Option<Box<dyn Error + Send + Sync>>> {
+        self.ok_or(None)
+    }
+}
+
+impl<V: HostValues, E: Into<Box<dyn Error + Send + Sync>>> HostReturn for Result<V, E> {
+    type Values = V;
+
+    fn into_host_result(self) -> Result<V, Option<Box<dyn Error + Send + Sync>>> {
+        self.map_err(|err| Some(err.into()))
+    }
+}
+
+/// Stores panics and failures in the EvalStatus instead of unwinding
+fn call_host<R: HostReturn>(status: *mut EvalStatus, outs: *const *mut c_void, function: impl FnOnce() -> R) -> MaybeUninit<<R::Values as HostValues>::First> {
+    // SAFETY: status is the address of the EvalStatus of the Slab that is
+    //         being evaluated, which isn't borrowed during the call, and outs
+    //         is created by the compiled code from the function's return types
+    match catch_unwind(AssertUnwindSafe(function)) {
+        Ok(value) => match value.into_host_result() {
+            Ok(values) => MaybeUninit::new(unsafe { values.write_rest(outs) }),
+            Err(err) => {
+                unsafe { (*status).set_host_error(err) };
+                MaybeUninit::uninit()
+            },
+        },
+        Err(payload) => {
+            unsafe { (*status).set_host_panic(payload) };
+            MaybeUninit::uninit()
+        },
+    }
+}
+
+macro_rules! impl_host_function {
+    ($argc:literal; $($param:ident $arg:ident $idx:tt),*) => {
+        impl<F, R: HostReturn + 'static, $($param: ToBFPValueType + 'static),*> HostFunction<($($param,)*)> for F
+        where
+            F: Fn($($param),*) -> R + Copy + 'static,
+        {
+            type SpecArgs = [FnSpecCallArg; $argc];
+
+            fn get_ret_types() -> BindingFuncRets {
+                R::Values::get_value_types()
+            }
+
+            fn get_param_types() -> BindingFuncParams {
+                [$($param::to_bfp_value_type()),*].into()
+            }
+
+            #[allow(unused_variables)]
+            fn guard_spec_args(args: &Self::SpecArgs, params: &BindingFuncParams) -> Result<(), CommonError> {
+                $(args[$idx].guard::<$param>(params)?;)*
+                Ok(())
+            }
+
+            fn get_shim(self) -> Option<(FnPointer, usize)> {
+                extern "C" fn shim<R: HostReturn, $($param),*>(function: FnPointer, status: *mut EvalStatus, outs: *const *mut c_void, $($arg: $param),*) -> MaybeUninit<<R::Values as HostValues>::First> {
+                    // SAFETY: the pointer was created from a fn pointer with
+                    //         the same signature
+                    let function = unsafe { transmute::<FnPointer, fn($($param),*) -> R>(function) };
+                    call_host(status, outs, || function($($arg),*))
+                }
+
+                extern "C" fn item_shim<F: Fn($($param),*) -> R, R: HostReturn, $($param),*>(_: usize, status: *mut EvalStatus, outs: *const *mut c_void, $($arg: $param),*) -> MaybeUninit<<R::Values as HostValues>::First> {
+                    // SAFETY: F is a zero sized Copy type, so it has no data
+                    //         to read, and copying it is always allowed
+                    let function = unsafe { NonNull::<F>::dangling().read() };
+                    call_host(status, outs, || function($($arg),*))
+                }
+
+                // fn items and closures without captures are zero sized, so
+                // their shim doesn't need a target
+                if size_of::<F>() == 0 {
+                    return Some((item_shim::<F, R, $($param),*> as FnPointer, 0));
+                }
+
+                let function = *(&self as &dyn Any).downcast_ref::<fn($($param),*) -> R>()?;
+                Some((shim::<R, $($param),*> as FnPointer, (function as FnPointer).addr()))
+            }
+        }
+
+        impl<F, R: HostReturn, $($param: ToBFPValueType),*> HostClosure<($($param,)*)> for F
+        where
+            F: Fn($($param),*) -> R + Send + Sync + 'static,
+        {
+            fn get_ret_types() -> BindingFuncRets {
+                R::Values::get_value_types()
+            }
+
+            fn get_param_types() -> BindingFuncParams {
+                [$($param::to_bfp_value_type()),*].into()
+            }
+
+            fn get_trampoline() -> FnPointer {
+                extern "C" fn trampoline<F: Fn($($param),*) -> R, R: HostReturn, $($param),*>(closure: *const F, status: *mut EvalStatus, outs: *const *mut c_void, $($arg: $param),*) -> MaybeUninit<<R::Values as HostValues>::First> {
+                    // SAFETY: the closure is kept alive by the Slabs of the
+                    //         Table it was added to
+                    let closure = unsafe { &*closure };
+                    call_host(status, outs, || closure($($arg),*))
+                }
+
+                trampoline::<F, R, $($param),*> as FnPointer
+            }
+        }
+    };
+}
+
+impl_host_function!(0;);
+impl_host_function!(1; P1 p1 0);
+impl_host_function!(2; P1 p1 0, P2 p2 1);
+impl_host_function!(3; P1 p1 0, P2 p2 1, P3 p3 2);
+impl_host_function!(4; P1 p1 0, P2 p2 1, P3 p3 2, P4 p4 3);
+impl_host_function!(5; P1 p1 0, P2 p2 1, P3 p3 2, P4 p4 3, P5 p5 4);
+impl_host_function!(6; P1 p1 0, P2 p2 1, P3 p3 2, P4 p4 3, P5 p5 4, P6 p6 5);
+impl_host_function!(7; P1 p1 0, P2 p2 1, P3 p3 2, P4 p4 3, P5 p5 4, P6 p6 5, P7 p7 6);
+impl_host_function!(8; P1 p1 0, P2 p2 1, P3 p3 2, P4 p4 3, P5 p5 4, P6 p6 5, P7 p7 6, P8 p8 7);
+impl_host_function!(9; P1 p1 0, P2 p2 1, P3 p3 2, P4 p4 3, P5 p5 4, P6 p6 5, P7 p7 6, P8 p8 7, P9 p9 8);
+impl_host_function!(10; P1 p1 0, P2 p2 1, P3 p3 2, P4 p4 3, P5 p5 4, P6 p6 5, P7 p7 6, P8 p8 7, P9 p9 8, P10 p10 9);
+impl_host_function!(11; P1 p1 0, P2 p2 1, P3 p3 2, P4 p4 3, P5 p5 4, P6 p6 5, P7 p7 6, P8 p8 7, P9 p9 8, P10 p10 9, P11 p11 10);
+impl_host_function!(12; P1 p1 0, P2 p2 1, P3 p3 2, P4 p4 3, P5 p5 4, P6 p6 5, P7 p7 6, P8 p8 7, P9 p9 8, P10 p10 9, P11 p11 10, P12 p12 11);
\ No newline at end of file
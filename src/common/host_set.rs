@@ -0,0 +1,102 @@
+use super::{error::CommonError, value::Value, value_type::ValueType};
+
+/// The set's data is allocated in chunks so that it's aligned enough for any
+/// set value type
+#[derive(Clone, Copy)]
+#[repr(C, align(16))]
+struct HostSetChunk([u8; 16]);
+
+/// The contents of a host set, used by `x in name` expressions. Keys are
+/// sorted and deduplicated, and stored with the same layout as an array of the
+/// set's value type, so that compiled expressions can binary search it
+/// directly
+pub struct HostSetData {
+    value_type: ValueType,
+    len: usize,
+    data: Box<[HostSetChunk]>,
+}
+
+impl HostSetData {
+    /// Only integers up to 64 bits are supported, so that every key fits in an
+    /// i128 regardless of signedness
+    pub const fn is_valid_type(value_type: ValueType) -> bool {
+        value_type.is_integer() && value_type.get_size() <= 8
+    }
+
+    pub fn new<T: Into<Value>>(name: &str, value_type: ValueType, values: impl IntoIterator<Item = T>) -> Result<Self, CommonError> {
+        if !Self::is_valid_type(value_type) {
+            return Err(CommonError::SetBadType { name: name.into(), value_type });
+        }
+
+        let mut keys = Vec::new();
+        for value in values {
+            let value = value.into();
+            match value.get_int_key() {
+                Some(key) if value.get_value_type() == value_type => keys.push(key),
+                _ => return Err(CommonError::SetValueBadType { name: name.into(), expected: value_type, got: value.get_value_type() }),
+            }
+        }
+
+        keys.sort_unstable();
+        keys.dedup();
+
+        let size = value_type.get_size();
+        let mut data = vec![HostSetChunk([0; 16]); (keys.len() * size).div_ceil(size_of::<HostSetChunk>())].into_boxed_slice();
+        // SAFETY: the chunks are plain bytes, and there are at least
+        //         keys.len() * size of them
+        let bytes = unsafe { std::slice::from_raw_parts_mut(data.as_mut_ptr().cast::<u8>(), data.len() * size_of::<HostSetChunk>()) };
+        for (key, dst) in keys.iter().zip(bytes.chunks_exact_mut(size)) {
+            // truncating keeps the low bytes, which are the key's bytes in the
+            // narrower type for both signed and unsigned types
+            let key_bytes = (*key as i64).to_ne_bytes();
+            if cfg!(target_endian = "little") {
+                dst.copy_from_slice(&key_bytes[..size]);
+            } else {
+                dst.copy_from_slice(&key_bytes[8 - size..]);
+            }
+        }
+
+        Ok(Self { value_type, len: keys.len(), data })
+    }
+
+    pub const fn get_value_type(&self) -> ValueType {
+        self.value_type
+    }
+
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get_address(&self) -> usize {
+        self.data.as_ptr().addr()
+    }
+
+    /// Gets the sorted keys, extended to an i128
+    pub fn iter_keys(&self) -> impl Iterator<Item = i128> + '_ {
+        let size = self.value_type.get_size();
+        let bits = (size * 8) as u32;
+        let is_signed = self.value_type.is_signed();
+        // SAFETY: the chunks are plain bytes, and there are at least len * size
+        //         of them
+        let bytes = unsafe { std::slice::from_raw_parts(self.data.as_ptr().cast::<u8>(), self.data.len() * size_of::<HostSetChunk>()) };
+        bytes.chunks_exact(size).take(self.len).map(move |src| {
+            let mut key_bytes = [0u8; 8];
+            if cfg!(target_endian = "little") {
+                key_bytes[..size].copy_from_slice(src);
+            } else {
+                key_bytes[8 - size..].copy_from_slice(src);
+            }
+
+            let raw = u64::from_ne_bytes(key_bytes);
+            if is_signed {
+                ((raw << (64 - bits)) as i64 >> (64 - bits)).into()
+            } else {
+                raw.into()
+            }
+        })
+    }
+}
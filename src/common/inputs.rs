@@ -0,0 +1,21 @@
+use super::{error::CommonError, table::Table};
+
+/// A struct whose fields are read directly by compiled expressions. Use
+/// `#[derive(HotEvalInputs)]` instead of implementing this manually, add it to
+/// a Table with [Table::add_inputs], and pass a reference to it when calling
+/// the compiled expression, via [crate::codegen::compiled_expression::eval]
+///
+/// # Safety
+///
+/// add_input_fields must only add fields of Self, with their real offsets and
+/// types
+pub unsafe trait HotEvalInputs: 'static {
+    fn add_input_fields(table: &mut Table) -> Result<(), CommonError>;
+}
+
+/// The inputs of Tables without inputs
+unsafe impl HotEvalInputs for () {
+    fn add_input_fields(_table: &mut Table) -> Result<(), CommonError> {
+        Ok(())
+    }
+}
\ No newline at end of file
@@ -0,0 +1,93 @@
+use super::binding::TypeConstraint;
+
+/// Built-in math functions, which are lowered to LLVM intrinsics instead of
+/// host function calls. All arguments and the result have the same type.
+/// Bindings in the Table take precedence over these, and they can be disabled
+/// with [super::table::Table::set_math_intrinsics_enabled]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MathIntrinsic {
+    Sqrt,
+    Abs,
+    Min,
+    Max,
+    /// `clamp(x, min, max)`
+    Clamp,
+    Floor,
+    Ceil,
+    /// Rounds half-way cases away from zero
+    Round,
+    Trunc,
+    /// `fma(a, b, c)` is `a * b + c`, with a single rounding
+    Fma,
+    Pow,
+    Exp,
+    /// Natural logarithm
+    Log,
+    Sin,
+    Cos,
+    CopySign,
+    /// Euclidean remainder, which is never negative
+    RemEuclid,
+}
+
+impl MathIntrinsic {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "sqrt" => Self::Sqrt,
+            "abs" => Self::Abs,
+            "min" => Self::Min,
+            "max" => Self::Max,
+            "clamp" => Self::Clamp,
+            "floor" => Self::Floor,
+            "ceil" => Self::Ceil,
+            "round" => Self::Round,
+            "trunc" => Self::Trunc,
+            "fma" => Self::Fma,
+            "pow" => Self::Pow,
+            "exp" => Self::Exp,
+            "log" => Self::Log,
+            "sin" => Self::Sin,
+            "cos" => Self::Cos,
+            "copysign" => Self::CopySign,
+            "rem_euclid" => Self::RemEuclid,
+            _ => return None,
+        })
+    }
+
+    pub const fn get_name(&self) -> &'static str {
+        match self {
+            Self::Sqrt => "sqrt",
+            Self::Abs => "abs",
+            Self::Min => "min",
+            Self::Max => "max",
+            Self::Clamp => "clamp",
+            Self::Floor => "floor",
+            Self::Ceil => "ceil",
+            Self::Round => "round",
+            Self::Trunc => "trunc",
+            Self::Fma => "fma",
+            Self::Pow => "pow",
+            Self::Exp => "exp",
+            Self::Log => "log",
+            Self::Sin => "sin",
+            Self::Cos => "cos",
+            Self::CopySign => "copysign",
+            Self::RemEuclid => "rem_euclid",
+        }
+    }
+
+    pub const fn get_argc(&self) -> usize {
+        match self {
+            Self::Sqrt | Self::Abs | Self::Floor | Self::Ceil | Self::Round | Self::Trunc | Self::Exp | Self::Log | Self::Sin | Self::Cos => 1,
+            Self::Min | Self::Max | Self::Pow | Self::CopySign | Self::RemEuclid => 2,
+            Self::Clamp | Self::Fma => 3,
+        }
+    }
+
+    pub const fn get_constraint(&self) -> TypeConstraint {
+        match self {
+            Self::Abs | Self::Min | Self::Max | Self::Clamp | Self::RemEuclid => TypeConstraint::Numeric,
+            Self::Sqrt | Self::Floor | Self::Ceil | Self::Round | Self::Trunc | Self::Fma | Self::Pow | Self::Exp | Self::Log | Self::Sin | Self::Cos | Self::CopySign => TypeConstraint::Float,
+        }
+    }
+}
@@ -5,4 +5,9 @@ pub mod untyped_value;
 pub mod table;
 pub mod binding;
 pub mod slab;
-pub mod ir_const;
\ No newline at end of file
+pub mod ir_const;
+pub mod eval_error;
+pub mod inputs;
+pub mod host_function;
+pub mod math_intrinsic;
+pub mod host_set;
@@ -1,133 +1,417 @@
-use std::{collections::HashMap, mem::MaybeUninit};
+use std::{any::{Any, TypeId}, cell::UnsafeCell, collections::HashMap, ffi::c_void, marker::PhantomData, mem::MaybeUninit, sync::{Arc, atomic::{AtomicUsize, Ordering}}};
 
-use crate::common::binding::Binding;
+use crate::common::{binding::{Binding, HiddenStateValue, ToBFPValueType}, host_set::HostSetData, inputs::HotEvalInputs, table::{HiddenState, SetHandle}};
 
-use super::{error::CommonError, table::Table, value_type::ValueType};
+use super::{error::CommonError, eval_error::{EvalError, EvalStatus}, table::Table, value::Value, value_type::ValueType};
 
 pub enum SlabBindingInfo {
     Variable { idx: usize, value_type: ValueType },
-    Function { idx: usize, ret_type: ValueType, arg_types: Vec<ValueType> },
+    Input { offset: usize, value_type: ValueType },
+    Function { idx: usize, ret_types: Vec<ValueType>, arg_types: Vec<ValueType> },
+    /// Data address and length of an updatable set
+    Set { ptr_idx: usize, len_idx: usize, value_type: ValueType },
 }
 
+/// Keeps an updatable set's data alive while the Slab points to it
+struct SlabSet {
+    name: String,
+    ptr_idx: usize,
+    len_idx: usize,
+    data: Arc<HostSetData>,
+}
+
+/// Location of a single binding or hidden state in the Slab's data
+#[derive(Clone, Copy, Debug)]
+pub struct SlabSlot {
+    /// Offset in bytes, aligned for the value type
+    pub offset: usize,
+    pub value_type: ValueType,
+}
+
+/// Aligned enough for any ValueType
+#[derive(Clone, Copy)]
+#[repr(C, align(16))]
+struct SlabChunk([MaybeUninit<u8>; 16]);
+
+/// A type-checked reference to a variable of a specific Slab
+pub struct VarHandle<T> {
+    slab_id: usize,
+    offset: usize,
+    _phantom: PhantomData<T>,
+}
+
+impl<T> Clone for VarHandle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for VarHandle<T> { }
+
+static NEXT_SLAB_ID: AtomicUsize = AtomicUsize::new(0);
+
 pub struct Slab {
-    data: Box<[MaybeUninit<usize>]>,
+    id: usize,
+    data: Box<[SlabChunk]>,
+    /// Hidden states first, then variables
+    slots: Box<[SlabSlot]>,
     hidden_state_count: usize,
     binding_map: HashMap<String, SlabBindingInfo>,
-    hidden_state_types: Box<[ValueType]>,
+    eval_status: Box<UnsafeCell<EvalStatus>>,
+    inputs_type: Option<TypeId>,
+    context_type: Option<TypeId>,
+    /// Keeps the Table's closures alive
+    _closures: Box<[Arc<dyn Any + Send + Sync>]>,
+    /// Indexed by [SetHandle::get_index]
+    sets: Box<[SlabSet]>,
+    table_id: usize,
 }
 
 impl Slab {
     pub fn from_table(table: &Table) -> Result<Self, CommonError> {
         let mut binding_map = HashMap::<String, SlabBindingInfo>::new();
         let hidden_state_count = table.get_hidden_state_count();
-        let mut idx = hidden_state_count;
+        let mut value_types = Vec::new();
+
+        for i in 0..hidden_state_count {
+            value_types.push(*table.get_hidden_state(i).unwrap());
+        }
+
+        let mut sets = Vec::new();
+        sets.resize_with(table.get_updatable_set_count(), || None);
 
         for (name, binding) in table.iter_bindings() {
            let info = match binding {
                 Binding::Const { .. } |
-                Binding::Function { .. } => None,
-                Binding::Variable { value_type } => Some(SlabBindingInfo::Variable { idx, value_type: *value_type }),
+                Binding::Function { .. } |
+                Binding::GenericFunction { .. } |
+                Binding::Set { handle_idx: None, .. } => None,
+                Binding::Variable { value_type } => Some(SlabBindingInfo::Variable { idx: value_types.len(), value_type: *value_type }),
+                Binding::Input { offset, value_type } => Some(SlabBindingInfo::Input { offset: *offset, value_type: *value_type }),
+                Binding::Set { data, handle_idx: Some(handle_idx) } => {
+                    let (ptr_idx, len_idx) = (value_types.len(), value_types.len() + 1);
+                    value_types.extend([ValueType::USize, ValueType::USize]);
+                    sets[*handle_idx] = Some(SlabSet { name: name.clone(), ptr_idx, len_idx, data: data.clone() });
+                    Some(SlabBindingInfo::Set { ptr_idx, len_idx, value_type: data.get_value_type() })
+                },
             };
 
             if let Some(info) = info {
+                if let SlabBindingInfo::Variable { value_type, .. } = &info {
+                    value_types.push(*value_type);
+                }
+
                 binding_map.insert(name.clone(), info);
-                idx += 1;
             }
         }
 
-        let mut hst = Vec::new();
-        for i in 0..hidden_state_count {
-            hst.push(*table.get_hidden_state(i).unwrap());
+        // place the most aligned values first. all sizes are multiples of
+        // their alignment, and all alignments are powers of 2, so this packs
+        // the values without any padding between them
+        let mut layout_order: Vec<usize> = (0..value_types.len()).collect();
+        layout_order.sort_by_key(|&idx| std::cmp::Reverse(value_types[idx].get_alignment()));
+
+        let mut slots = Vec::with_capacity(value_types.len());
+        slots.resize(value_types.len(), SlabSlot { offset: 0, value_type: ValueType::Bool });
+        let mut size = 0usize;
+
+        for idx in layout_order {
+            let value_type = value_types[idx];
+            let alignment = value_type.get_alignment();
+            debug_assert!(alignment <= align_of::<SlabChunk>());
+            let offset = size.next_multiple_of(alignment);
+            slots[idx] = SlabSlot { offset, value_type };
+            size = offset + value_type.get_size();
         }
 
-        let mut data = Vec::with_capacity(idx);
-        data.resize(idx, MaybeUninit::new(0));
+        let mut data = Vec::with_capacity(size.div_ceil(size_of::<SlabChunk>()));
+        data.resize(data.capacity(), SlabChunk([MaybeUninit::new(0); 16]));
 
-        Ok(Slab { data: data.into(), hidden_state_count, binding_map, hidden_state_types: hst.into() })
+        let sets = sets.into_iter().collect::<Option<Box<[SlabSet]>>>().ok_or(CommonError::SetHandleMismatch)?;
+        let mut slab = Slab { id: NEXT_SLAB_ID.fetch_add(1, Ordering::Relaxed), data: data.into(), slots: slots.into(), hidden_state_count, binding_map, eval_status: Default::default(), inputs_type: table.get_inputs_type(), context_type: table.get_context_type(), _closures: table.get_closures().into(), sets, table_id: table.get_id() };
+        for idx in 0..slab.sets.len() {
+            slab.write_set_slots(idx);
+        }
+
+        Ok(slab)
     }
 
-    pub fn get_binding_info(&self, name: &String) -> Option<&SlabBindingInfo> {
+    /// Replaces the contents of an updatable set
+    pub fn update_set<T: Into<Value>>(&mut self, handle: SetHandle, values: impl IntoIterator<Item = T>) -> Result<(), CommonError> {
+        let set = self.get_set(handle)?;
+        let data = HostSetData::new(&set.name, handle.get_value_type(), values)?;
+        self.set_set_data(handle, Arc::new(data))
+    }
+
+    /// Same as [Slab::update_set], with data that can be shared between Slabs
+    pub fn set_set_data(&mut self, handle: SetHandle, data: Arc<HostSetData>) -> Result<(), CommonError> {
+        let set = self.get_set(handle)?;
+        if data.get_value_type() != set.data.get_value_type() {
+            return Err(CommonError::SetValueBadType { name: set.name.clone(), expected: set.data.get_value_type(), got: data.get_value_type() });
+        }
+
+        self.sets[handle.get_index()].data = data;
+        self.write_set_slots(handle.get_index());
+        Ok(())
+    }
+
+    fn get_set(&self, handle: SetHandle) -> Result<&SlabSet, CommonError> {
+        if handle.get_table_id() != self.table_id {
+            return Err(CommonError::SetHandleMismatch);
+        }
+
+        self.sets.get(handle.get_index()).ok_or(CommonError::SetHandleMismatch)
+    }
+
+    fn write_set_slots(&mut self, idx: usize) {
+        let set = &self.sets[idx];
+        let (ptr_idx, len_idx, address, len) = (set.ptr_idx, set.len_idx, set.data.get_address(), set.data.len());
+        self.set_value(ptr_idx, address);
+        self.set_value(len_idx, len);
+    }
+
+    pub fn get_binding_info(&self, name: &str) -> Option<&SlabBindingInfo> {
         self.binding_map.get(name)
     }
 
-    pub fn get_binding_index(&self, name: &String) -> Option<usize> {
+    pub fn get_binding_index(&self, name: &str) -> Option<usize> {
         match self.binding_map.get(name) {
             Some(info) => Some(match info {
                 SlabBindingInfo::Variable { idx, .. } |
                 SlabBindingInfo::Function { idx, .. } => *idx,
+                SlabBindingInfo::Input { .. } |
+                SlabBindingInfo::Set { .. } => return None,
             }),
             None => None,
         }
     }
 
+    pub fn handle<T: ToBFPValueType + Copy>(&self, name: &str) -> Result<VarHandle<T>, CommonError> {
+        let idx = match self.binding_map.get(name) {
+            Some(SlabBindingInfo::Variable { idx, .. }) => *idx,
+            _ => return Err(CommonError::UnknownVariable { name: name.into() }),
+        };
+
+        let slot = &self.slots[idx];
+        let expected = T::to_bfp_value_type();
+        if slot.value_type != expected || size_of::<T>() != slot.value_type.get_size() {
+            return Err(CommonError::VariableBadType { name: name.into(), expected, got: slot.value_type });
+        }
+
+        Ok(VarHandle { slab_id: self.id, offset: slot.offset, _phantom: PhantomData })
+    }
+
+    #[inline(always)]
+    pub fn set<T: Copy>(&mut self, handle: VarHandle<T>, value: T) {
+        assert!(handle.slab_id == self.id);
+        // SAFETY: the handle was created by this Slab, so the offset is in
+        //         bounds and the slot's type matches T
+        unsafe { *((self.data.as_mut_ptr() as *mut u8).add(handle.offset) as *mut T) = value };
+    }
+
+    #[inline(always)]
+    pub fn get<T: Copy>(&self, handle: VarHandle<T>) -> T {
+        assert!(handle.slab_id == self.id);
+        // SAFETY: the handle was created by this Slab, so the offset is in
+        //         bounds and the slot's type matches T
+        unsafe { *((self.data.as_ptr() as *const u8).add(handle.offset) as *const T) }
+    }
+
+    pub fn set_hidden_state<T: HiddenStateValue>(&mut self, state: HiddenState<T>, value: T) {
+        self.set_value(state.get_index(), value);
+    }
+
+    /// # Safety
+    ///
+    /// The caller must guarantee that the pointer is valid when the expression
+    /// using this Slab is evaluated
+    pub unsafe fn set_hidden_ptr<T>(&mut self, state: HiddenState<*const T>, pointer: *const T) {
+        self.set_value(state.get_index(), pointer.addr());
+    }
+
     pub const fn get_hidden_state_count(&self) -> usize {
         self.hidden_state_count
     }
 
     pub fn get_hidden_state_type(&self, idx: usize) -> Option<ValueType> {
-        if idx < self.hidden_state_types.len() {
-            Some(self.hidden_state_types[idx])
+        if idx < self.hidden_state_count {
+            Some(self.slots[idx].value_type)
         } else {
             None
         }
     }
 
+    pub fn get_slot(&self, idx: usize) -> Option<&SlabSlot> {
+        self.slots.get(idx)
+    }
+
     pub fn get_address(&self, idx: usize) -> usize {
-        (&self.data[idx] as *const MaybeUninit<usize>).addr()
+        self.data.as_ptr().addr() + self.slots[idx].offset
+    }
+
+    /// Panics if I isn't the Table's inputs type
+    pub fn inputs_ptr<I: HotEvalInputs>(&self, inputs: &I) -> *const c_void {
+        assert!(self.inputs_type.unwrap_or(TypeId::of::<()>()) == TypeId::of::<I>());
+        (inputs as *const I).cast()
+    }
+
+    /// Panics if C isn't the Table's context type
+    pub fn context_ptr<C: 'static>(&self, context: &mut C) -> *mut c_void {
+        assert!(self.context_type == Some(TypeId::of::<C>()));
+        (context as *mut C).cast()
     }
 
+    pub const fn get_context_type(&self) -> Option<TypeId> {
+        self.context_type
+    }
+
+    pub fn get_eval_status_address(&self) -> usize {
+        self.eval_status.get().addr()
+    }
+
+    /// Returns and clears the error of the last failed evaluation
+    pub fn take_eval_error(&mut self) -> Option<EvalError> {
+        self.eval_status.get_mut().take_error()
+    }
+
+    /// Panics if idx is invalid or T doesn't match the slot's type
     #[inline(always)]
-    const unsafe fn get_value_ptr_unchecked<T: Copy>(&mut self, idx: usize) -> *mut T {
-        const { assert!(size_of::<T>() <= size_of::<usize>()); }
-        // SAFETY: idx must be guaranteed to be < self.data.len() by the caller,
-        //         so there is no OOB access, and T fits in a usize, so the
-        //         pointer can be safely cast to *mut T
-        unsafe { self.data.as_mut_ptr().offset(idx as isize) as *mut T }
+    fn assert_slot_type<T: ToBFPValueType>(&self, idx: usize) {
+        let slot = &self.slots[idx];
+        assert!(slot.value_type == T::to_bfp_value_type() && size_of::<T>() == slot.value_type.get_size());
     }
 
     #[inline(always)]
-    pub const unsafe fn set_value_unchecked<T: Copy>(&mut self, idx: usize, value: T) {
-        // SAFETY: idx must be guaranteed to be < self.data.len() by the caller
+    unsafe fn get_value_ptr_unchecked<T: Copy>(&self, idx: usize) -> *mut T {
+        // SAFETY: idx must be guaranteed to be < self.slots.len() by the
+        //         caller, and T must match the slot's value type, so there is
+        //         no OOB access and the pointer is aligned for T
+        unsafe {
+            let offset = self.slots.get_unchecked(idx).offset;
+            (self.data.as_ptr() as *mut u8).add(offset) as *mut T
+        }
+    }
+
+    /// # Safety
+    ///
+    /// idx must be a valid slot index, and T must have the same size and
+    /// alignment as the slot's value type
+    #[inline(always)]
+    pub unsafe fn set_value_unchecked<T: Copy>(&mut self, idx: usize, value: T) {
         unsafe { *self.get_value_ptr_unchecked(idx) = value };
     }
 
     #[inline(always)]
-    pub const fn set_value<T: Copy>(&mut self, idx: usize, value: T) {
-        assert!(idx < self.data.len());
-        // SAFETY: idx < self.data.len(), so there is no OOB access
+    pub fn set_value<T: ToBFPValueType + Copy>(&mut self, idx: usize, value: T) {
+        self.assert_slot_type::<T>(idx);
+        // SAFETY: idx is valid and T matches the slot's value type
         unsafe { self.set_value_unchecked(idx, value) };
     }
 
-    /// SAFETY: The caller must guarantee that the pointer is valid when the
-    ///         expression using this Slab is evaluated
+    /// # Safety
+    ///
+    /// The caller must guarantee that the pointer is valid when the expression
+    /// using this Slab is evaluated, and that idx is a valid pointer slot
+    /// index
     #[inline(always)]
     pub unsafe fn set_ptr_value_unchecked<T>(&mut self, idx: usize, pointer: *const T) {
-        // SAFETY: idx must be guaranteed to be < self.data.len() by the caller
         unsafe { self.set_value_unchecked(idx, pointer.addr()) };
     }
 
-    /// SAFETY: The caller must guarantee that the pointer is valid when the
-    ///         expression using this Slab is evaluated
-    ///
     /// This method is unsafe since it only checks that idx is valid
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee that the pointer is valid when the expression
+    /// using this Slab is evaluated
     #[inline(always)]
     pub unsafe fn set_ptr_value<T>(&mut self, idx: usize, pointer: *const T) {
         self.set_value(idx, pointer.addr());
     }
 
+    /// # Safety
+    ///
+    /// idx must be a valid slot index, and T must have the same size and
+    /// alignment as the slot's value type
     #[inline(always)]
-    pub const unsafe fn get_value_unchecked<T: Copy>(&self, idx: usize) -> T {
-        const { assert!(size_of::<T>() <= size_of::<usize>()); }
-        // SAFETY: idx must be guaranteed to be < self.data.len() by the caller,
-        //         so there is no OOB access, and T fits in a usize, so the
-        //         pointer can be safely cast to *mut T
-        unsafe { *(self.data.as_ptr().offset(idx as isize) as *const T) }
+    pub unsafe fn get_value_unchecked<T: Copy>(&self, idx: usize) -> T {
+        unsafe { *self.get_value_ptr_unchecked(idx) }
     }
 
     #[inline(always)]
-    pub const fn get_value<T: Copy>(&self, idx: usize) -> T {
-        assert!(idx < self.data.len());
-        // SAFETY: idx < self.data.len(), so there is no OOB access
+    pub fn get_value<T: ToBFPValueType + Copy>(&self, idx: usize) -> T {
+        self.assert_slot_type::<T>(idx);
+        // SAFETY: idx is valid and T matches the slot's value type
         unsafe { self.get_value_unchecked(idx) }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slots_are_aligned_for_their_type() {
+        let mut table = Table::new();
+        table.add_variable("a".into(), ValueType::U8).unwrap();
+        table.add_variable("b".into(), ValueType::U128).unwrap();
+        table.add_variable("c".into(), ValueType::U16).unwrap();
+        table.add_variable("d".into(), ValueType::F64).unwrap();
+        let slab = Slab::from_table(&table).unwrap();
+
+        for name in ["a", "b", "c", "d"] {
+            let idx = slab.get_binding_index(name).unwrap();
+            let slot = slab.get_slot(idx).unwrap();
+            assert_eq!(slab.get_address(idx) % slot.value_type.get_alignment(), 0);
+        }
+    }
+
+    #[test]
+    fn values_wider_than_usize_roundtrip() {
+        let mut table = Table::new();
+        table.add_variable("a".into(), ValueType::I128).unwrap();
+        table.add_variable("b".into(), ValueType::U8).unwrap();
+        let mut slab = Slab::from_table(&table).unwrap();
+
+        let a_idx = slab.get_binding_index("a").unwrap();
+        let b_idx = slab.get_binding_index("b").unwrap();
+        slab.set_value(a_idx, i128::MIN + 1);
+        slab.set_value(b_idx, 7u8);
+        assert_eq!(slab.get_value::<i128>(a_idx), i128::MIN + 1);
+        assert_eq!(slab.get_value::<u8>(b_idx), 7);
+    }
+
+    #[test]
+    #[should_panic]
+    fn set_value_checks_the_slot_type() {
+        let mut table = Table::new();
+        table.add_variable("a".into(), ValueType::U32).unwrap();
+        let mut slab = Slab::from_table(&table).unwrap();
+        let idx = slab.get_binding_index("a").unwrap();
+        slab.set_value(idx, 1u64);
+    }
+
+    #[test]
+    fn var_handles_read_and_write_variables() {
+        let mut table = Table::new();
+        table.add_variable("a".into(), ValueType::U64).unwrap();
+        let mut slab = Slab::from_table(&table).unwrap();
+
+        let handle = slab.handle::<u64>("a").unwrap();
+        slab.set(handle, 42);
+        assert_eq!(slab.get(handle), 42);
+        assert!(slab.handle::<u32>("a").is_err());
+        assert!(slab.handle::<u64>("b").is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn var_handles_are_tied_to_their_slab() {
+        let mut table = Table::new();
+        table.add_variable("a".into(), ValueType::U64).unwrap();
+        let first = Slab::from_table(&table).unwrap();
+        let mut second = Slab::from_table(&table).unwrap();
+
+        let handle = first.handle::<u64>("a").unwrap();
+        second.set(handle, 42);
+    }
+}
@@ -1,14 +1,16 @@
-use std::collections::{HashMap, hash_map::Iter};
+use std::{any::{Any, TypeId, type_name}, collections::HashMap, marker::PhantomData, sync::{Arc, atomic::{AtomicUsize, Ordering}}};
 
-use super::{binding::{Binding, FnPointer, FnSpecCallArg, FnSpecChoice, ToBFPValueType}, error::CommonError, value::Value, value_type::ValueType};
+use super::{binding::{Binding, BindingFuncParams, BindingFuncRets, FnPointer, FnSpecCallArg, FnSpecChoice, GenericFnInstance, GenericParam, HiddenStateValue, ParamDef, ToBFPValueType, TypeConstraint}, error::CommonError, host_function::{HostClosure, HostFunction}, host_set::HostSetData, inputs::HotEvalInputs, math_intrinsic::MathIntrinsic, value::Value, value_type::ValueType};
 
 struct BindingFunctionParamBuilder {
     mapping: HashMap<usize, ValueType>,
+    variadic: Option<ValueType>,
+    has_variadic_length: bool,
 }
 
 impl BindingFunctionParamBuilder {
     pub fn new() -> Self {
-        Self { mapping: HashMap::new() }
+        Self { mapping: HashMap::new(), variadic: None, has_variadic_length: false }
     }
 
     pub fn maybe_add_spec_call_arg(&mut self, arg: &FnSpecCallArg, fn_ptr_arg_type: ValueType) -> Result<(), CommonError> {
@@ -19,10 +21,30 @@ impl BindingFunctionParamBuilder {
             }
         }
 
+        match *arg {
+            FnSpecCallArg::VariadicPointer { .. } if self.variadic.is_some() || self.has_variadic_length => {
+                return Err(CommonError::FuncSpecArgBadVariadic);
+            },
+            FnSpecCallArg::VariadicPointer { value_type, .. } => {
+                self.variadic = Some(value_type);
+            },
+            FnSpecCallArg::VariadicLength if self.variadic.is_none() || self.has_variadic_length => {
+                return Err(CommonError::FuncSpecArgBadVariadic);
+            },
+            FnSpecCallArg::VariadicLength => {
+                self.has_variadic_length = true;
+            },
+            _ => {},
+        }
+
         Ok(())
     }
 
     pub fn finish(self) -> Result<Box<[ValueType]>, CommonError> {
+        if self.variadic.is_some() && !self.has_variadic_length {
+            return Err(CommonError::FuncSpecArgBadVariadic);
+        }
+
         let mut params = Vec::new();
         let len = self.mapping.len();
         for i in 0..len {
@@ -36,40 +58,142 @@ impl BindingFunctionParamBuilder {
     }
 }
 
+/// A typed reference to a hidden state of a Table and its Slabs
+pub struct HiddenState<T> {
+    idx: usize,
+    _phantom: PhantomData<T>,
+}
+
+impl<T> HiddenState<T> {
+    pub const fn get_index(&self) -> usize {
+        self.idx
+    }
+}
+
+impl<T> Clone for HiddenState<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for HiddenState<T> { }
+
+/// A reference to an updatable set of a Table and its Slabs
+#[derive(Clone, Copy, Debug)]
+pub struct SetHandle {
+    table_id: usize,
+    idx: usize,
+    value_type: ValueType,
+}
+
+impl SetHandle {
+    pub const fn get_table_id(&self) -> usize {
+        self.table_id
+    }
+
+    pub const fn get_index(&self) -> usize {
+        self.idx
+    }
+
+    pub const fn get_value_type(&self) -> ValueType {
+        self.value_type
+    }
+}
+
+static NEXT_TABLE_ID: AtomicUsize = AtomicUsize::new(0);
+
 pub struct Table<'table> {
-    bindings: HashMap<String, Binding<'table>>,
+    id: usize,
+    /// More than one binding per name only for function overloads
+    bindings: HashMap<String, Vec<Binding<'table>>>,
     hidden_states: Vec<ValueType>,
+    inputs_type: Option<(TypeId, &'static str)>,
+    context_type: Option<(TypeId, &'static str)>,
+    /// Cloned into every Slab, so that they outlive the Table
+    closures: Vec<Arc<dyn Any + Send + Sync>>,
+    math_intrinsics: bool,
+    updatable_set_count: usize,
+}
+
+impl Default for Table<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<'table> Table<'table> {
     pub fn new() -> Self {
-        Table { bindings: HashMap::new(), hidden_states: Vec::new() }
+        Table { id: NEXT_TABLE_ID.fetch_add(1, Ordering::Relaxed), bindings: HashMap::new(), hidden_states: Vec::new(), inputs_type: None, context_type: None, closures: Vec::new(), math_intrinsics: true, updatable_set_count: 0 }
     }
 
+    /// Functions can be added more than once with different parameter types
+    ///
+    /// # Safety
+    ///
+    /// Compiled expressions call function bindings with their declared
+    /// parameter and return types, so the function pointer must have a
+    /// matching signature, and every hidden state argument must match the
+    /// type of its slot
     pub unsafe fn add_binding(&mut self, name: String, binding: Binding<'table>) -> Result<(), CommonError> {
-        if self.bindings.contains_key(&name) {
-            Err(CommonError::BindingAlreadyExists { name })
-        } else {
-            self.bindings.insert(name, binding);
-            Ok(())
+        let Some(existing) = self.bindings.get_mut(&name) else {
+            self.bindings.insert(name, vec![binding]);
+            return Ok(())
+        };
+
+        let Binding::Function { params, variadic, .. } = &binding else {
+            return Err(CommonError::BindingAlreadyExists { name })
+        };
+
+        for overload in existing.iter() {
+            match overload {
+                Binding::Function { params: existing_params, variadic: existing_variadic, .. } if (existing_params, existing_variadic) != (params, variadic) => {},
+                Binding::Function { .. } => return Err(CommonError::OverloadAlreadyExists { name, params: params.clone() }),
+                _ => return Err(CommonError::BindingAlreadyExists { name }),
+            }
         }
+
+        existing.push(binding);
+        Ok(())
     }
 
+    /// For overloaded functions, this is the first overload that was added
     pub fn get_binding(&self, name: &String) -> Option<&'_ Binding<'_>> {
-        self.bindings.get(name)
+        self.bindings.get(name).and_then(|overloads| overloads.first())
+    }
+
+    pub fn get_overloads(&self, name: &String) -> Option<&'_ [Binding<'_>]> {
+        self.bindings.get(name).map(Vec::as_slice)
+    }
+
+    pub fn iter_bindings(&self) -> impl Iterator<Item = (&'_ String, &'_ Binding<'_>)> {
+        self.bindings.iter().flat_map(|(name, overloads)| overloads.iter().map(move |binding| (name, binding)))
+    }
+
+    /// Math intrinsics are enabled by default
+    pub fn set_math_intrinsics_enabled(&mut self, enabled: bool) {
+        self.math_intrinsics = enabled;
+    }
+
+    /// Bindings with the same name take precedence over math intrinsics
+    pub fn get_math_intrinsic(&self, name: &str) -> Option<MathIntrinsic> {
+        if self.math_intrinsics && !self.bindings.contains_key(name) {
+            MathIntrinsic::from_name(name)
+        } else {
+            None
+        }
     }
 
-    pub fn iter_bindings(&self) -> Iter<'_, String, Binding<'_>> {
-        self.bindings.iter()
+    fn add_hidden_state_raw<T: ToBFPValueType>(&mut self) -> HiddenState<T> {
+        self.hidden_states.push(T::to_bfp_value_type());
+        HiddenState { idx: self.hidden_states.len() - 1, _phantom: PhantomData }
     }
 
-    pub fn add_hidden_state(&mut self, value_type: ValueType) -> usize {
-        self.hidden_states.push(value_type);
-        self.hidden_states.len() - 1
+    pub fn add_hidden_state<T: HiddenStateValue>(&mut self) -> HiddenState<T> {
+        self.add_hidden_state_raw()
     }
 
-    pub fn add_ptr_hidden_state(&mut self) -> usize {
-        self.add_hidden_state(ValueType::USize)
+    pub fn add_ptr_hidden_state<T: 'static>(&mut self) -> HiddenState<*const T> {
+        self.add_hidden_state_raw()
     }
 
     pub fn get_hidden_state(&'table self, hidden_state_idx: usize) -> Option<&'table ValueType> {
@@ -80,6 +204,50 @@ impl<'table> Table<'table> {
         self.hidden_states.len()
     }
 
+    /// A Table can only have one inputs type
+    pub fn add_inputs<I: HotEvalInputs>(&mut self) -> Result<(), CommonError> {
+        let new = type_name::<I>();
+        if let Some((_, existing)) = self.inputs_type {
+            return Err(CommonError::InputsAlreadyAdded { existing, new })
+        }
+
+        self.inputs_type = Some((TypeId::of::<I>(), new));
+        I::add_input_fields(self)
+    }
+
+    /// Only meant to be called by [HotEvalInputs::add_input_fields]
+    ///
+    /// # Safety
+    ///
+    /// The offset must be the offset of a field with type T in the Table's
+    /// inputs struct
+    pub unsafe fn add_input_field<T: ToBFPValueType>(&mut self, name: String, offset: usize) -> Result<(), CommonError> {
+        unsafe { self.add_binding(name, Binding::Input { offset, value_type: T::to_bfp_value_type() }) }
+    }
+
+    pub fn get_inputs_type(&self) -> Option<TypeId> {
+        self.inputs_type.map(|(type_id, _)| type_id)
+    }
+
+    /// Only needed for functions added with [Table::add_binding]
+    pub fn set_context_type<C: 'static>(&mut self) -> Result<(), CommonError> {
+        self.set_context_type_raw(TypeId::of::<C>(), type_name::<C>())
+    }
+
+    fn set_context_type_raw(&mut self, type_id: TypeId, new: &'static str) -> Result<(), CommonError> {
+        match self.context_type {
+            Some((existing_id, existing)) if existing_id != type_id => Err(CommonError::ContextTypeConflict { existing, new }),
+            _ => {
+                self.context_type = Some((type_id, new));
+                Ok(())
+            },
+        }
+    }
+
+    pub fn get_context_type(&self) -> Option<TypeId> {
+        self.context_type.map(|(type_id, _)| type_id)
+    }
+
     pub fn add_const<T: Into<Value>>(&mut self, name: String, value: T) -> Result<(), CommonError> {
         unsafe { self.add_binding(name, Binding::Const { value: value.into() }) }
     }
@@ -88,328 +256,265 @@ impl<'table> Table<'table> {
         unsafe { self.add_binding(name, Binding::Variable { value_type }) }
     }
 
-    // TODO: having different methods for each number of parameters is a
-    //       complete mess, but the alternative is using a trait like
-    //       IntoBinding for fn pointers, where you still have to implement the
-    //       trait for each number of parameters. the problem is that, even if
-    //       that was an acceptable compromise (which it is), functions aren't
-    //       implicitly coerced into fn pointers when using them as type
-    //       parameters for traits, so you have to cast the function to a
-    //       pointer. e.g.:
-    //       table.add_binding("test", test);
-    //       ... becomes:
-    //       table.add_binding("test", test as fn() -> f64);
-    //       this defeats the whole purpose of having these helper methods.
-    //       it would also be nice if this code could somehow be reused into a
-    //       single implementation, but that would require rust to have a more
-    //       flexible type system. because of this, i'm only implementing up to
-    //       a few parameters for my sanity. if you need more, then use the
-    //       not-so-safe version where you have to pass a Binding value directly
+    /// Adds an integer set for `x in name` expressions
+    pub fn add_set<T: Into<Value>>(&mut self, name: String, value_type: ValueType, values: impl IntoIterator<Item = T>) -> Result<(), CommonError> {
+        let data = HostSetData::new(&name, value_type, values)?;
+        unsafe { self.add_binding(name, Binding::Set { data: Arc::new(data), handle_idx: None }) }
+    }
 
+    /// Same as [Table::add_set], but the contents can be replaced per Slab
+    pub fn add_updatable_set<T: Into<Value>>(&mut self, name: String, value_type: ValueType, values: impl IntoIterator<Item = T>) -> Result<SetHandle, CommonError> {
+        let data = HostSetData::new(&name, value_type, values)?;
+        let handle = SetHandle { table_id: self.id, idx: self.updatable_set_count, value_type };
+        unsafe { self.add_binding(name, Binding::Set { data: Arc::new(data), handle_idx: Some(handle.idx) })? };
+        self.updatable_set_count += 1;
+        Ok(handle)
+    }
+
+    pub const fn get_id(&self) -> usize {
+        self.id
+    }
+
+    pub fn get_updatable_set_count(&self) -> usize {
+        self.updatable_set_count
+    }
+
+    /// Binds a host function shim, with arguments from [FnSpecCallArg::shim_args]
+    fn add_shim_binding(&mut self, name: String, ret_types: BindingFuncRets, params: BindingFuncParams, shim: FnPointer, args: Box<[FnSpecCallArg]>) -> Result<(), CommonError> {
+        let variadic = args.iter().find_map(|arg| match arg {
+            FnSpecCallArg::VariadicPointer { value_type, .. } => Some(*value_type),
+            _ => None,
+        });
+
+        // SAFETY: the shims check their own arguments' types
+        unsafe { self.add_binding(name, Binding::Function {
+            ret_types,
+            params,
+            variadic,
+            param_defs: None,
+            fn_spec: Box::new(move |_| Ok(FnSpecChoice::Call { fn_ptr: shim, args: args.clone() })),
+        }) }
+    }
+
+    pub fn add_function<A, F: HostFunction<A>>(&mut self, name: String, function: F) -> Result<(), CommonError> {
+        let Some((shim, target)) = function.get_shim() else {
+            return Err(CommonError::UnsupportedFunction { name });
+        };
+
+        let params = F::get_param_types();
+        let args: Box<[FnSpecCallArg]> = (0..params.len()).map(|param_idx| FnSpecCallArg::MappedArgument { param_idx }).collect();
+        self.add_shim_binding(name, F::get_ret_types(), params, shim, FnSpecCallArg::shim_args(target, &args))
+    }
+
+    pub fn add_closure<A, F: HostClosure<A>>(&mut self, name: String, closure: F) -> Result<(), CommonError> {
+        let closure = Arc::new(closure);
+        let params = F::get_param_types();
+        let args: Box<[FnSpecCallArg]> = (0..params.len()).map(|param_idx| FnSpecCallArg::MappedArgument { param_idx }).collect();
+        self.add_shim_binding(name, F::get_ret_types(), params, F::get_trampoline(), FnSpecCallArg::shim_args(Arc::as_ptr(&closure).addr(), &args))?;
+        self.closures.push(closure);
+        Ok(())
+    }
+
+    /// Applies to the last added overload, with one entry per parameter
+    pub fn set_param_defs(&mut self, name: &str, defs: &[ParamDef]) -> Result<(), CommonError> {
+        let Some(Binding::Function { params, param_defs, .. }) = self.bindings.get_mut(name).and_then(|overloads| overloads.last_mut()) else {
+            return Err(CommonError::ParamDefsNotFunction { name: name.into() })
+        };
+
+        if defs.len() != params.len() {
+            return Err(CommonError::ParamDefsBadCount { name: name.into(), expected: params.len(), got: defs.len() })
+        }
+
+        for (i, (def, param)) in defs.iter().zip(params.iter()).enumerate() {
+            if defs[..i].iter().any(|other| other.name == def.name) {
+                return Err(CommonError::ParamDefsDuplicateName { name: name.into(), param: def.name.clone() })
+            }
+
+            if let Some(default) = def.default && default.get_value_type() != *param {
+                return Err(CommonError::ParamDefaultBadType { name: name.into(), param: def.name.clone(), expected: *param, got: default.get_value_type() })
+            }
+        }
+
+        *param_defs = Some(defs.into());
+        Ok(())
+    }
+
+    /// Generic parameters are specialised for each call during analysis
+    pub fn add_generic_function<S>(&mut self, name: String, constraint: TypeConstraint, params: &[GenericParam], fn_spec: S) -> Result<(), CommonError>
+    where
+        S: Fn(&[ValueType]) -> Result<GenericFnInstance, String> + 'table
+    {
+        // SAFETY: instances can only be created from host functions, and are
+        //         checked against the concrete parameter types during analysis
+        unsafe { self.add_binding(name, Binding::GenericFunction { params: params.into(), constraint, fn_spec: Box::new(fn_spec) }) }
+    }
+
+    pub fn get_closures(&self) -> &[Arc<dyn Any + Send + Sync>] {
+        &self.closures
+    }
+
+    pub fn add_function_map<A, F: HostFunction<A>>(&mut self, name: String, function: F, args: F::SpecArgs) -> Result<(), CommonError> {
+        let Some((shim, target)) = function.get_shim() else {
+            return Err(CommonError::UnsupportedFunction { name });
+        };
+
+        let mut params_builder = BindingFunctionParamBuilder::new();
+
+        for (arg, param_type) in args.as_ref().iter().zip(F::get_param_types().iter()) {
+            params_builder.maybe_add_spec_call_arg(arg, *param_type)?;
+        }
+
+        let params = params_builder.finish()?;
+        F::guard_spec_args(&args, &params)?;
+
+        // the context type is only set once the function is bound, so that a
+        // failed binding doesn't require a context
+        let mut context_type = self.context_type;
+        for arg in args.as_ref() {
+            if let FnSpecCallArg::Context { context_type: arg_type } = *arg {
+                match context_type {
+                    Some((existing_id, existing)) if existing_id != arg_type.get_type_id() => return Err(CommonError::ContextTypeConflict { existing, new: arg_type.get_type_name() }),
+                    _ => context_type = Some((arg_type.get_type_id(), arg_type.get_type_name())),
+                }
+            }
+        }
+
+        self.add_shim_binding(name, F::get_ret_types(), params, shim, FnSpecCallArg::shim_args(target, args.as_ref()))?;
+        self.context_type = context_type;
+        Ok(())
+    }
+
+    #[deprecated(note = "use add_function")]
     pub fn add_function_0<R>(&mut self, name: String, fn_ptr: fn() -> R) -> Result<(), CommonError>
     where
-        R: ToBFPValueType,
+        R: ToBFPValueType + 'static,
     {
-        let fn_ptr = fn_ptr as FnPointer;
-        unsafe { self.add_binding(name, Binding::Function {
-            ret_type: R::to_bfp_value_type(),
-            params: [].into(),
-            fn_spec: Box::new(move |_| Ok(FnSpecChoice::Call { fn_ptr, args: [].into() })),
-        }) }
+        self.add_function(name, fn_ptr)
     }
 
+    #[deprecated(note = "use add_function")]
     pub fn add_function_1<R, P1>(&mut self, name: String, fn_ptr: fn(P1) -> R) -> Result<(), CommonError>
     where
-        R: ToBFPValueType,
-        P1: ToBFPValueType,
+        R: ToBFPValueType + 'static,
+        P1: ToBFPValueType + 'static,
     {
-        let fn_ptr = fn_ptr as FnPointer;
-        unsafe { self.add_binding(name, Binding::Function {
-            ret_type: R::to_bfp_value_type(),
-            params: [
-                P1::to_bfp_value_type().into(),
-            ].into(),
-            fn_spec: Box::new(move |_| Ok(FnSpecChoice::Call {fn_ptr, args: [
-                FnSpecCallArg::MappedArgument { param_idx: 0 },
-            ].into() })),
-        }) }
+        self.add_function(name, fn_ptr)
     }
 
+    #[deprecated(note = "use add_function_map")]
     pub fn add_function_1_map<R, P1, M1>(&mut self, name: String, fn_ptr: fn(P1) -> R, p1: M1) -> Result<(), CommonError>
     where
-        R: ToBFPValueType,
-        P1: ToBFPValueType,
+        R: ToBFPValueType + 'static,
+        P1: ToBFPValueType + 'static,
         M1: Into<FnSpecCallArg>,
     {
-        let fn_ptr = fn_ptr as FnPointer;
-        let mut params_builder = BindingFunctionParamBuilder::new();
-
-        let p1 = p1.into();
-        params_builder.maybe_add_spec_call_arg(&p1, P1::to_bfp_value_type().into())?;
-
-        let params = params_builder.finish()?;
-        p1.guard::<P1>(&params)?;
-
-        unsafe { self.add_binding(name, Binding::Function {
-            ret_type: R::to_bfp_value_type(),
-            params,
-            fn_spec: Box::new(move |_| Ok(FnSpecChoice::Call {fn_ptr, args: [
-                p1,
-            ].into() })),
-        }) }
+        self.add_function_map(name, fn_ptr, [p1.into()])
     }
 
+    #[deprecated(note = "use add_function")]
     pub fn add_function_2<R, P1, P2>(&mut self, name: String, fn_ptr: fn(P1, P2) -> R) -> Result<(), CommonError>
     where
-        R: ToBFPValueType,
-        P1: ToBFPValueType,
-        P2: ToBFPValueType,
+        R: ToBFPValueType + 'static,
+        P1: ToBFPValueType + 'static,
+        P2: ToBFPValueType + 'static,
     {
-        let fn_ptr = fn_ptr as FnPointer;
-        unsafe { self.add_binding(name, Binding::Function {
-            ret_type: R::to_bfp_value_type(),
-            params: [
-                P1::to_bfp_value_type().into(),
-                P2::to_bfp_value_type().into(),
-            ].into(),
-            fn_spec: Box::new(move |_| Ok(FnSpecChoice::Call {fn_ptr, args: [
-                FnSpecCallArg::MappedArgument { param_idx: 0 },
-                FnSpecCallArg::MappedArgument { param_idx: 1 },
-            ].into() })),
-        }) }
+        self.add_function(name, fn_ptr)
     }
 
+    #[deprecated(note = "use add_function_map")]
     pub fn add_function_2_map<R, P1, M1, P2, M2>(&mut self, name: String, fn_ptr: fn(P1, P2) -> R, p1: M1, p2: M2) -> Result<(), CommonError>
     where
-        R: ToBFPValueType,
-        P1: ToBFPValueType,
+        R: ToBFPValueType + 'static,
+        P1: ToBFPValueType + 'static,
         M1: Into<FnSpecCallArg>,
-        P2: ToBFPValueType,
+        P2: ToBFPValueType + 'static,
         M2: Into<FnSpecCallArg>,
     {
-        let fn_ptr = fn_ptr as FnPointer;
-        let mut params_builder = BindingFunctionParamBuilder::new();
-
-        let p1 = p1.into();
-        params_builder.maybe_add_spec_call_arg(&p1, P1::to_bfp_value_type().into())?;
-        let p2 = p2.into();
-        params_builder.maybe_add_spec_call_arg(&p2, P2::to_bfp_value_type().into())?;
-
-        let params = params_builder.finish()?;
-        p1.guard::<P1>(&params)?;
-        p2.guard::<P2>(&params)?;
-
-        unsafe { self.add_binding(name, Binding::Function {
-            ret_type: R::to_bfp_value_type(),
-            params,
-            fn_spec: Box::new(move |_| Ok(FnSpecChoice::Call {fn_ptr, args: [
-                p1,
-                p2,
-            ].into() })),
-        }) }
+        self.add_function_map(name, fn_ptr, [p1.into(), p2.into()])
     }
 
+    #[deprecated(note = "use add_function")]
     pub fn add_function_3<R, P1, P2, P3>(&mut self, name: String, fn_ptr: fn(P1, P2, P3) -> R) -> Result<(), CommonError>
     where
-        R: ToBFPValueType,
-        P1: ToBFPValueType,
-        P2: ToBFPValueType,
-        P3: ToBFPValueType,
+        R: ToBFPValueType + 'static,
+        P1: ToBFPValueType + 'static,
+        P2: ToBFPValueType + 'static,
+        P3: ToBFPValueType + 'static,
     {
-        let fn_ptr = fn_ptr as FnPointer;
-        unsafe { self.add_binding(name, Binding::Function {
-            ret_type: R::to_bfp_value_type(),
-            params: [
-                P1::to_bfp_value_type().into(),
-                P2::to_bfp_value_type().into(),
-                P3::to_bfp_value_type().into(),
-            ].into(),
-            fn_spec: Box::new(move |_| Ok(FnSpecChoice::Call {fn_ptr, args: [
-                FnSpecCallArg::MappedArgument { param_idx: 0 },
-                FnSpecCallArg::MappedArgument { param_idx: 1 },
-                FnSpecCallArg::MappedArgument { param_idx: 2 },
-            ].into() })),
-        }) }
+        self.add_function(name, fn_ptr)
     }
 
+    #[deprecated(note = "use add_function_map")]
     pub fn add_function_3_map<R, P1, M1, P2, M2, P3, M3>(&mut self, name: String, fn_ptr: fn(P1, P2, P3) -> R, p1: M1, p2: M2, p3: M3) -> Result<(), CommonError>
     where
-        R: ToBFPValueType,
-        P1: ToBFPValueType,
+        R: ToBFPValueType + 'static,
+        P1: ToBFPValueType + 'static,
         M1: Into<FnSpecCallArg>,
-        P2: ToBFPValueType,
+        P2: ToBFPValueType + 'static,
         M2: Into<FnSpecCallArg>,
-        P3: ToBFPValueType,
+        P3: ToBFPValueType + 'static,
         M3: Into<FnSpecCallArg>,
     {
-        let fn_ptr = fn_ptr as FnPointer;
-        let mut params_builder = BindingFunctionParamBuilder::new();
-
-        let p1 = p1.into();
-        params_builder.maybe_add_spec_call_arg(&p1, P1::to_bfp_value_type().into())?;
-        let p2 = p2.into();
-        params_builder.maybe_add_spec_call_arg(&p2, P2::to_bfp_value_type().into())?;
-        let p3 = p3.into();
-        params_builder.maybe_add_spec_call_arg(&p3, P3::to_bfp_value_type().into())?;
-
-        let params = params_builder.finish()?;
-        p1.guard::<P1>(&params)?;
-        p2.guard::<P2>(&params)?;
-        p3.guard::<P3>(&params)?;
-
-        unsafe { self.add_binding(name, Binding::Function {
-            ret_type: R::to_bfp_value_type(),
-            params,
-            fn_spec: Box::new(move |_| Ok(FnSpecChoice::Call {fn_ptr, args: [
-                p1,
-                p2,
-                p3,
-            ].into() })),
-        }) }
+        self.add_function_map(name, fn_ptr, [p1.into(), p2.into(), p3.into()])
     }
 
+    #[deprecated(note = "use add_function")]
     pub fn add_function_4<R, P1, P2, P3, P4>(&mut self, name: String, fn_ptr: fn(P1, P2, P3, P4) -> R) -> Result<(), CommonError>
     where
-        R: ToBFPValueType,
-        P1: ToBFPValueType,
-        P2: ToBFPValueType,
-        P3: ToBFPValueType,
-        P4: ToBFPValueType,
+        R: ToBFPValueType + 'static,
+        P1: ToBFPValueType + 'static,
+        P2: ToBFPValueType + 'static,
+        P3: ToBFPValueType + 'static,
+        P4: ToBFPValueType + 'static,
     {
-        let fn_ptr = fn_ptr as FnPointer;
-        unsafe { self.add_binding(name, Binding::Function {
-            ret_type: R::to_bfp_value_type(),
-            params: [
-                P1::to_bfp_value_type().into(),
-                P2::to_bfp_value_type().into(),
-                P3::to_bfp_value_type().into(),
-                P4::to_bfp_value_type().into(),
-            ].into(),
-            fn_spec: Box::new(move |_| Ok(FnSpecChoice::Call {fn_ptr, args: [
-                FnSpecCallArg::MappedArgument { param_idx: 0 },
-                FnSpecCallArg::MappedArgument { param_idx: 1 },
-                FnSpecCallArg::MappedArgument { param_idx: 2 },
-                FnSpecCallArg::MappedArgument { param_idx: 3 },
-            ].into() })),
-        }) }
+        self.add_function(name, fn_ptr)
     }
 
+    #[deprecated(note = "use add_function_map")]
     pub fn add_function_4_map<R, P1, M1, P2, M2, P3, M3, P4, M4>(&mut self, name: String, fn_ptr: fn(P1, P2, P3, P4) -> R, p1: M1, p2: M2, p3: M3, p4: M4) -> Result<(), CommonError>
     where
-        R: ToBFPValueType,
-        P1: ToBFPValueType,
+        R: ToBFPValueType + 'static,
+        P1: ToBFPValueType + 'static,
         M1: Into<FnSpecCallArg>,
-        P2: ToBFPValueType,
+        P2: ToBFPValueType + 'static,
         M2: Into<FnSpecCallArg>,
-        P3: ToBFPValueType,
+        P3: ToBFPValueType + 'static,
         M3: Into<FnSpecCallArg>,
-        P4: ToBFPValueType,
+        P4: ToBFPValueType + 'static,
         M4: Into<FnSpecCallArg>,
     {
-        let fn_ptr = fn_ptr as FnPointer;
-        let mut params_builder = BindingFunctionParamBuilder::new();
-
-        let p1 = p1.into();
-        params_builder.maybe_add_spec_call_arg(&p1, P1::to_bfp_value_type().into())?;
-        let p2 = p2.into();
-        params_builder.maybe_add_spec_call_arg(&p2, P2::to_bfp_value_type().into())?;
-        let p3 = p3.into();
-        params_builder.maybe_add_spec_call_arg(&p3, P3::to_bfp_value_type().into())?;
-        let p4 = p4.into();
-        params_builder.maybe_add_spec_call_arg(&p4, P4::to_bfp_value_type().into())?;
-
-        let params = params_builder.finish()?;
-        p1.guard::<P1>(&params)?;
-        p2.guard::<P2>(&params)?;
-        p3.guard::<P3>(&params)?;
-        p4.guard::<P4>(&params)?;
-
-        unsafe { self.add_binding(name, Binding::Function {
-            ret_type: R::to_bfp_value_type(),
-            params,
-            fn_spec: Box::new(move |_| Ok(FnSpecChoice::Call {fn_ptr, args: [
-                p1,
-                p2,
-                p3,
-                p4,
-            ].into() })),
-        }) }
+        self.add_function_map(name, fn_ptr, [p1.into(), p2.into(), p3.into(), p4.into()])
     }
 
+    #[deprecated(note = "use add_function")]
     pub fn add_function_5<R, P1, P2, P3, P4, P5>(&mut self, name: String, fn_ptr: fn(P1, P2, P3, P4, P5) -> R) -> Result<(), CommonError>
     where
-        R: ToBFPValueType,
-        P1: ToBFPValueType,
-        P2: ToBFPValueType,
-        P3: ToBFPValueType,
-        P4: ToBFPValueType,
-        P5: ToBFPValueType,
+        R: ToBFPValueType + 'static,
+        P1: ToBFPValueType + 'static,
+        P2: ToBFPValueType + 'static,
+        P3: ToBFPValueType + 'static,
+        P4: ToBFPValueType + 'static,
+        P5: ToBFPValueType + 'static,
     {
-        let fn_ptr = fn_ptr as FnPointer;
-        unsafe { self.add_binding(name, Binding::Function {
-            ret_type: R::to_bfp_value_type(),
-            params: [
-                P1::to_bfp_value_type().into(),
-                P2::to_bfp_value_type().into(),
-                P3::to_bfp_value_type().into(),
-                P4::to_bfp_value_type().into(),
-                P5::to_bfp_value_type().into(),
-            ].into(),
-            fn_spec: Box::new(move |_| Ok(FnSpecChoice::Call {fn_ptr, args: [
-                FnSpecCallArg::MappedArgument { param_idx: 0 },
-                FnSpecCallArg::MappedArgument { param_idx: 1 },
-                FnSpecCallArg::MappedArgument { param_idx: 2 },
-                FnSpecCallArg::MappedArgument { param_idx: 3 },
-                FnSpecCallArg::MappedArgument { param_idx: 4 },
-            ].into() })),
-        }) }
+        self.add_function(name, fn_ptr)
     }
 
+    #[deprecated(note = "use add_function_map")]
+    #[allow(clippy::too_many_arguments)]
     pub fn add_function_5_map<R, P1, M1, P2, M2, P3, M3, P4, M4, P5, M5>(&mut self, name: String, fn_ptr: fn(P1, P2, P3, P4, P5) -> R, p1: M1, p2: M2, p3: M3, p4: M4, p5: M5) -> Result<(), CommonError>
     where
-        R: ToBFPValueType,
-        P1: ToBFPValueType,
+        R: ToBFPValueType + 'static,
+        P1: ToBFPValueType + 'static,
         M1: Into<FnSpecCallArg>,
-        P2: ToBFPValueType,
+        P2: ToBFPValueType + 'static,
         M2: Into<FnSpecCallArg>,
-        P3: ToBFPValueType,
+        P3: ToBFPValueType + 'static,
         M3: Into<FnSpecCallArg>,
-        P4: ToBFPValueType,
+        P4: ToBFPValueType + 'static,
         M4: Into<FnSpecCallArg>,
-        P5: ToBFPValueType,
+        P5: ToBFPValueType + 'static,
         M5: Into<FnSpecCallArg>,
     {
-        let fn_ptr = fn_ptr as FnPointer;
-        let mut params_builder = BindingFunctionParamBuilder::new();
-
-        let p1 = p1.into();
-        params_builder.maybe_add_spec_call_arg(&p1, P1::to_bfp_value_type().into())?;
-        let p2 = p2.into();
-        params_builder.maybe_add_spec_call_arg(&p2, P2::to_bfp_value_type().into())?;
-        let p3 = p3.into();
-        params_builder.maybe_add_spec_call_arg(&p3, P3::to_bfp_value_type().into())?;
-        let p4 = p4.into();
-        params_builder.maybe_add_spec_call_arg(&p4, P4::to_bfp_value_type().into())?;
-        let p5 = p5.into();
-        params_builder.maybe_add_spec_call_arg(&p5, P5::to_bfp_value_type().into())?;
-
-        let params = params_builder.finish()?;
-        p1.guard::<P1>(&params)?;
-        p2.guard::<P2>(&params)?;
-        p3.guard::<P3>(&params)?;
-        p4.guard::<P4>(&params)?;
-        p5.guard::<P5>(&params)?;
-
-        unsafe { self.add_binding(name, Binding::Function {
-            ret_type: R::to_bfp_value_type(),
-            params,
-            fn_spec: Box::new(move |_| Ok(FnSpecChoice::Call {fn_ptr, args: [
-                p1,
-                p2,
-                p3,
-                p4,
-                p5,
-            ].into() })),
-        }) }
+        self.add_function_map(name, fn_ptr, [p1.into(), p2.into(), p3.into(), p4.into(), p5.into()])
     }
 }
\ No newline at end of file
@@ -24,11 +24,14 @@ impl UntypedValue {
                     ValueType::U16 => Value::U16 { inner: (*inner).try_into()? },
                     ValueType::U32 => Value::U32 { inner: (*inner).try_into()? },
                     ValueType::U64 => Value::U64 { inner: *inner },
+                    ValueType::U128 => Value::U128 { inner: (*inner).into() },
                     ValueType::USize => Value::USize { inner: (*inner).try_into()? },
                     ValueType::I8 => Value::I8 { inner: (*inner).try_into()? },
                     ValueType::I16 => Value::I16 { inner: (*inner).try_into()? },
                     ValueType::I32 => Value::I32 { inner: (*inner).try_into()? },
                     ValueType::I64 => Value::I64 { inner: (*inner).try_into()? },
+                    ValueType::I128 => Value::I128 { inner: (*inner).into() },
+                    ValueType::ISize => Value::ISize { inner: (*inner).try_into()? },
                     ValueType::F32 => Value::F32 { inner: *inner as f32 },
                     ValueType::F64 => Value::F64 { inner: *inner as f64 },
                     _ => return Err(Box::new(CommonError::CannotResolve { from: self.clone(), to: resolved_type })),
@@ -1,16 +1,19 @@
 use super::value_type::ValueType;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Value {
     U8 { inner: u8 },
     U16 { inner: u16 },
     U32 { inner: u32 },
     U64 { inner: u64 },
+    U128 { inner: u128 },
     USize { inner: usize },
     I8 { inner: i8 },
     I16 { inner: i16 },
     I32 { inner: i32 },
     I64 { inner: i64 },
+    I128 { inner: i128 },
+    ISize { inner: isize },
     F32 { inner: f32 },
     F64 { inner: f64 },
     Bool { inner: bool },
@@ -23,27 +26,75 @@ impl Value {
             Value::U16 { .. } => ValueType::U16,
             Value::U32 { .. } => ValueType::U32,
             Value::U64 { .. } => ValueType::U64,
+            Value::U128 { .. } => ValueType::U128,
             Value::USize { .. } => ValueType::USize,
             Value::I8 { .. } => ValueType::I8,
             Value::I16 { .. } => ValueType::I16,
             Value::I32 { .. } => ValueType::I32,
             Value::I64 { .. } => ValueType::I64,
+            Value::I128 { .. } => ValueType::I128,
+            Value::ISize { .. } => ValueType::ISize,
             Value::F32 { .. } => ValueType::F32,
             Value::F64 { .. } => ValueType::F64,
             Value::Bool { .. } => ValueType::Bool,
         }
     }
+
+    /// Gets an integer value as an i128, if it fits in one
+    pub fn get_int_key(&self) -> Option<i128> {
+        Some(match *self {
+            Value::U8 { inner } => inner.into(),
+            Value::U16 { inner } => inner.into(),
+            Value::U32 { inner } => inner.into(),
+            Value::U64 { inner } => inner.into(),
+            Value::U128 { inner } => inner.try_into().ok()?,
+            Value::USize { inner } => inner as i128,
+            Value::I8 { inner } => inner.into(),
+            Value::I16 { inner } => inner.into(),
+            Value::I32 { inner } => inner.into(),
+            Value::I64 { inner } => inner.into(),
+            Value::I128 { inner } => inner,
+            Value::ISize { inner } => inner as i128,
+            Value::F32 { .. } |
+            Value::F64 { .. } |
+            Value::Bool { .. } => return None,
+        })
+    }
+
+    /// Makes an integer value with the given type, if the key fits in it
+    pub fn from_int_key(key: i128, value_type: ValueType) -> Option<Self> {
+        Some(match value_type {
+            ValueType::U8 => Value::U8 { inner: key.try_into().ok()? },
+            ValueType::U16 => Value::U16 { inner: key.try_into().ok()? },
+            ValueType::U32 => Value::U32 { inner: key.try_into().ok()? },
+            ValueType::U64 => Value::U64 { inner: key.try_into().ok()? },
+            ValueType::U128 => Value::U128 { inner: key.try_into().ok()? },
+            ValueType::USize => Value::USize { inner: key.try_into().ok()? },
+            ValueType::I8 => Value::I8 { inner: key.try_into().ok()? },
+            ValueType::I16 => Value::I16 { inner: key.try_into().ok()? },
+            ValueType::I32 => Value::I32 { inner: key.try_into().ok()? },
+            ValueType::I64 => Value::I64 { inner: key.try_into().ok()? },
+            ValueType::I128 => Value::I128 { inner: key },
+            ValueType::ISize => Value::ISize { inner: key.try_into().ok()? },
+            ValueType::F32 |
+            ValueType::F64 |
+            ValueType::Bool => return None,
+        })
+    }
 }
 
 impl From<u8> for Value { fn from(inner: u8) -> Self { Self::U8 { inner } } }
 impl From<u16> for Value { fn from(inner: u16) -> Self { Self::U16 { inner } } }
 impl From<u32> for Value { fn from(inner: u32) -> Self { Self::U32 { inner } } }
 impl From<u64> for Value { fn from(inner: u64) -> Self { Self::U64 { inner } } }
+impl From<u128> for Value { fn from(inner: u128) -> Self { Self::U128 { inner } } }
 impl From<usize> for Value { fn from(inner: usize) -> Self { Self::USize { inner } } }
 impl From<i8> for Value { fn from(inner: i8) -> Self { Self::I8 { inner } } }
 impl From<i16> for Value { fn from(inner: i16) -> Self { Self::I16 { inner } } }
 impl From<i32> for Value { fn from(inner: i32) -> Self { Self::I32 { inner } } }
 impl From<i64> for Value { fn from(inner: i64) -> Self { Self::I64 { inner } } }
+impl From<i128> for Value { fn from(inner: i128) -> Self { Self::I128 { inner } } }
+impl From<isize> for Value { fn from(inner: isize) -> Self { Self::ISize { inner } } }
 impl From<f32> for Value { fn from(inner: f32) -> Self { Self::F32 { inner } } }
 impl From<f64> for Value { fn from(inner: f64) -> Self { Self::F64 { inner } } }
 impl From<bool> for Value { fn from(inner: bool) -> Self { Self::Bool { inner } } }
\ No newline at end of file
@@ -6,11 +6,14 @@ pub enum ValueType {
     U16,
     U32,
     U64,
+    U128,
     USize,
     I8,
     I16,
     I32,
     I64,
+    I128,
+    ISize,
     F32,
     F64,
     Bool,
@@ -25,29 +28,80 @@ impl ValueType {
             Self::U16 => 3,
             Self::I16 => 4,
             Self::U32 => 5,
-            Self::I32 => 6,
-            Self::U64 => 7,
-            Self::I64 => 8,
-            Self::USize => 9,
-            Self::F32 => 10,
-            Self::F64 => 11,
+            // usize comes right after u32 on 32-bit targets, so that it can be
+            // widened to u64 and i64
+            Self::USize => if cfg!(target_pointer_width = "64") { 11 } else { 6 },
+            Self::I32 => 7,
+            Self::ISize => 8,
+            Self::U64 => 9,
+            Self::I64 => 10,
+            Self::I128 => 12,
+            Self::U128 => 13,
+            Self::F32 => 14,
+            Self::F64 => 15,
+        }
+    }
+
+    /// Size of the value in bytes, when stored in memory
+    pub const fn get_size(&self) -> usize {
+        match self {
+            Self::U8 => size_of::<u8>(),
+            Self::U16 => size_of::<u16>(),
+            Self::U32 => size_of::<u32>(),
+            Self::U64 => size_of::<u64>(),
+            Self::U128 => size_of::<u128>(),
+            Self::USize => size_of::<usize>(),
+            Self::I8 => size_of::<i8>(),
+            Self::I16 => size_of::<i16>(),
+            Self::I32 => size_of::<i32>(),
+            Self::I64 => size_of::<i64>(),
+            Self::I128 => size_of::<i128>(),
+            Self::ISize => size_of::<isize>(),
+            Self::F32 => size_of::<f32>(),
+            Self::F64 => size_of::<f64>(),
+            Self::Bool => size_of::<bool>(),
+        }
+    }
+
+    /// Alignment of the value in bytes, when stored in memory
+    pub const fn get_alignment(&self) -> usize {
+        match self {
+            Self::U8 => align_of::<u8>(),
+            Self::U16 => align_of::<u16>(),
+            Self::U32 => align_of::<u32>(),
+            Self::U64 => align_of::<u64>(),
+            Self::U128 => align_of::<u128>(),
+            Self::USize => align_of::<usize>(),
+            Self::I8 => align_of::<i8>(),
+            Self::I16 => align_of::<i16>(),
+            Self::I32 => align_of::<i32>(),
+            Self::I64 => align_of::<i64>(),
+            Self::I128 => align_of::<i128>(),
+            Self::ISize => align_of::<isize>(),
+            Self::F32 => align_of::<f32>(),
+            Self::F64 => align_of::<f64>(),
+            Self::Bool => align_of::<bool>(),
         }
     }
 
     pub const fn is_signed(&self) -> bool {
         match self {
-            Self::Bool | Self::U8 | Self::U16 | Self::U32 | Self::U64 | Self::USize => false,
-            Self::I8 | Self::I16 | Self::I32 | Self::I64 | Self::F32 | Self::F64 => true,
+            Self::Bool | Self::U8 | Self::U16 | Self::U32 | Self::U64 | Self::U128 | Self::USize => false,
+            Self::I8 | Self::I16 | Self::I32 | Self::I64 | Self::I128 | Self::ISize | Self::F32 | Self::F64 => true,
         }
     }
 
     pub const fn is_float(&self) -> bool {
         match self {
-            Self::Bool | Self::U8 | Self::U16 | Self::U32 | Self::U64 | Self::USize | Self::I8 | Self::I16 | Self::I32 | Self::I64 => false,
+            Self::Bool | Self::U8 | Self::U16 | Self::U32 | Self::U64 | Self::U128 | Self::USize | Self::I8 | Self::I16 | Self::I32 | Self::I64 | Self::I128 | Self::ISize => false,
             Self::F32 | Self::F64 => true,
         }
     }
 
+    pub const fn is_integer(&self) -> bool {
+        !self.is_float() && !matches!(self, Self::Bool)
+    }
+
     pub const fn can_implicit_cast_to(&self, to: &Self) -> bool {
         // can implicitly cast:
         // - low-priority unsigned/signed -> high-priority signed
@@ -55,8 +109,28 @@ impl ValueType {
         // all these should be safe except for floats which will inherently
         // lose precision for high values, but is expected behaviour in most
         // languages (e.g. i32 -> f32, or u64 -> f32)
-        (self.get_implicit_cast_priority() < to.get_implicit_cast_priority()) &&
-            (to.is_signed() || !self.is_signed())
+        if !((self.get_implicit_cast_priority() < to.get_implicit_cast_priority()) &&
+            (to.is_signed() || !self.is_signed()))
+        {
+            return false;
+        }
+
+        // the width of usize and isize depends on the target, so casts from or
+        // to them are only allowed if they can't lose any values on this
+        // target (e.g. u32 -> isize is lossy on 32-bit targets)
+        if (self.is_pointer_sized() || to.is_pointer_sized()) && self.is_integer() && to.is_integer() {
+            if self.is_signed() == to.is_signed() {
+                to.get_size() >= self.get_size()
+            } else {
+                to.get_size() > self.get_size()
+            }
+        } else {
+            true
+        }
+    }
+
+    const fn is_pointer_sized(&self) -> bool {
+        matches!(self, Self::USize | Self::ISize)
     }
 
     pub fn widen(mut a: Self, mut b: Self) -> Result<Self, CommonError> {
@@ -69,7 +143,7 @@ impl ValueType {
         if a.can_implicit_cast_to(&b) {
             Ok(b)
         } else {
-            Err(CommonError::CannotImplicitCast { from: a, to: b }.into())
+            Err(CommonError::CannotImplicitCast { from: a, to: b })
         }
     }
 
@@ -85,6 +159,17 @@ impl ValueType {
         })
     }
 
+    /// Same as [Self::widen_optional_greedy], except that integers with
+    /// different signedness are never widened, since comparisons between them
+    /// are done without casting to a common type
+    pub fn widen_optional_for_comparison(a: Option<Self>, b: Option<Self>) -> Result<Option<Self>, CommonError> {
+        if let (Some(a), Some(b)) = (a, b) && a.is_integer() && b.is_integer() && a.is_signed() != b.is_signed() {
+            return Ok(None);
+        }
+
+        Self::widen_optional_greedy(a, b)
+    }
+
     pub fn widen_optional_non_greedy(a: Option<Self>, b: Option<Self>) -> Result<Option<Self>, CommonError> {
         Ok(if let Some(a) = a {
             if let Some(b) = b {
@@ -99,13 +184,14 @@ impl ValueType {
 
     pub fn to_signed(&self) -> Result<Self, CommonError> {
         Ok(match self {
-            Self::Bool |
-            Self::USize => return Err(CommonError::CannotMakeSigned { from: self.clone() }),
+            Self::Bool => return Err(CommonError::CannotMakeSigned { from: *self }),
             Self::U8 => Self::I8,
             Self::U16 => Self::I16,
             Self::U32 => Self::I32,
             Self::U64 => Self::I64,
-            _ => self.clone(),
+            Self::U128 => Self::I128,
+            Self::USize => Self::ISize,
+            _ => *self,
         })
     }
 
@@ -116,4 +202,62 @@ impl ValueType {
             None
         })
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comparison_widening_skips_mixed_sign_integers() {
+        assert_eq!(ValueType::widen_optional_for_comparison(Some(ValueType::U8), Some(ValueType::I8)).unwrap(), None);
+        assert_eq!(ValueType::widen_optional_for_comparison(Some(ValueType::U64), Some(ValueType::I32)).unwrap(), None);
+        assert_eq!(ValueType::widen_optional_for_comparison(Some(ValueType::U8), Some(ValueType::U32)).unwrap(), Some(ValueType::U32));
+        assert_eq!(ValueType::widen_optional_for_comparison(Some(ValueType::I8), Some(ValueType::F32)).unwrap(), Some(ValueType::F32));
+        assert_eq!(ValueType::widen_optional_for_comparison(None, Some(ValueType::U16)).unwrap(), Some(ValueType::U16));
+    }
+
+    #[test]
+    fn no_same_width_unsigned_to_signed_casts_for_new_types() {
+        assert!(!ValueType::USize.can_implicit_cast_to(&ValueType::ISize));
+        assert!(!ValueType::U64.can_implicit_cast_to(&ValueType::ISize));
+        assert!(!ValueType::U128.can_implicit_cast_to(&ValueType::I128));
+        assert!(!ValueType::I128.can_implicit_cast_to(&ValueType::U128));
+        assert!(ValueType::U64.can_implicit_cast_to(&ValueType::I128));
+        assert!(ValueType::USize.can_implicit_cast_to(&ValueType::I128));
+        assert!(ValueType::USize.can_implicit_cast_to(&ValueType::U128));
+        assert!(ValueType::I32.can_implicit_cast_to(&ValueType::ISize));
+        assert!(ValueType::ISize.can_implicit_cast_to(&ValueType::I64));
+        assert_eq!(ValueType::U32.can_implicit_cast_to(&ValueType::ISize), size_of::<isize>() > 4);
+        assert_eq!(ValueType::U64.can_implicit_cast_to(&ValueType::USize), size_of::<usize>() >= 8);
+    }
+
+    #[test]
+    fn widening_pointer_sized_types() {
+        assert_eq!(ValueType::widen(ValueType::I32, ValueType::ISize).unwrap(), ValueType::ISize);
+        assert_eq!(ValueType::widen(ValueType::ISize, ValueType::I128).unwrap(), ValueType::I128);
+        assert!(ValueType::widen(ValueType::USize, ValueType::ISize).is_err());
+        assert!(ValueType::widen(ValueType::U64, ValueType::ISize).is_err());
+        assert_eq!(ValueType::widen(ValueType::USize, ValueType::I128).unwrap(), ValueType::I128);
+        assert_eq!(ValueType::widen(ValueType::U16, ValueType::ISize).unwrap(), ValueType::ISize);
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn widening_pointer_sized_types_on_64_bit_targets() {
+        assert_eq!(ValueType::widen(ValueType::U64, ValueType::USize).unwrap(), ValueType::USize);
+        assert_eq!(ValueType::widen(ValueType::U32, ValueType::ISize).unwrap(), ValueType::ISize);
+        assert_eq!(ValueType::widen(ValueType::ISize, ValueType::I64).unwrap(), ValueType::I64);
+        assert!(ValueType::widen(ValueType::USize, ValueType::I64).is_err());
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "32")]
+    fn widening_pointer_sized_types_on_32_bit_targets() {
+        assert_eq!(ValueType::widen(ValueType::U32, ValueType::USize).unwrap(), ValueType::USize);
+        assert_eq!(ValueType::widen(ValueType::USize, ValueType::U64).unwrap(), ValueType::U64);
+        assert_eq!(ValueType::widen(ValueType::USize, ValueType::I64).unwrap(), ValueType::I64);
+        assert_eq!(ValueType::widen(ValueType::ISize, ValueType::I64).unwrap(), ValueType::I64);
+        assert!(ValueType::widen(ValueType::USize, ValueType::I32).is_err());
+        assert!(ValueType::widen(ValueType::U32, ValueType::ISize).is_err());
+    }
+}
@@ -1,4 +1,7 @@
 pub mod ast;
 pub mod codegen;
 pub mod common;
-pub mod analysis;
\ No newline at end of file
+pub mod analysis;
+
+pub use common::inputs::HotEvalInputs;
+pub use hot_eval_derive::{HotEvalInputs, function};
\ No newline at end of file
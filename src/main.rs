@@ -1,22 +1,29 @@
-use std::{error::Error, hint::black_box, time::Instant};
+use std::{error::Error, hint::black_box, ptr::{null, null_mut}, time::Instant};
 
-use hot_eval::{codegen::{compiled_expression::{CompiledExpression, HotEvalJitFunction}, jit_context::JITContext}, common::{binding::FnSpecCallArg, slab::Slab, table::Table, value_type::ValueType}};
+use hot_eval::{HotEvalInputs, codegen::{compiled_expression::{CompiledExpression, HotEvalJitFunction}, jit_context::JITContext}, common::{binding::FnSpecCallArg, slab::{Slab, VarHandle}, table::{HiddenState, Table}, value_type::ValueType}};
 
 const ITERS: u32 = 100_000_000;
 
+#[derive(HotEvalInputs)]
+#[repr(C)]
+struct Inputs {
+    x: u32,
+}
+
+#[hot_eval::function]
 #[inline(never)]
 fn get_wanted_x(seed1: u32, seed2: u32, seed3: &u32) -> u32 {
     (seed1 * 123 - 45) / seed2 + *seed3
 }
 
 #[inline(never)]
-fn benchmark_jit<'ctx>(slab: &'ctx mut Slab, jit_fn: HotEvalJitFunction<'ctx, bool>, test_value_idx: usize, seed3_idx: usize) -> u32 {
+fn benchmark_jit_slab<'ctx>(slab: &'ctx mut Slab, jit_fn: HotEvalJitFunction<'ctx, bool>, test_value: VarHandle<u32>, seed3: HiddenState<*const u32>) -> u32 {
     let mut matches = 0;
 
     for x in 0..ITERS {
-        slab.set_value(test_value_idx, x);
-        unsafe { slab.set_ptr_value(seed3_idx, &42); }
-        if unsafe { jit_fn.call() } {
+        slab.set(test_value, x);
+        unsafe { slab.set_hidden_ptr(seed3, &42); }
+        if unsafe { jit_fn.call(null(), null_mut()) } {
             matches += 1;
         }
     }
@@ -25,7 +32,22 @@ fn benchmark_jit<'ctx>(slab: &'ctx mut Slab, jit_fn: HotEvalJitFunction<'ctx, bo
 }
 
 #[inline(never)]
-fn benchmark_aot_inline<'ctx>() -> u32 {
+fn benchmark_jit_inputs<'ctx>(slab: &'ctx mut Slab, jit_fn: HotEvalJitFunction<'ctx, bool>, seed3: HiddenState<*const u32>) -> u32 {
+    let mut matches = 0;
+
+    for x in 0..ITERS {
+        let inputs = Inputs { x };
+        unsafe { slab.set_hidden_ptr(seed3, &42); }
+        if unsafe { jit_fn.call(slab.inputs_ptr(&inputs), null_mut()) } {
+            matches += 1;
+        }
+    }
+
+    matches
+}
+
+#[inline(never)]
+fn benchmark_aot_inline() -> u32 {
     let mut matches = 0;
 
     for x in 0..ITERS {
@@ -40,7 +62,7 @@ fn benchmark_aot_inline<'ctx>() -> u32 {
 }
 
 #[inline(never)]
-fn benchmark_aot_closure<'ctx>(closure: impl Fn(u32) -> bool) -> u32 {
+fn benchmark_aot_closure(closure: impl Fn(u32) -> bool) -> u32 {
     let mut matches = 0;
 
     for x in 0..ITERS {
@@ -55,7 +77,7 @@ fn benchmark_aot_closure<'ctx>(closure: impl Fn(u32) -> bool) -> u32 {
 }
 
 #[inline(never)]
-fn benchmark_aot_capturing_closure<'ctx>(closure: impl Fn(u32) -> bool) -> u32 {
+fn benchmark_aot_capturing_closure(closure: impl Fn(u32) -> bool) -> u32 {
     let mut matches = 0;
 
     for x in 0..ITERS {
@@ -71,22 +93,46 @@ fn benchmark_aot_capturing_closure<'ctx>(closure: impl Fn(u32) -> bool) -> u32 {
 
 fn run() -> Result<(), Box<dyn Error>> {
     let mut jit_ctx = JITContext::new();
-    let mut comp_ctx = jit_ctx.make_compilation_context()?;
-    let mut table = Table::new();
-
-    let seed3_idx = table.add_ptr_hidden_state();
 
-    table.add_variable("x".into(), ValueType::U32)?;
-    table.add_function_3_map("get_wanted_x".into(), get_wanted_x, 3u32, FnSpecCallArg::MappedArgument { param_idx: 0 }, FnSpecCallArg::from_hidden_state(seed3_idx))?;
+    {
+        // x is a variable, written to the Slab before every evaluation
+        let mut comp_ctx = jit_ctx.make_compilation_context()?;
+        let mut table = Table::new();
+
+        let seed3 = table.add_ptr_hidden_state::<u32>();
+
+        table.add_variable("x".into(), ValueType::U32)?;
+        table.add_function_map("get_wanted_x".into(), GET_WANTED_X, [3u32.into(), FnSpecCallArg::MappedArgument { param_idx: 0 }, FnSpecCallArg::from_hidden_state(seed3)])?;
+
+        if let CompiledExpression::Bool { mut slab, jit_fn } = comp_ctx.compile_str("x == get_wanted_x(2)", &table)? {
+            let test_value = slab.handle::<u32>("x")?;
+            let start = Instant::now();
+            let matches = benchmark_jit_slab(&mut slab, jit_fn, test_value, seed3);
+            let secs = Instant::now().duration_since(start).as_secs_f64();
+            println!("             [jit_slab] found {matches} matches in {secs} seconds");
+        } else {
+            panic!("expected a predicate, not any other type of expression");
+        }
+    }
 
-    if let CompiledExpression::Bool { mut slab, jit_fn } = comp_ctx.compile_str("x == get_wanted_x(2)", &table)? {
-        let test_value_idx = slab.get_binding_index(&"x".into()).unwrap();
-        let start = Instant::now();
-        let matches = benchmark_jit(&mut slab, jit_fn, test_value_idx, seed3_idx);
-        let secs = Instant::now().duration_since(start).as_secs_f64();
-        println!("                  [jit] found {matches} matches in {secs} seconds");
-    } else {
-        panic!("expected a predicate, not any other type of expression");
+    {
+        // x is a field of the inputs struct, which is read directly
+        let mut comp_ctx = jit_ctx.make_compilation_context()?;
+        let mut table = Table::new();
+
+        let seed3 = table.add_ptr_hidden_state::<u32>();
+
+        table.add_inputs::<Inputs>()?;
+        table.add_function_map("get_wanted_x".into(), GET_WANTED_X, [3u32.into(), FnSpecCallArg::MappedArgument { param_idx: 0 }, FnSpecCallArg::from_hidden_state(seed3)])?;
+
+        if let CompiledExpression::Bool { mut slab, jit_fn } = comp_ctx.compile_str("x == get_wanted_x(2)", &table)? {
+            let start = Instant::now();
+            let matches = benchmark_jit_inputs(&mut slab, jit_fn, seed3);
+            let secs = Instant::now().duration_since(start).as_secs_f64();
+            println!("           [jit_inputs] found {matches} matches in {secs} seconds");
+        } else {
+            panic!("expected a predicate, not any other type of expression");
+        }
     }
 
     {
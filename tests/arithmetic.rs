@@ -0,0 +1,110 @@
+mod common;
+
+use common::{eval, eval_in};
+use hot_eval::{codegen::arithmetic_mode::{ArithmeticMode, DivByZeroResult}, common::{table::Table, value::Value}};
+
+const SATURATE: ArithmeticMode = ArithmeticMode::Defined { div_by_zero: DivByZeroResult::Saturate };
+
+#[test]
+fn defined_division_by_zero_is_zero() {
+    assert_eq!(eval("7i32 / 0i32"), Value::from(0i32));
+    assert_eq!(eval("7u8 / 0u8"), Value::from(0u8));
+}
+
+#[test]
+fn remainder_has_the_sign_of_the_dividend() {
+    assert_eq!(eval("-7i32 % 3i32"), Value::from(-1i32));
+    assert_eq!(eval("7i32 % -3i32"), Value::from(1i32));
+    assert_eq!(eval("7u8 % 3u8"), Value::from(1u8));
+    assert_eq!(eval("-7.5 % 2.0"), Value::from(-1.5f64));
+}
+
+#[test]
+fn defined_remainder_by_zero_is_dividend() {
+    assert_eq!(eval("7i32 % 0i32"), Value::from(7i32));
+    assert_eq!(eval("-7i64 % 0i64"), Value::from(-7i64));
+    assert_eq!(eval("200u8 % 0u8"), Value::from(200u8));
+}
+
+#[test]
+fn defined_division_by_zero_saturates() {
+    let table = Table::new();
+    assert_eq!(eval_in("7i32 / 0i32", &table, SATURATE).unwrap(), Value::from(i32::MAX));
+    assert_eq!(eval_in("-7i32 / 0i32", &table, SATURATE).unwrap(), Value::from(i32::MIN));
+    assert_eq!(eval_in("0i32 / 0i32", &table, SATURATE).unwrap(), Value::from(0i32));
+    assert_eq!(eval_in("7u16 / 0u16", &table, SATURATE).unwrap(), Value::from(u16::MAX));
+}
+
+#[test]
+fn defined_signed_division_overflow_wraps() {
+    assert_eq!(eval("(-2147483647i32 - 1i32) / -1i32"), Value::from(i32::MIN));
+    assert_eq!(eval("(-2147483647i32 - 1i32) % -1i32"), Value::from(0i32));
+}
+
+#[test]
+fn defined_overflow_wraps() {
+    assert_eq!(eval("255u8 + 1u8"), Value::from(0u8));
+    assert_eq!(eval("0u16 - 1u16"), Value::from(u16::MAX));
+    assert_eq!(eval("2147483647i32 + 1i32"), Value::from(i32::MIN));
+    assert_eq!(eval("16i8 * 16i8"), Value::from(0i8));
+}
+
+#[test]
+fn saturating_operators() {
+    assert_eq!(eval("250u8 +| 10u8"), Value::from(u8::MAX));
+    assert_eq!(eval("5u8 -| 10u8"), Value::from(0u8));
+    assert_eq!(eval("-100i8 -| 100i8"), Value::from(i8::MIN));
+    assert_eq!(eval("100i8 *| -2i8"), Value::from(i8::MIN));
+    assert_eq!(eval("3i32 +| 4i32"), Value::from(7i32));
+}
+
+#[test]
+fn wrapping_operators() {
+    assert_eq!(eval("250u8 +% 10u8"), Value::from(4u8));
+    assert_eq!(eval("5u8 -% 10u8"), Value::from(251u8));
+    assert_eq!(eval("16i8 *% 16i8"), Value::from(0i8));
+}
+
+#[test]
+fn wrapping_operators_never_fail_in_checked_mode() {
+    let table = Table::new();
+    assert_eq!(eval_in("255u8 +% 1u8", &table, ArithmeticMode::Checked).unwrap(), Value::from(0u8));
+    assert_eq!(eval_in("255u8 +| 1u8", &table, ArithmeticMode::Checked).unwrap(), Value::from(255u8));
+}
+
+#[test]
+fn wide_and_pointer_sized_types() {
+    assert_eq!(eval("340282366920938463463374607431768211455u128 / 2u128"), Value::from(u128::MAX / 2));
+    assert_eq!(eval("-170141183460469231731687303715884105727i128 - 1i128"), Value::from(i128::MIN));
+    assert_eq!(eval("18446744073709551615u64 + 1i128"), Value::from(1i128 << 64));
+    assert_eq!(eval("-5isize * 3i32"), Value::from(-15isize));
+    assert_eq!(eval("7usize % 4u8"), Value::from(3usize));
+}
+
+#[test]
+fn unsigned_operands_are_zero_extended() {
+    assert_eq!(eval("200u8 + 1i32"), Value::from(201i32));
+    assert_eq!(eval("4294967295u32 + 0i64"), Value::from(4294967295i64));
+}
+
+#[test]
+fn power_operator() {
+    assert_eq!(eval("2 ** 10"), Value::from(1024i32));
+    assert_eq!(eval("2 ** 3 ** 2"), Value::from(512i32));
+    assert_eq!(eval("-2 ** 2"), Value::from(-4i32));
+    assert_eq!(eval("(-2) ** 3"), Value::from(-8i32));
+    assert_eq!(eval("3u32 ** (10u32 + 10u32)"), Value::from(3u32.wrapping_pow(20)));
+    assert_eq!(eval("2u8 ** (4u8 + 4u8)"), Value::from(0u8));
+    assert_eq!(eval("4 ** 0.5"), Value::from(2.0f64));
+    assert_eq!(eval("2.0 ** 20"), Value::from(1048576.0f64));
+    assert_eq!(eval("1.5f32 ** (1u16 + 1u16)"), Value::from(2.25f32));
+}
+
+#[test]
+fn power_operator_with_negative_exponents() {
+    assert_eq!(eval("2.0 ** -1"), Value::from(0.5f64));
+    assert_eq!(eval("2f32 ** -2i8"), Value::from(0.25f32));
+    assert_eq!(eval("4.0 ** -0.5"), Value::from(0.5f64));
+    assert_eq!(eval("2.0 ** -1 ** 2"), Value::from(0.5f64));
+    assert_eq!(eval("-2.0 ** -1"), Value::from(-0.5f64));
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use common::eval_in;
+use hot_eval::{codegen::arithmetic_mode::ArithmeticMode, common::{eval_error::EvalErrorKind, table::Table, value::Value}};
+
+fn eval_checked(source: &str) -> Result<Value, (EvalErrorKind, usize)> {
+    eval_in(source, &Table::new(), ArithmeticMode::Checked).map_err(|err| (err.kind, err.node))
+}
+
+#[test]
+fn checked_in_range_operations_succeed() {
+    assert_eq!(eval_checked("254u8 + 1u8"), Ok(Value::from(255u8)));
+    assert_eq!(eval_checked("-2147483647i32 - 1i32"), Ok(Value::from(i32::MIN)));
+    assert_eq!(eval_checked("7i32 / 2i32"), Ok(Value::from(3i32)));
+    assert_eq!(eval_checked("-(2u32 - 1u32)"), Ok(Value::from(-1i32)));
+}
+
+#[test]
+fn checked_overflow_reports_failing_node() {
+    // nodes are numbered in post-order, so the root is always the last node
+    assert_eq!(eval_checked("255u8 + 1u8"), Err((EvalErrorKind::Overflow, 2)));
+    assert_eq!(eval_checked("0u16 - 1u16"), Err((EvalErrorKind::Overflow, 2)));
+    assert_eq!(eval_checked("16i8 * 16i8"), Err((EvalErrorKind::Overflow, 2)));
+    assert_eq!(eval_checked("(255u8 + 1u8) == 0u8"), Err((EvalErrorKind::Overflow, 2)));
+}
+
+#[test]
+fn checked_division_by_zero() {
+    assert_eq!(eval_checked("7i32 / 0i32"), Err((EvalErrorKind::DivisionByZero, 2)));
+    assert_eq!(eval_checked("7u64 % 0u64"), Err((EvalErrorKind::DivisionByZero, 2)));
+}
+
+#[test]
+fn checked_signed_division_overflow() {
+    assert_eq!(eval_checked("(-2147483647i32 - 1i32) / -1i32"), Err((EvalErrorKind::Overflow, 6)));
+}
+
+#[test]
+fn checked_negation_overflow() {
+    assert_eq!(eval_checked("-(-127i8 - 1i8)"), Err((EvalErrorKind::Overflow, 4)));
+}
+
+#[test]
+fn checked_negation_of_unsigned_operand() {
+    // the result has the signed type of the same width, so only operands up to
+    // the magnitude of its minimum value fit
+    assert_eq!(eval_checked("-128u8"), Ok(Value::from(-128i8)));
+    assert_eq!(eval_checked("-(127u8 + 1u8)"), Ok(Value::from(-128i8)));
+    assert_eq!(eval_checked("-129u8"), Err((EvalErrorKind::Overflow, 1)));
+    assert_eq!(eval_checked("-(200u8 + 55u8)"), Err((EvalErrorKind::Overflow, 3)));
+    assert_eq!(eval_checked("-18446744073709551615u64"), Err((EvalErrorKind::Overflow, 1)));
+}
+
+#[test]
+fn checked_power_overflow() {
+    assert_eq!(eval_checked("2u8 ** 7u8"), Ok(Value::from(128u8)));
+    assert_eq!(eval_checked("2u8 ** 8u8"), Err((EvalErrorKind::Overflow, 2)));
+    assert_eq!(eval_checked("3i32 ** (10u32 + 10u32)"), Err((EvalErrorKind::Overflow, 4)));
+}
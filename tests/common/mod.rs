@@ -0,0 +1,28 @@
+#![allow(dead_code)]
+
+use std::ptr::null;
+
+use hot_eval::{codegen::{arithmetic_mode::ArithmeticMode, compiled_expression::{CompiledExpression, call_checked}, jit_context::JITContext}, common::{eval_error::EvalError, table::Table, value::Value}};
+
+macro_rules! call_compiled {
+    ($compiled:expr, $($variant:ident),*) => {
+        match $compiled {
+            $(CompiledExpression::$variant { mut slab, jit_fn } => unsafe { call_checked(&jit_fn, &mut slab, null()) }.map(Value::from),)*
+        }
+    };
+}
+
+/// Compiles an expression with the given arithmetic mode and evaluates it once.
+/// Panics if the expression doesn't compile
+pub fn eval_in(source: &str, table: &Table, mode: ArithmeticMode) -> Result<Value, EvalError> {
+    let mut jit_ctx = JITContext::new();
+    let mut comp_ctx = jit_ctx.make_compilation_context().unwrap();
+    comp_ctx.set_arithmetic_mode(mode);
+    let compiled = comp_ctx.compile_str(source, table).unwrap_or_else(|err| panic!("failed to compile {source:?}: {err}"));
+    call_compiled!(compiled, U8, U16, U32, U64, U128, USize, I8, I16, I32, I64, I128, ISize, F32, F64, Bool)
+}
+
+/// Same as [eval_in], with the default arithmetic mode and an empty Table
+pub fn eval(source: &str) -> Value {
+    eval_in(source, &Table::new(), ArithmeticMode::default()).unwrap()
+}
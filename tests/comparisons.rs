@@ -0,0 +1,51 @@
+mod common;
+
+use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
+
+use common::{eval, eval_in};
+use hot_eval::{codegen::arithmetic_mode::ArithmeticMode, common::{table::Table, value::Value}};
+
+#[test]
+fn mixed_sign_comparisons_keep_the_sign() {
+    assert_eq!(eval("255u8 > -1i8"), Value::from(true));
+    assert_eq!(eval("-1i8 < 255u8"), Value::from(true));
+    assert_eq!(eval("-1i8 == 255u8"), Value::from(false));
+    assert_eq!(eval("-1i8 != 255u8"), Value::from(true));
+    assert_eq!(eval("18446744073709551615u64 > -1i64"), Value::from(true));
+    assert_eq!(eval("-1i64 >= 18446744073709551615u64"), Value::from(false));
+    assert_eq!(eval("4294967295u32 > -1i16"), Value::from(true));
+    assert_eq!(eval("-1i64 < 0u8"), Value::from(true));
+}
+
+#[test]
+fn mixed_sign_comparisons_of_equal_values() {
+    assert_eq!(eval("5u32 == 5i8"), Value::from(true));
+    assert_eq!(eval("5i64 <= 5u64"), Value::from(true));
+    assert_eq!(eval("127i8 >= 127u8"), Value::from(true));
+}
+
+#[test]
+fn chained_comparisons() {
+    assert_eq!(eval("1 < 2 < 3"), Value::from(true));
+    assert_eq!(eval("1 < 3 < 2"), Value::from(false));
+    assert_eq!(eval("3 > 2 >= 2 == 2 != 1"), Value::from(true));
+    assert_eq!(eval("0.5 <= 0.5 < 1"), Value::from(true));
+    assert_eq!(eval("-1i8 < 200u8 < 300u16"), Value::from(true));
+    assert_eq!(eval("1 < 2 < 3 && 3 < 2 < 1"), Value::from(false));
+}
+
+#[test]
+fn chained_comparisons_evaluate_operands_once() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let mut table = Table::new();
+    let counter = calls.clone();
+    table.add_closure("middle".into(), move || {
+        counter.fetch_add(1, Ordering::Relaxed);
+        5i32
+    }).unwrap();
+
+    assert_eq!(eval_in("0 < middle() <= 5", &table, ArithmeticMode::default()).unwrap(), Value::from(true));
+    assert_eq!(calls.load(Ordering::Relaxed), 1);
+    assert_eq!(eval_in("0 < middle() < middle()", &table, ArithmeticMode::default()).unwrap(), Value::from(false));
+    assert_eq!(calls.load(Ordering::Relaxed), 3);
+}
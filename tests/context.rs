@@ -0,0 +1,63 @@
+use std::ptr::null;
+
+use hot_eval::{codegen::{compiled_expression::{CompiledExpression, call_checked, eval_with}, jit_context::JITContext}, common::{binding::FnSpecCallArg, table::Table}};
+
+struct Log {
+    calls: Vec<u32>,
+}
+
+fn record(log: &'static mut Log, x: u32) -> u32 {
+    log.calls.push(x);
+    x * 2
+}
+
+#[test]
+fn context_is_passed_to_host_functions() {
+    let mut table = Table::new();
+    table.add_function_map("record".into(), record, [FnSpecCallArg::from_context::<Log>(), FnSpecCallArg::MappedArgument { param_idx: 0 }]).unwrap();
+    let mut jit_ctx = JITContext::new();
+    let mut comp_ctx = jit_ctx.make_compilation_context().unwrap();
+    let CompiledExpression::U32 { mut slab, jit_fn } = comp_ctx.compile_str("record(1u32) + record(2u32)", &table).unwrap() else { panic!("expected a u32 expression") };
+
+    let mut log = Log { calls: Vec::new() };
+    assert_eq!(unsafe { eval_with(&jit_fn, &mut slab, &(), &mut log) }.unwrap(), 6);
+    assert_eq!(unsafe { eval_with(&jit_fn, &mut slab, &(), &mut log) }.unwrap(), 6);
+    assert_eq!(log.calls, [1, 2, 1, 2]);
+}
+
+#[test]
+fn failed_bindings_do_not_set_the_context_type() {
+    let mut table = Table::new();
+    table.add_function("record".into(), |x: u32| x).unwrap();
+    assert!(table.add_function_map("record".into(), record, [FnSpecCallArg::from_context::<Log>(), FnSpecCallArg::MappedArgument { param_idx: 0 }]).is_err());
+    assert!(table.get_context_type().is_none());
+
+    let mut jit_ctx = JITContext::new();
+    let mut comp_ctx = jit_ctx.make_compilation_context().unwrap();
+    let CompiledExpression::U32 { mut slab, jit_fn } = comp_ctx.compile_str("record(1u32)", &table).unwrap() else { panic!("expected a u32 expression") };
+    assert_eq!(unsafe { call_checked(&jit_fn, &mut slab, null()) }.unwrap(), 1);
+}
+
+#[test]
+#[should_panic]
+fn context_must_have_the_table_context_type() {
+    let mut table = Table::new();
+    table.add_function_map("record".into(), record, [FnSpecCallArg::from_context::<Log>(), FnSpecCallArg::MappedArgument { param_idx: 0 }]).unwrap();
+    let mut jit_ctx = JITContext::new();
+    let mut comp_ctx = jit_ctx.make_compilation_context().unwrap();
+    let CompiledExpression::U32 { mut slab, jit_fn } = comp_ctx.compile_str("record(1u32)", &table).unwrap() else { panic!("expected a u32 expression") };
+
+    let _ = unsafe { eval_with(&jit_fn, &mut slab, &(), &mut 0u32) };
+}
+
+#[test]
+#[should_panic(expected = "eval_with")]
+fn expressions_with_a_context_cannot_be_called_without_one() {
+    let mut table = Table::new();
+    table.add_function_map("record".into(), record, [FnSpecCallArg::from_context::<Log>(), FnSpecCallArg::MappedArgument { param_idx: 0 }]).unwrap();
+    let mut jit_ctx = JITContext::new();
+    let mut comp_ctx = jit_ctx.make_compilation_context().unwrap();
+    let CompiledExpression::U32 { mut slab, jit_fn } = comp_ctx.compile_str("record(1u32)", &table).unwrap() else { panic!("expected a u32 expression") };
+
+    let _ = unsafe { call_checked(&jit_fn, &mut slab, null()) };
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use std::{error::Error, fmt};
+
+use common::eval_in;
+use hot_eval::{codegen::arithmetic_mode::ArithmeticMode, common::{eval_error::EvalErrorKind, table::Table, value::Value}};
+
+#[derive(Debug)]
+struct MissingKey(u32);
+
+impl fmt::Display for MissingKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "missing key {}", self.0)
+    }
+}
+
+impl Error for MissingKey {}
+
+fn lookup(key: u32) -> Result<u32, MissingKey> {
+    match key {
+        1 => Ok(10),
+        2 => Ok(20),
+        _ => Err(MissingKey(key)),
+    }
+}
+
+fn checked_half(x: u32) -> Option<u32> {
+    x.is_multiple_of(2).then_some(x / 2)
+}
+
+#[test]
+fn successful_calls_return_their_value() {
+    let mut table = Table::new();
+    table.add_function("lookup".into(), lookup).unwrap();
+    table.add_function("half".into(), checked_half).unwrap();
+    table.add_closure("parse".into(), |x: u32| if x < 10 { Ok(x) } else { Err("too large") }).unwrap();
+    assert_eq!(eval_in("lookup(1u32) + lookup(2u32)", &table, ArithmeticMode::default()).unwrap(), Value::from(30u32));
+    assert_eq!(eval_in("half(8u32)", &table, ArithmeticMode::default()).unwrap(), Value::from(4u32));
+    assert_eq!(eval_in("parse(9u32)", &table, ArithmeticMode::default()).unwrap(), Value::from(9u32));
+}
+
+#[test]
+fn errors_abort_the_evaluation() {
+    let mut table = Table::new();
+    table.add_function("lookup".into(), lookup).unwrap();
+    table.add_function("half".into(), checked_half).unwrap();
+    table.add_closure("parse".into(), |x: u32| if x < 10 { Ok(x) } else { Err("too large") }).unwrap();
+
+    // fallible functions are checked in every mode
+    for mode in [ArithmeticMode::default(), ArithmeticMode::Unchecked, ArithmeticMode::Checked] {
+        let err = eval_in("lookup(1u32) + lookup(3u32)", &table, mode).unwrap_err();
+        assert_eq!((err.kind, err.node), (EvalErrorKind::HostError, 3));
+        assert_eq!(err.host_error.unwrap().to_string(), "missing key 3");
+
+        let err = eval_in("half(half(6u32))", &table, mode).unwrap_err();
+        assert_eq!((err.kind, err.node), (EvalErrorKind::HostError, 2));
+        assert!(err.host_error.is_none());
+
+        let err = eval_in("parse(10u32)", &table, mode).unwrap_err();
+        assert_eq!(err.host_error.unwrap().to_string(), "too large");
+    }
+}
+
+#[test]
+fn errors_short_circuit_later_calls() {
+    let mut table = Table::new();
+    table.add_function("lookup".into(), lookup).unwrap();
+    table.add_closure("unreachable".into(), |x: u32| -> u32 { panic!("called with {x}") }).unwrap();
+
+    let err = eval_in("unreachable(lookup(5u32))", &table, ArithmeticMode::default()).unwrap_err();
+    assert_eq!((err.kind, err.node), (EvalErrorKind::HostError, 1));
+}
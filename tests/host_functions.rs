@@ -0,0 +1,160 @@
+mod common;
+
+use common::eval_in;
+use hot_eval::{codegen::arithmetic_mode::ArithmeticMode, common::{binding::{FnSpecCallArg, GenericFnInstance, GenericParam, TypeConstraint}, error::CommonError, eval_error::EvalErrorKind, table::Table, value::Value, value_type::ValueType}};
+
+#[hot_eval::function]
+fn add_u32(a: u32, b: u32) -> u32 {
+    a + b
+}
+
+#[hot_eval::function]
+fn add_ref(a: u32, b: &u32) -> u32 {
+    a + *b
+}
+
+#[test]
+fn function_attribute_keeps_the_function_callable() {
+    let local = 5;
+    assert_eq!(add_ref(1, &local), 6);
+    assert_eq!(ADD_REF(1, &5), 6);
+}
+
+#[test]
+fn function_attribute_const_can_be_bound() {
+    let mut table = Table::new();
+    table.add_function("add".into(), ADD_U32).unwrap();
+    assert_eq!(eval_in("add(1, add(2u32, 3))", &table, ArithmeticMode::default()).unwrap(), Value::from(6u32));
+}
+
+fn mix(a: u8, b: i16, c: f32, d: u64, e: bool) -> f64 {
+    if e { a as f64 + b as f64 * c as f64 - d as f64 } else { 0.0 }
+}
+
+#[test]
+fn functions_of_any_arity_can_be_bound() {
+    let mut table = Table::new();
+    table.add_function("mix".into(), mix as fn(u8, i16, f32, u64, bool) -> f64).unwrap();
+    assert_eq!(eval_in("mix(1u8, -2i16, 0.5f32, 3u64, true)", &table, ArithmeticMode::default()).unwrap(), Value::from(-3.0f64));
+    assert_eq!(eval_in("mix(1u8, -2i16, 0.5f32, 3u64, false)", &table, ArithmeticMode::default()).unwrap(), Value::from(0.0f64));
+}
+
+#[test]
+fn functions_can_be_bound_without_a_cast() {
+    let mut table = Table::new();
+    table.add_function("mix".into(), mix).unwrap();
+    table.add_function("add_ref".into(), add_ref).unwrap();
+    table.add_function("double".into(), |x: i32| x * 2).unwrap();
+    assert_eq!(eval_in("mix(1u8, -2i16, 0.5f32, 3u64, true)", &table, ArithmeticMode::default()).unwrap(), Value::from(-3.0f64));
+    assert_eq!(eval_in("double(21)", &table, ArithmeticMode::default()).unwrap(), Value::from(42i32));
+
+    let offset = 10i32;
+    let err = table.add_function("add_offset".into(), move |x: i32| x + offset).unwrap_err();
+    assert!(matches!(err, CommonError::UnsupportedFunction { name } if name == "add_offset"));
+}
+
+#[test]
+#[allow(deprecated)]
+fn deprecated_fixed_arity_functions_can_still_be_bound() {
+    let mut table = Table::new();
+    table.add_function_2("add".into(), add_u32 as fn(u32, u32) -> u32).unwrap();
+    table.add_function_2_map("add_five".into(), add_u32 as fn(u32, u32) -> u32, 5u32, FnSpecCallArg::MappedArgument { param_idx: 0 }).unwrap();
+    assert_eq!(eval_in("add(1u32, 2u32)", &table, ArithmeticMode::default()).unwrap(), Value::from(3u32));
+    assert_eq!(eval_in("add_five(2u32)", &table, ArithmeticMode::default()).unwrap(), Value::from(7u32));
+}
+
+#[test]
+fn capturing_closures_can_be_bound() {
+    let offset = 10u32;
+    let mut table = Table::new();
+    table.add_closure("add_offset".into(), move |x: u32| x + offset).unwrap();
+    table.add_closure("scale".into(), |x: f64, y: f64| x * y).unwrap();
+    assert_eq!(eval_in("add_offset(5u32)", &table, ArithmeticMode::default()).unwrap(), Value::from(15u32));
+    assert_eq!(eval_in("scale(2.0, 0.25)", &table, ArithmeticMode::default()).unwrap(), Value::from(0.5f64));
+}
+
+fn div_or_panic(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        panic!("div_or_panic by zero");
+    }
+
+    a / b
+}
+
+#[test]
+fn panics_are_reported_as_errors() {
+    let mut table = Table::new();
+    table.add_function("div".into(), div_or_panic as fn(u32, u32) -> u32).unwrap();
+    table.add_closure("div_closure".into(), |a: u32, b: u32| div_or_panic(a, b)).unwrap();
+
+    for mode in [ArithmeticMode::default(), ArithmeticMode::Unchecked, ArithmeticMode::Checked] {
+        assert_eq!(eval_in("div(6u32, 3u32) + 1", &table, mode).unwrap(), Value::from(3u32));
+
+        let err = eval_in("1 + div(6u32, 0u32)", &table, mode).unwrap_err();
+        assert_eq!((err.kind, err.node), (EvalErrorKind::HostPanic, 3));
+        assert_eq!(err.host_error.unwrap().to_string(), "div_or_panic by zero");
+
+        let err = eval_in("div_closure(6u32, 0u32)", &table, mode).unwrap_err();
+        assert_eq!((err.kind, err.node), (EvalErrorKind::HostPanic, 2));
+    }
+}
+
+fn div_rem(a: u32, b: u32) -> (u32, u32) {
+    (a / b, a % b)
+}
+
+#[test]
+fn multiple_return_values_can_be_selected() {
+    let mut table = Table::new();
+    table.add_function("divmod".into(), div_rem as fn(u32, u32) -> (u32, u32)).unwrap();
+    table.add_closure("sincos".into(), |x: f64| (x.sin(), x.cos())).unwrap();
+    table.add_closure("parts".into(), |x: i64| (x as i8, x as i16, x as i32, x < 0)).unwrap();
+
+    assert_eq!(eval_in("divmod(17u32, 5u32).0", &table, ArithmeticMode::default()).unwrap(), Value::from(3u32));
+    assert_eq!(eval_in("divmod(17u32, 5u32).1", &table, ArithmeticMode::default()).unwrap(), Value::from(2u32));
+    assert_eq!(eval_in("divmod(17u32, 5u32).0 * 5 + divmod(17u32, 5u32).1", &table, ArithmeticMode::default()).unwrap(), Value::from(17u32));
+    assert_eq!(eval_in("sincos(0.0).1", &table, ArithmeticMode::default()).unwrap(), Value::from(1.0f64));
+    assert_eq!(eval_in("parts(-257i64).2", &table, ArithmeticMode::default()).unwrap(), Value::from(-257i32));
+    assert_eq!(eval_in("parts(-257i64).3", &table, ArithmeticMode::default()).unwrap(), Value::from(true));
+}
+
+#[test]
+fn overloads_call_the_closest_match() {
+    let mut table = Table::new();
+    table.add_function("describe".into(), (|_| 32) as fn(i32) -> u8).unwrap();
+    table.add_function("describe".into(), (|_| 64) as fn(f64) -> u8).unwrap();
+    table.add_function("describe".into(), (|_| 128) as fn(u64) -> u8).unwrap();
+
+    assert_eq!(eval_in("describe(-3i16)", &table, ArithmeticMode::default()).unwrap(), Value::from(32u8));
+    assert_eq!(eval_in("describe(5u8)", &table, ArithmeticMode::default()).unwrap(), Value::from(32u8));
+    assert_eq!(eval_in("describe(5u64)", &table, ArithmeticMode::default()).unwrap(), Value::from(128u8));
+    assert_eq!(eval_in("describe(5f32)", &table, ArithmeticMode::default()).unwrap(), Value::from(64u8));
+}
+
+#[test]
+fn generic_functions_call_their_instance() {
+    let mut table = Table::new();
+    table.add_generic_function("double".into(), TypeConstraint::Numeric, &[GenericParam::Generic], |types| match types[0] {
+        ValueType::U8 => Ok(GenericFnInstance::from_function((|x: u8| x.wrapping_mul(2)) as fn(u8) -> u8)),
+        ValueType::I32 => Ok(GenericFnInstance::from_function((|x: i32| x * 2) as fn(i32) -> i32)),
+        ValueType::F64 => Ok(GenericFnInstance::from_function((|x: f64| x * 2.0) as fn(f64) -> f64)),
+        value_type => Err(format!("{value_type:?} isn't supported")),
+    }).unwrap();
+
+    assert_eq!(eval_in("double(200u8)", &table, ArithmeticMode::default()).unwrap(), Value::from(144u8));
+    assert_eq!(eval_in("double(-21)", &table, ArithmeticMode::default()).unwrap(), Value::from(-42i32));
+    assert_eq!(eval_in("double(0.25)", &table, ArithmeticMode::default()).unwrap(), Value::from(0.5f64));
+    assert_eq!(eval_in("double(double(3u8))", &table, ArithmeticMode::default()).unwrap(), Value::from(12u8));
+}
+
+#[test]
+fn method_calls_resolve_to_functions() {
+    let mut table = Table::new();
+    table.add_function("divmod".into(), div_rem as fn(u32, u32) -> (u32, u32)).unwrap();
+    table.add_closure("clamp_to".into(), |x: i32, lo: i32, hi: i32| x.clamp(lo, hi)).unwrap();
+
+    assert_eq!(eval_in("(-7).clamp_to(0, 5)", &table, ArithmeticMode::default()).unwrap(), Value::from(0i32));
+    assert_eq!(eval_in("(17u32).divmod(5u32).1", &table, ArithmeticMode::default()).unwrap(), Value::from(2u32));
+    assert_eq!(eval_in("(2 * 4).clamp_to(0, 5).clamp_to(6, 9)", &table, ArithmeticMode::default()).unwrap(), Value::from(6i32));
+    assert_eq!(eval_in("(-2.5).abs().max(1.0)", &table, ArithmeticMode::default()).unwrap(), Value::from(2.5f64));
+}
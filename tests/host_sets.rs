@@ -0,0 +1,118 @@
+mod common;
+
+use std::{ptr::null, sync::Arc};
+
+use common::eval_in;
+use hot_eval::{codegen::{arithmetic_mode::ArithmeticMode, compiled_expression::{CompiledExpression, call_checked}, jit_context::JITContext}, common::{error::CommonError, host_set::HostSetData, table::Table, value::Value, value_type::ValueType}};
+
+fn eval_set(table: &Table, source: &str, value: i32) -> bool {
+    match eval_in(&format!("i32v({value}) in {source}"), table, ArithmeticMode::default()).unwrap() {
+        Value::Bool { inner } => inner,
+        value => panic!("expected a bool, got {value:?}"),
+    }
+}
+
+#[test]
+fn constant_sets() {
+    let mut table = Table::new();
+    table.add_closure("i32v".into(), |x: i32| x).unwrap();
+    table.add_set("small".into(), ValueType::I32, [1i32, 5, 9, 60]).unwrap();
+    table.add_set("sparse".into(), ValueType::I32, [0i32, 100, 1000, -5000]).unwrap();
+    table.add_set("many".into(), ValueType::I32, (-10..10).map(|i: i32| i * 100)).unwrap();
+    table.add_set("empty".into(), ValueType::I32, [0i32; 0]).unwrap();
+
+    for value in [1, 5, 9, 60] {
+        assert!(eval_set(&table, "small", value), "{value}");
+    }
+    for value in [0, -1, 7, 61, 65] {
+        assert!(!eval_set(&table, "small", value), "{value}");
+    }
+
+    for value in [0, 100, 1000, -5000] {
+        assert!(eval_set(&table, "sparse", value), "{value}");
+    }
+    for value in [1, 999, 5000] {
+        assert!(!eval_set(&table, "sparse", value), "{value}");
+    }
+
+    for value in [-1000, 0, 900] {
+        assert!(eval_set(&table, "many", value), "{value}");
+    }
+    for value in [-1100, 50, 1000] {
+        assert!(!eval_set(&table, "many", value), "{value}");
+    }
+
+    assert!(!eval_set(&table, "empty", 0));
+}
+
+#[test]
+fn values_are_cast_to_the_set_type() {
+    let mut table = Table::new();
+    table.add_closure("u8v".into(), |x: u8| x).unwrap();
+    table.add_closure("i32v".into(), |x: i32| x).unwrap();
+    table.add_set("wide".into(), ValueType::U16, [1u16, 300]).unwrap();
+    assert_eq!(eval_in("u8v(44) in wide", &table, ArithmeticMode::default()).unwrap(), Value::from(false));
+    assert_eq!(eval_in("u8v(1) in wide", &table, ArithmeticMode::default()).unwrap(), Value::from(true));
+    assert_eq!(eval_in("300 in wide", &table, ArithmeticMode::default()).unwrap(), Value::from(true));
+
+    let mut jit_ctx = JITContext::new();
+    let mut comp_ctx = jit_ctx.make_compilation_context().unwrap();
+    assert!(comp_ctx.compile_str("i32v(1) in wide", &table).is_err());
+}
+
+#[test]
+fn set_data_is_validated() {
+    assert!(matches!(HostSetData::new("s", ValueType::U128, [1u128]), Err(CommonError::SetBadType { .. })));
+    assert!(matches!(HostSetData::new("s", ValueType::F64, [1.0f64]), Err(CommonError::SetBadType { .. })));
+    assert!(matches!(HostSetData::new("s", ValueType::U8, [1u16]), Err(CommonError::SetValueBadType { .. })));
+
+    let data = HostSetData::new("s", ValueType::I16, [3i16, -1, 3, 2]).unwrap();
+    assert_eq!(data.len(), 3);
+    assert_eq!(data.iter_keys().collect::<Vec<_>>(), [-1, 2, 3]);
+}
+
+#[test]
+fn updatable_sets_can_be_replaced() {
+    let mut table = Table::new();
+    table.add_closure("u32v".into(), |x: u32| x).unwrap();
+    let handle = table.add_updatable_set("ids".into(), ValueType::U32, [1u32, 2, 3]).unwrap();
+
+    let mut jit_ctx = JITContext::new();
+    let mut comp_ctx = jit_ctx.make_compilation_context().unwrap();
+    let CompiledExpression::Bool { mut slab, jit_fn } = comp_ctx.compile_str("u32v(1000) in ids", &table).unwrap() else {
+        panic!("expected a bool expression")
+    };
+
+    assert!(!unsafe { call_checked(&jit_fn, &mut slab, null()) }.unwrap());
+    slab.update_set(handle, [1000u32]).unwrap();
+    assert!(unsafe { call_checked(&jit_fn, &mut slab, null()) }.unwrap());
+    slab.update_set(handle, [0u32; 0]).unwrap();
+    assert!(!unsafe { call_checked(&jit_fn, &mut slab, null()) }.unwrap());
+
+    let shared = Arc::new(HostSetData::new("ids", ValueType::U32, (0..100).map(|i: u32| i * 100)).unwrap());
+    slab.set_set_data(handle, shared.clone()).unwrap();
+    assert!(unsafe { call_checked(&jit_fn, &mut slab, null()) }.unwrap());
+
+    assert!(matches!(slab.update_set(handle, [1u8]), Err(CommonError::SetValueBadType { .. })));
+    let wrong_type = Arc::new(HostSetData::new("ids", ValueType::U8, [1u8]).unwrap());
+    assert!(matches!(slab.set_set_data(handle, wrong_type), Err(CommonError::SetValueBadType { .. })));
+}
+
+#[test]
+fn set_handles_belong_to_their_table() {
+    let mut table = Table::new();
+    table.add_updatable_set("ids".into(), ValueType::U32, [1u32]).unwrap();
+    let mut other_table = Table::new();
+    let other_handle = other_table.add_updatable_set("ids".into(), ValueType::U32, [1u32]).unwrap();
+
+    let mut jit_ctx = JITContext::new();
+    let mut comp_ctx = jit_ctx.make_compilation_context().unwrap();
+    let CompiledExpression::Bool { mut slab, jit_fn } = comp_ctx.compile_str("1u32 in ids", &table).unwrap() else {
+        panic!("expected a bool expression")
+    };
+
+    assert!(matches!(slab.update_set(other_handle, [2u32]), Err(CommonError::SetHandleMismatch)));
+    let data = Arc::new(HostSetData::new("ids", ValueType::U32, [2u32]).unwrap());
+    assert!(matches!(slab.set_set_data(other_handle, data), Err(CommonError::SetHandleMismatch)));
+    assert!(unsafe { call_checked(&jit_fn, &mut slab, null()) }.unwrap());
+}
@@ -0,0 +1,72 @@
+
+use hot_eval::{HotEvalInputs, codegen::{compiled_expression::{CompiledExpression, eval}, jit_context::JITContext}, common::{binding::Binding, table::Table}};
+
+#[derive(HotEvalInputs)]
+#[repr(C)]
+struct Inputs {
+    a: u8,
+    b: i64,
+    #[hot_eval(rename = "c")]
+    renamed: f64,
+    #[hot_eval(skip)]
+    _skipped: u32,
+}
+
+#[test]
+fn derived_inputs_add_fields_as_bindings() {
+    let mut table = Table::new();
+    table.add_inputs::<Inputs>().unwrap();
+
+    assert!(matches!(table.get_binding(&"a".into()), Some(Binding::Input { .. })));
+    assert!(matches!(table.get_binding(&"c".into()), Some(Binding::Input { .. })));
+    assert!(table.get_binding(&"renamed".into()).is_none());
+    assert!(table.get_binding(&"_skipped".into()).is_none());
+    assert!(table.add_inputs::<Inputs>().is_err());
+}
+
+#[test]
+fn compiled_expressions_read_inputs() {
+    let mut table = Table::new();
+    table.add_inputs::<Inputs>().unwrap();
+
+    let mut jit_ctx = JITContext::new();
+    let mut comp_ctx = jit_ctx.make_compilation_context().unwrap();
+    let CompiledExpression::F64 { mut slab, jit_fn } = comp_ctx.compile_str("(b - a) * c", &table).unwrap() else {
+        panic!("expected a f64 expression");
+    };
+
+    for (a, b, c) in [(1, 3, 0.5), (200, -5, 2.0)] {
+        let inputs = Inputs { a, b, renamed: c, _skipped: 0 };
+        let result = unsafe { eval(&jit_fn, &mut slab, &inputs) }.unwrap();
+        assert_eq!(result, (b - a as i64) as f64 * c);
+    }
+}
+
+
+#[test]
+#[should_panic]
+fn inputs_must_have_the_table_inputs_type() {
+    let mut table = Table::new();
+    table.add_inputs::<Inputs>().unwrap();
+
+    let mut jit_ctx = JITContext::new();
+    let mut comp_ctx = jit_ctx.make_compilation_context().unwrap();
+    let CompiledExpression::U8 { mut slab, jit_fn } = comp_ctx.compile_str("a", &table).unwrap() else {
+        panic!("expected a u8 expression");
+    };
+
+    let _ = unsafe { eval(&jit_fn, &mut slab, &()) };
+}
+
+#[test]
+fn tables_without_inputs_take_unit_inputs() {
+    let table = Table::new();
+
+    let mut jit_ctx = JITContext::new();
+    let mut comp_ctx = jit_ctx.make_compilation_context().unwrap();
+    let CompiledExpression::U8 { mut slab, jit_fn } = comp_ctx.compile_str("1u8 + 2u8", &table).unwrap() else {
+        panic!("expected a u8 expression");
+    };
+
+    assert_eq!(unsafe { eval(&jit_fn, &mut slab, &()) }.unwrap(), 3);
+}
\ No newline at end of file
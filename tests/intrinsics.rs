@@ -0,0 +1,64 @@
+mod common;
+
+use common::{eval, eval_in};
+use hot_eval::{codegen::arithmetic_mode::ArithmeticMode, common::{eval_error::EvalErrorKind, table::Table, value::Value}};
+
+#[test]
+fn integer_intrinsics() {
+    assert_eq!(eval("abs(-5)"), Value::from(5i32));
+    assert_eq!(eval("abs(-127i8 - 1i8)"), Value::from(-128i8));
+    assert_eq!(eval("abs(200u8)"), Value::from(200u8));
+    assert_eq!(eval("min(-1, 2)"), Value::from(-1i32));
+    assert_eq!(eval("max(200u8, 100u8)"), Value::from(200u8));
+    assert_eq!(eval("min(1u64, 2u8)"), Value::from(1u64));
+    assert_eq!(eval("clamp(-20i16, -10i16, 10i16)"), Value::from(-10i16));
+    assert_eq!(eval("clamp(7u32, 0u32, 5u32)"), Value::from(5u32));
+    assert_eq!(eval("rem_euclid(-7, 3)"), Value::from(2i32));
+    assert_eq!(eval("rem_euclid(-7, -3)"), Value::from(2i32));
+    assert_eq!(eval("rem_euclid(7, -3)"), Value::from(1i32));
+    assert_eq!(eval("rem_euclid(7u8, 3u8)"), Value::from(1u8));
+}
+
+#[test]
+fn float_intrinsics() {
+    assert_eq!(eval("sqrt(16.0)"), Value::from(4.0f64));
+    assert_eq!(eval("sqrt(2.25f32)"), Value::from(1.5f32));
+    assert_eq!(eval("abs(-2.5)"), Value::from(2.5f64));
+    assert_eq!(eval("min(1.5, -0.5)"), Value::from(-0.5f64));
+    assert_eq!(eval("max(1.5, -0.5)"), Value::from(1.5f64));
+    assert_eq!(eval("clamp(1.5, 0.0, 1.0)"), Value::from(1.0f64));
+    assert_eq!(eval("floor(-1.5)"), Value::from(-2.0f64));
+    assert_eq!(eval("ceil(-1.5)"), Value::from(-1.0f64));
+    assert_eq!(eval("round(2.5)"), Value::from(3.0f64));
+    assert_eq!(eval("round(-2.5)"), Value::from(-3.0f64));
+    assert_eq!(eval("trunc(-2.7)"), Value::from(-2.0f64));
+    assert_eq!(eval("fma(2.0, 3.0, 1.0)"), Value::from(7.0f64));
+    assert_eq!(eval("pow(2.0, 10.0)"), Value::from(1024.0f64));
+    assert_eq!(eval("exp(0.0)"), Value::from(1.0f64));
+    assert_eq!(eval("log(1.0)"), Value::from(0.0f64));
+    assert_eq!(eval("sin(0.0)"), Value::from(0.0f64));
+    assert_eq!(eval("cos(0.0)"), Value::from(1.0f64));
+    assert_eq!(eval("copysign(2.0, -0.0)"), Value::from(-2.0f64));
+    assert_eq!(eval("rem_euclid(-7.5, 2.0)"), Value::from(0.5f64));
+}
+
+#[test]
+fn intrinsic_arguments_are_widened() {
+    assert_eq!(eval("max(1u8, 300u16)"), Value::from(300u16));
+    assert_eq!(eval("sqrt(16)"), Value::from(4.0f64));
+    assert_eq!(eval("min(2, 1.5f32)"), Value::from(1.5f32));
+}
+
+#[test]
+fn checked_abs_reports_overflow() {
+    let err = eval_in("abs(-127i8 - 1i8)", &Table::new(), ArithmeticMode::Checked).unwrap_err();
+    assert_eq!((err.kind, err.node), (EvalErrorKind::Overflow, 4));
+    assert_eq!(eval_in("abs(-127i8)", &Table::new(), ArithmeticMode::Checked).unwrap(), Value::from(127i8));
+}
+
+#[test]
+fn bindings_take_precedence_over_intrinsics() {
+    let mut table = Table::new();
+    table.add_closure("abs".into(), |x: i32| x + 1).unwrap();
+    assert_eq!(eval_in("abs(-5)", &table, ArithmeticMode::default()).unwrap(), Value::from(-4i32));
+}
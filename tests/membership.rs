@@ -0,0 +1,91 @@
+mod common;
+
+use common::{eval, eval_in};
+use hot_eval::{codegen::arithmetic_mode::ArithmeticMode, common::{table::Table, value::Value}};
+
+/// Evaluates with identity functions, so that the value being tested isn't a
+/// constant
+fn eval_opaque(source: &str) -> Value {
+    let mut table = Table::new();
+    table.add_closure("u8v".into(), |x: u8| x).unwrap();
+    table.add_closure("i8v".into(), |x: i8| x).unwrap();
+    table.add_closure("i32v".into(), |x: i32| x).unwrap();
+    table.add_closure("f64v".into(), |x: f64| x).unwrap();
+    eval_in(source, &table, ArithmeticMode::default()).unwrap()
+}
+
+#[test]
+fn list_membership() {
+    assert_eq!(eval("3 in [1, 2, 3]"), Value::from(true));
+    assert_eq!(eval("4 in [1, 2, 3]"), Value::from(false));
+    assert_eq!(eval_opaque("f64v(2.5) in [1.5, 2.5]"), Value::from(true));
+    assert_eq!(eval_opaque("f64v(2) in [1.5, 2.5]"), Value::from(false));
+    assert_eq!(eval_opaque("i32v(2) in [i32v(1), i32v(2)]"), Value::from(true));
+    assert_eq!(eval_opaque("i32v(3) in [i32v(1), i32v(2)]"), Value::from(false));
+}
+
+#[test]
+fn small_spans_use_a_bitset() {
+    for (value, expected) in [(1, true), (5, true), (60, true), (7, false), (0, false), (-1, false), (61, false), (65, false)] {
+        assert_eq!(eval_opaque(&format!("i32v({value}) in [1, 5, 9, 60]")), Value::from(expected), "{value}");
+    }
+}
+
+#[test]
+fn large_spans_use_a_switch() {
+    for (value, expected) in [(1000, true), (-5000, true), (0, true), (999, false), (5000, false)] {
+        assert_eq!(eval_opaque(&format!("i32v({value}) in [0, 100, 1000, -5000]")), Value::from(expected), "{value}");
+    }
+}
+
+#[test]
+fn many_items_use_a_binary_search() {
+    let items = (-10..10).map(|i| (i * 100).to_string()).collect::<Vec<_>>().join(", ");
+    for (value, expected) in [(-1000, true), (0, true), (900, true), (-1100, false), (50, false), (1000, false)] {
+        assert_eq!(eval_opaque(&format!("i32v({value}) in [{items}]")), Value::from(expected), "{value}");
+    }
+}
+
+#[test]
+fn negative_literals_are_constants() {
+    assert_eq!(eval_opaque("i32v(-3) in [-1, -2, -3]"), Value::from(true));
+    assert_eq!(eval_opaque("i32v(3) in [-1, -2, -3]"), Value::from(false));
+    assert_eq!(eval_opaque("i32v(-1000) in [-1, 1000, -1000]"), Value::from(true));
+    assert_eq!(eval_opaque("i8v(-127 - 1) in [-128u8]"), Value::from(true));
+    assert_eq!(eval_opaque("i8v(-1) in [-200u8, -1i8]"), Value::from(true));
+}
+
+#[test]
+fn mixed_sign_lists_keep_the_sign() {
+    assert_eq!(eval_opaque("i8v(-1) in [255u8]"), Value::from(false));
+    assert_eq!(eval_opaque("u8v(255) in [-1i8]"), Value::from(false));
+    assert_eq!(eval_opaque("u8v(200) in [-1i8, 200u16]"), Value::from(true));
+    assert_eq!(eval_opaque("u8v(44) in [300u16]"), Value::from(false));
+    assert_eq!(eval_opaque("i8v(-1) in [u8v(255), i8v(-1)]"), Value::from(true));
+    assert_eq!(eval_opaque("i8v(-1) in [u8v(255)]"), Value::from(false));
+}
+
+#[test]
+fn range_membership() {
+    assert_eq!(eval_opaque("i32v(5) in 1..5"), Value::from(false));
+    assert_eq!(eval_opaque("i32v(5) in 1..=5"), Value::from(true));
+    assert_eq!(eval_opaque("i32v(1) in 1..5"), Value::from(true));
+    assert_eq!(eval_opaque("i32v(0) in 1..5"), Value::from(false));
+    assert_eq!(eval_opaque("i32v(-3) in -5..-1"), Value::from(true));
+    assert_eq!(eval_opaque("i32v(5) in 5..5"), Value::from(false));
+    assert_eq!(eval_opaque("f64v(0.5) in 0..1"), Value::from(true));
+    assert_eq!(eval_opaque("i32v(3) in i32v(1)..i32v(4)"), Value::from(true));
+    assert_eq!(eval_opaque("i32v(4) in i32v(1)..i32v(4)"), Value::from(false));
+}
+
+#[test]
+fn mixed_sign_ranges_keep_the_sign() {
+    assert_eq!(eval_opaque("u8v(200) in -1i8..=127i8"), Value::from(false));
+    assert_eq!(eval_opaque("u8v(0) in -1i8..=127i8"), Value::from(true));
+    assert_eq!(eval_opaque("i8v(-1) in 0u8..=255u8"), Value::from(false));
+    assert_eq!(eval_opaque("u8v(255) in 0u16..256u16"), Value::from(true));
+    assert_eq!(eval_opaque("i8v(-127 - 1) in -200i16..0i16"), Value::from(true));
+    assert_eq!(eval_opaque("u8v(5) in 300u16..400u16"), Value::from(false));
+    assert_eq!(eval_opaque("i8v(-1) in u8v(0)..u8v(10)"), Value::from(false));
+    assert_eq!(eval_opaque("i8v(3) in u8v(0)..u8v(10)"), Value::from(true));
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use common::eval_in;
+use hot_eval::{codegen::arithmetic_mode::ArithmeticMode, common::{binding::ParamDef, table::Table, value::Value}};
+
+fn window(x: i32, size: i32, offset: i32) -> i32 {
+    (x - offset).div_euclid(size)
+}
+
+fn param(name: &str, default: Option<Value>) -> ParamDef {
+    ParamDef { name: name.into(), default }
+}
+
+#[test]
+fn arguments_can_be_named_in_any_order() {
+    let mut table = Table::new();
+    table.add_function("window".into(), window).unwrap();
+    table.set_param_defs("window", &[param("x", None), param("size", Some(10i32.into())), param("offset", Some(0i32.into()))]).unwrap();
+    assert_eq!(eval_in("window(25, 10, 5)", &table, ArithmeticMode::default()).unwrap(), Value::from(2i32));
+    assert_eq!(eval_in("window(x: 25, size: 10, offset: 5)", &table, ArithmeticMode::default()).unwrap(), Value::from(2i32));
+    assert_eq!(eval_in("window(offset: 5, x: 25, size: 10)", &table, ArithmeticMode::default()).unwrap(), Value::from(2i32));
+    assert_eq!(eval_in("window(25, offset: 5, size: 10)", &table, ArithmeticMode::default()).unwrap(), Value::from(2i32));
+}
+
+#[test]
+fn missing_arguments_use_their_defaults() {
+    let mut table = Table::new();
+    table.add_function("window".into(), window).unwrap();
+    table.set_param_defs("window", &[param("x", None), param("size", Some(10i32.into())), param("offset", Some(0i32.into()))]).unwrap();
+    assert_eq!(eval_in("window(25)", &table, ArithmeticMode::default()).unwrap(), Value::from(2i32));
+    assert_eq!(eval_in("window(25, 4)", &table, ArithmeticMode::default()).unwrap(), Value::from(6i32));
+    assert_eq!(eval_in("window(25, offset: 6)", &table, ArithmeticMode::default()).unwrap(), Value::from(1i32));
+    assert_eq!(eval_in("window(offset: -5, x: 25)", &table, ArithmeticMode::default()).unwrap(), Value::from(3i32));
+}
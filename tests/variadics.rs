@@ -0,0 +1,48 @@
+mod common;
+
+use common::eval_in;
+use hot_eval::{codegen::arithmetic_mode::ArithmeticMode, common::{binding::FnSpecCallArg, error::CommonError, table::Table, value::Value}};
+
+fn sum(values: *const u32, len: usize) -> u32 {
+    // SAFETY: the compiled code passes a pointer to len values
+    unsafe { std::slice::from_raw_parts(values, len) }.iter().sum()
+}
+
+fn weighted_sum(weight: f64, values: *const f64, len: usize) -> f64 {
+    // SAFETY: the compiled code passes a pointer to len values
+    unsafe { std::slice::from_raw_parts(values, len) }.iter().map(|value| value * weight).sum()
+}
+
+#[test]
+fn variadic_arguments_are_passed_as_a_slice() {
+    let mut table = Table::new();
+    table.add_function_map("sum".into(), sum, [FnSpecCallArg::from_variadic::<u32>(), FnSpecCallArg::VariadicLength]).unwrap();
+    assert_eq!(eval_in("sum()", &table, ArithmeticMode::default()).unwrap(), Value::from(0u32));
+    assert_eq!(eval_in("sum(1u32)", &table, ArithmeticMode::default()).unwrap(), Value::from(1u32));
+    assert_eq!(eval_in("sum(1u32, 2u8, 3, 4u16)", &table, ArithmeticMode::default()).unwrap(), Value::from(10u32));
+    assert_eq!(eval_in("sum(sum(1u32, 2u32), sum())", &table, ArithmeticMode::default()).unwrap(), Value::from(3u32));
+}
+
+#[test]
+fn variadic_arguments_follow_the_fixed_ones() {
+    let mut table = Table::new();
+    table.add_function_map("weighted_sum".into(), weighted_sum, [FnSpecCallArg::MappedArgument { param_idx: 0 }, FnSpecCallArg::from_variadic::<f64>(), FnSpecCallArg::VariadicLength]).unwrap();
+    assert_eq!(eval_in("weighted_sum(0.5)", &table, ArithmeticMode::default()).unwrap(), Value::from(0.0f64));
+    assert_eq!(eval_in("weighted_sum(0.5, 1.0, 2, 3f32)", &table, ArithmeticMode::default()).unwrap(), Value::from(3.0f64));
+}
+
+#[test]
+fn variadic_arguments_must_be_declared_correctly() {
+    let mut table = Table::new();
+    let sum = sum as fn(*const u32, usize) -> u32;
+
+    let err = table.add_function_map("sum".into(), sum, [FnSpecCallArg::from_variadic::<i32>(), FnSpecCallArg::VariadicLength]).unwrap_err();
+    assert!(matches!(err, CommonError::FuncSpecArgBadPointee { expected: "i32", got: Some("u32") }));
+
+    let err = table.add_function_map("sum".into(), sum, [FnSpecCallArg::from_variadic::<u32>(), FnSpecCallArg::from_variadic::<u32>()]).unwrap_err();
+    assert!(matches!(err, CommonError::FuncSpecArgBadVariadic));
+
+    let weighted_sum = weighted_sum as fn(f64, *const f64, usize) -> f64;
+    let err = table.add_function_map("weighted_sum".into(), weighted_sum, [FnSpecCallArg::MappedArgument { param_idx: 0 }, FnSpecCallArg::VariadicLength, FnSpecCallArg::from_variadic::<f64>()]).unwrap_err();
+    assert!(matches!(err, CommonError::FuncSpecArgBadVariadic));
+}